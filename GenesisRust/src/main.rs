@@ -1,15 +1,34 @@
 extern crate common;
 extern crate gen_core;
 use common::address::Address;
+use common::node_config::NodeConfig;
 use gen_core::vm::gen_vm::GenVM;
 use gen_core::action::Action;
 
+use std::env;
+use std::process;
+
 fn main() {
+    match NodeConfig::load(env::args().skip(1)) {
+        Ok(config) => config.install(),
+        Err(e) => {
+            eprintln!("invalid configuration: {}", e);
+            process::exit(2);
+        }
+    }
+
+    let addr = match Address::load() {
+        Ok(addr) => addr,
+        Err(e) => {
+            eprintln!("can not load the node identity: {}", e);
+            process::exit(2);
+        }
+    };
     let mut test_action = Action {
         balance: 100,
-        addr: Address::load().unwrap()
+        addr: addr.clone()
     };
-    let test_addr = Address::load().unwrap();
+    let test_addr = addr;
     let mut vm = GenVM::new(&test_action, test_addr).unwrap();
 
     vm.launch(&mut test_action).unwrap();
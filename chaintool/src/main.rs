@@ -0,0 +1,87 @@
+//! Command line helpers for inspecting chain data.
+//!
+//! **Usage**
+//! - `chaintool decode [--json] <hex>` pretty-print an encoded RLP given as hex
+//! - `chaintool decode [--json] --file <path>` pretty-print a file holding raw encoded RLP
+//! - `chaintool encode <json>` / `chaintool encode --file <path>` encode annotated JSON, print hex
+
+extern crate rlp;
+
+use rlp::decoder::Decoder;
+use rlp::encoder::encode_to_vec;
+use rlp::inspect::{from_hex, from_json, pretty, to_hex, to_json};
+use rlp::serde_json;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+const USAGE: &'static str = "\
+usage:
+    chaintool decode [--json] <hex>
+    chaintool decode [--json] --file <path>
+    chaintool encode <json>
+    chaintool encode --file <path>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|cmd| cmd.as_str()) {
+        Some("decode") => decode(&args[1..]),
+        Some("encode") => encode(&args[1..]),
+        _ => Err(USAGE.to_string())
+    };
+
+    match result {
+        Ok(out) => println!("{}", out),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Input of a command, either the argument itself or the content of `--file <path>`
+fn read_input(args: &[String]) -> Result<Option<Vec<u8>>, String> {
+    match args {
+        [ref flag, ref path] if flag == "--file" => {
+            let mut content: Vec<u8> = vec![];
+            File::open(path)
+                .and_then(|mut file| file.read_to_end(&mut content))
+                .map_err(|e| format!("can not read {}: {}", path, e))?;
+            Ok(Some(content))
+        },
+        [ref arg] if !arg.starts_with("--") => Ok(None),
+        _ => Err(USAGE.to_string())
+    }
+}
+
+fn decode(args: &[String]) -> Result<String, String> {
+    let (json, args) = match args.first() {
+        Some(flag) if flag == "--json" => (true, &args[1..]),
+        _ => (false, args)
+    };
+    let input = match read_input(args)? {
+        Some(content) => content,
+        None => from_hex(&args[0]).map_err(|e| format!("invalid hex: {}", e))?
+    };
+
+    if json {
+        let rlp = Decoder::decode_strict(&input).map_err(|e| format!("invalid rlp: {}", e))?;
+        serde_json::to_string_pretty(&to_json(&rlp)).map_err(|e| e.to_string())
+    } else {
+        pretty(&input)
+            .map(|text| text.trim_end().to_string())
+            .map_err(|e| format!("invalid rlp: {}", e))
+    }
+}
+
+fn encode(args: &[String]) -> Result<String, String> {
+    let input = match read_input(args)? {
+        Some(content) => content,
+        None => args[0].clone().into_bytes()
+    };
+    let json: serde_json::Value = serde_json::from_slice(&input).map_err(|e| format!("invalid json: {}", e))?;
+    let rlp = from_json(&json).map_err(|e| format!("unsupported json: {}", e))?;
+    Ok(to_hex(&encode_to_vec(&rlp)))
+}
@@ -1,66 +1,223 @@
-use std::string::String;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
 
 use rlp::RLPSerialize;
 use rlp::types::*;
+use hash::{Hash, HashAlgorithm, HASH_LEN};
 use key::*;
+use keystore::{KeyStore, KeyStoreError};
+use node_config::node_config;
 
 use rust_base58::{ToBase58, FromBase58};
 
-/// Common key operations
-#[derive(Debug, Clone, Eq, PartialEq, PartialOrd)]
+/// Raw address lenth
+pub const ADDRESS_LEN: usize = 32usize;
+
+/// Raw address with its scheme tag
+pub const TAGGED_ADDRESS_LEN: usize = ADDRESS_LEN + 1;
+
+/// Lenth of the checksum appended to the textual form
+pub const ADDRESS_CHECKSUM_LEN: usize = 4usize;
+
+/// Version byte of addresses on the main network
+pub const MAINNET_ADDRESS_VERSION: u8 = 0x26u8;
+
+/// Version byte of addresses on test networks
+pub const TESTNET_ADDRESS_VERSION: u8 = 0x6fu8;
+
+lazy_static! {
+    /// Version byte expected when parsing addresses, set it once at startup
+    static ref ADDRESS_VERSION: RwLock<u8> = RwLock::new(MAINNET_ADDRESS_VERSION);
+}
+
+pub fn address_version() -> u8 {
+    *ADDRESS_VERSION.read().unwrap()
+}
+
+pub fn set_address_version(version: u8) {
+    *ADDRESS_VERSION.write().unwrap() = version;
+}
+
+/// Reasons a textual address is refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// not valid base58
+    InvalidEncoding,
+    /// decoded to the wrong number of bytes
+    InvalidLength(usize),
+    /// most likely a typo
+    InvalidChecksum,
+    /// valid address of another network
+    WrongVersion { expected: u8, found: u8 },
+    /// signature scheme tag this node does not know
+    UnknownScheme(u8)
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AddressError::InvalidEncoding => write!(f, "address is not valid base58"),
+            AddressError::InvalidLength(len) => write!(f, "address decodes to {} bytes", len),
+            AddressError::InvalidChecksum => write!(f, "address checksum mismatch"),
+            AddressError::WrongVersion { expected, found } => {
+                write!(f, "address version 0x{:02x} does not match network version 0x{:02x}", found, expected)
+            },
+            AddressError::UnknownScheme(tag) => write!(f, "unknown signature scheme 0x{:02x}", tag)
+        }
+    }
+}
+
+/// # Address
+/// **Usage**
+/// - 32 raw bytes tagged with the [SchemeId] of the account key, see [SignatureScheme]
+///   for what the bytes are in each scheme
+/// - textual form is base58 of `version || scheme || bytes || checksum`, the checksum being
+///   the first 4 bytes of a double SHA-256 over everything before it
+/// - parsing with [FromStr] checks the checksum and the network version of [address_version]
+/// ## Examples
+/// ```
+/// use common::address::Address;
+///
+/// let addr = Address::from([7u8; 32]);
+/// let text = addr.to_string();
+/// assert_eq!(text.parse::<Address>(), Ok(addr));
+/// ```
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub struct Address {
-    pub text: String
+    scheme: SchemeId,
+    bytes: [u8; ADDRESS_LEN]
 }
 
 impl Address {
-    /// Convert to key
+    pub fn new(scheme: SchemeId, bytes: [u8; ADDRESS_LEN]) -> Self {
+        Address {
+            scheme: scheme,
+            bytes: bytes
+        }
+    }
+
+    pub fn scheme(&self) -> SchemeId {
+        self.scheme
+    }
+
+    /// Raw bytes of the address
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_LEN] {
+        &self.bytes
+    }
+
+    /// Convert to key, only ed25519 addresses are keys themselves
     pub fn to_key(&self) -> Option<PublicKey> {
-        match self.text.from_base58() {
-            Ok(r) => {
-                if r.len() != PUBLIC_KEY_LEN {
-                    None
-                } else {
-                    let mut a: [u8; 32] = Default::default();
-                    a.copy_from_slice(&r[0..PUBLIC_KEY_LEN]);
-                    Some(a)
-                }
-            },
-            Err(_) => None
+        if self.scheme != SchemeId::Ed25519 {
+            return None;
         }
+        let mut a: PublicKey = [0u8; PUBLIC_KEY_LEN];
+        a.copy_from_slice(&self.bytes[0..PUBLIC_KEY_LEN]);
+        Some(a)
     }
 
-    /// load account
-    pub fn load() -> Option<Self> {
-        Some(Address {text: "12345678901234567890123456789012".to_string()})
+    /// Identity of this node, picked by [KeyStore::identity] from the keystore
+    /// named in the `account` settings of [node_config]
+    pub fn load() -> Result<Self, KeyStoreError> {
+        let settings = node_config().account;
+        KeyStore::open(&settings.keystore)?.identity(settings.address.as_ref())
     }
 
-    /// load vec
+    /// load the [TAGGED_ADDRESS_LEN] bytes of [Address::to_tagged_bytes].
+    /// Untagged bytes are refused so an address has a single encoding,
+    /// use `Address::from(key)` for a bare ed25519 key
     pub fn try_from(value: Vec<u8>) -> Result<Self, ()> {
-        match String::from_utf8(value) {
-            Ok(r) => Ok(Address {text: r}),
-            Err(_) => Err(())
+        if value.len() != TAGGED_ADDRESS_LEN {
+            return Err(());
         }
+        let scheme = SchemeId::from_tag(value[0]).ok_or(())?;
+        let mut bytes = [0u8; ADDRESS_LEN];
+        bytes.copy_from_slice(&value[1..]);
+        Ok(Address::new(scheme, bytes))
+    }
+
+    /// Scheme tag followed by the raw bytes
+    pub fn to_tagged_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::with_capacity(TAGGED_ADDRESS_LEN);
+        data.push(self.scheme.tag());
+        data.extend_from_slice(&self.bytes);
+        data
+    }
+
+    /// Textual form with an explicit version byte
+    pub fn to_string_with_version(&self, version: u8) -> String {
+        let mut data: Vec<u8> = Vec::with_capacity(1 + TAGGED_ADDRESS_LEN + ADDRESS_CHECKSUM_LEN);
+        data.push(version);
+        data.extend(self.to_tagged_bytes());
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum[0..ADDRESS_CHECKSUM_LEN]);
+        data.to_base58()
+    }
+
+    /// Parse a textual address which must carry `version`
+    pub fn from_str_with_version(text: &str, version: u8) -> Result<Self, AddressError> {
+        let data = text.from_base58().map_err(|_| AddressError::InvalidEncoding)?;
+        if data.len() != 1 + TAGGED_ADDRESS_LEN + ADDRESS_CHECKSUM_LEN {
+            return Err(AddressError::InvalidLength(data.len()));
+        }
+
+        let (body, tail) = data.split_at(1 + TAGGED_ADDRESS_LEN);
+        if checksum(body)[0..ADDRESS_CHECKSUM_LEN] != tail[..] {
+            return Err(AddressError::InvalidChecksum);
+        }
+        if body[0] != version {
+            return Err(AddressError::WrongVersion { expected: version, found: body[0] });
+        }
+
+        let scheme = SchemeId::from_tag(body[1]).ok_or(AddressError::UnknownScheme(body[1]))?;
+
+        let mut bytes = [0u8; ADDRESS_LEN];
+        bytes.copy_from_slice(&body[2..]);
+        Ok(Address::new(scheme, bytes))
     }
 }
 
+/// Double SHA-256, independent of the chain hash setting so addresses stay stable
+fn checksum(data: &[u8]) -> Hash {
+    let first = HashAlgorithm::Sha256.digest(data);
+    HashAlgorithm::Sha256.digest(&first[0..HASH_LEN])
+}
+
 impl From<PublicKey> for Address {
     fn from(v: PublicKey) -> Self {
-        Address {
-            text: v.to_base58()
-        }
+        Address::new(SchemeId::Ed25519, v)
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, AddressError> {
+        Address::from_str_with_version(s, address_version())
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_with_version(address_version()))
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Address({})", self)
     }
 }
 
 impl RLPSerialize for Address {
     fn serialize(&self) -> Result<RLP, RLPError> {
-        Ok(RLP::RLPItem(self.text.to_owned().into()))
+        Ok(RLP::RLPItem(self.to_tagged_bytes()))
     }
 
     fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        use std::str;
         match rlp {
             &RLP::RLPItem(ref value) => {
-                Ok(Address { text: str::from_utf8(value).unwrap().into() })
+                Address::try_from(value.clone()).map_err(|_| RLPError::RLPErrorType)
             },
             _ => {
                 Err(RLPError::RLPErrorType)
@@ -71,5 +228,73 @@ impl RLPSerialize for Address {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn sample() -> Address {
+        let mut bytes = [0u8; ADDRESS_LEN];
+        for i in 0..ADDRESS_LEN {
+            bytes[i] = i as u8;
+        }
+        Address::from(bytes)
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let addr = sample();
+        let text = addr.to_string_with_version(MAINNET_ADDRESS_VERSION);
+        assert_eq!(Address::from_str_with_version(&text, MAINNET_ADDRESS_VERSION), Ok(addr.clone()));
+        assert_eq!(text, addr.to_string());
+        assert_eq!(text.parse::<Address>(), Ok(addr));
+    }
+
+    #[test]
+    fn test_rejects_typos() {
+        let text = sample().to_string_with_version(MAINNET_ADDRESS_VERSION);
+        let mut chars: Vec<char> = text.chars().collect();
+        let last = chars.len() - 5;
+        chars[last] = if chars[last] == '2' { '3' } else { '2' };
+        let typo: String = chars.into_iter().collect();
+        assert_eq!(Address::from_str_with_version(&typo, MAINNET_ADDRESS_VERSION), Err(AddressError::InvalidChecksum));
+
+        assert_eq!(Address::from_str_with_version("0OIl", MAINNET_ADDRESS_VERSION), Err(AddressError::InvalidEncoding));
+        assert_eq!(Address::from_str_with_version(&[1u8; 8].to_base58(), MAINNET_ADDRESS_VERSION),
+                   Err(AddressError::InvalidLength(8)));
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_rejects_other_network() {
+        let text = sample().to_string_with_version(TESTNET_ADDRESS_VERSION);
+        assert_eq!(Address::from_str_with_version(&text, MAINNET_ADDRESS_VERSION),
+                   Err(AddressError::WrongVersion { expected: MAINNET_ADDRESS_VERSION, found: TESTNET_ADDRESS_VERSION }));
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        let addr = Address::from([1u8; 32]);
+        assert_eq!(Address::try_from(addr.to_tagged_bytes()), Ok(addr.clone()));
+        assert_eq!(addr.as_bytes(), &[1u8; 32]);
+        assert_eq!(Address::deserialize(&addr.serialize().unwrap()), Ok(addr));
+
+        // a bare key would be a second encoding of the same address
+        assert!(Address::try_from(vec![1u8; 32]).is_err());
+        assert!(Address::try_from(vec![1u8; 31]).is_err());
+        assert_eq!(Address::deserialize(&RLP::RLPItem(vec![1u8; 32])), Err(RLPError::RLPErrorType));
+        assert_eq!(Address::deserialize(&RLP::RLPItem(vec![1u8; 20])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_scheme_tag() {
+        let addr = Address::new(SchemeId::Secp256k1, [3u8; ADDRESS_LEN]);
+        assert!(addr.to_key().is_none());
+        assert_ne!(addr, Address::from([3u8; ADDRESS_LEN]));
+        assert_eq!(addr.to_string().parse::<Address>(), Ok(addr.clone()));
+        assert_eq!(Address::deserialize(&addr.serialize().unwrap()), Ok(addr));
+
+        let mut data = vec![MAINNET_ADDRESS_VERSION, 0x7f];
+        data.extend_from_slice(&[3u8; ADDRESS_LEN]);
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum[0..ADDRESS_CHECKSUM_LEN]);
+        assert_eq!(Address::from_str_with_version(&data.to_base58(), MAINNET_ADDRESS_VERSION),
+                   Err(AddressError::UnknownScheme(0x7f)));
+    }
+}
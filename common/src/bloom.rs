@@ -0,0 +1,172 @@
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+use rlp::RLPSerialize;
+use rlp::types::*;
+use hash::{Hasher, Keccak256Hasher};
+use rlp::inspect::to_hex;
+
+/// Bloom lenth in bytes, 2048 bits
+pub const BLOOM_LEN: usize = 256usize;
+
+/// Bits set per accrued input
+const BLOOM_BITS_PER_INPUT: usize = 3usize;
+
+/// # Bloom
+/// **Usage**
+/// - 2048-bit filter over the addresses and topics of logs, kept in receipts and blocks
+/// - every input sets 3 bits taken from its Keccak-256, independent of the chain hash
+///   setting so filters stay comparable across chains and indexers
+/// - blooms combine with `|`, the bloom of a block is the OR of its receipts
+/// ## Examples
+/// ```
+/// use common::bloom::Bloom;
+///
+/// let mut bloom = Bloom::new();
+/// bloom.accrue(b"Transfer");
+/// assert!(bloom.contains_input(b"Transfer"));
+/// assert!((bloom | Bloom::new()).contains_bloom(&bloom));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Bloom {
+    bits: [u8; BLOOM_LEN]
+}
+
+impl Bloom {
+    pub fn new() -> Self {
+        Bloom { bits: [0u8; BLOOM_LEN] }
+    }
+
+    /// Bloom of a single input
+    pub fn from_input(input: &[u8]) -> Self {
+        let mut bloom = Bloom::new();
+        bloom.accrue(input);
+        bloom
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != BLOOM_LEN {
+            return None;
+        }
+        let mut bloom = Bloom::new();
+        bloom.bits.copy_from_slice(bytes);
+        Some(bloom)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; BLOOM_LEN] {
+        &self.bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|b| *b == 0)
+    }
+
+    pub fn accrue(&mut self, input: &[u8]) {
+        let hash = Keccak256Hasher.digest(input);
+        for i in 0..BLOOM_BITS_PER_INPUT {
+            // low 11 bits of each of the first three byte pairs
+            let bit = (((hash[2 * i] as usize) << 8) | hash[2 * i + 1] as usize) & (BLOOM_LEN * 8 - 1);
+            self.bits[BLOOM_LEN - 1 - bit / 8] |= 1u8 << (bit % 8);
+        }
+    }
+
+    pub fn accrue_bloom(&mut self, other: &Bloom) {
+        for (a, b) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *a |= *b;
+        }
+    }
+
+    /// Every bit of `other` is set here
+    pub fn contains_bloom(&self, other: &Bloom) -> bool {
+        self.bits.iter().zip(other.bits.iter()).all(|(a, b)| a & b == *b)
+    }
+
+    /// False means `input` was never accrued, true only means it may have been
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        self.contains_bloom(&Bloom::from_input(input))
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom::new()
+    }
+}
+
+impl BitOr for Bloom {
+    type Output = Bloom;
+
+    fn bitor(mut self, other: Bloom) -> Bloom {
+        self.accrue_bloom(&other);
+        self
+    }
+}
+
+impl BitOrAssign for Bloom {
+    fn bitor_assign(&mut self, other: Bloom) {
+        self.accrue_bloom(&other);
+    }
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bloom(0x{})", to_hex(&self.bits))
+    }
+}
+
+impl RLPSerialize for Bloom {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(self.bits.to_vec()))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        match rlp {
+            &RLP::RLPItem(ref value) => Bloom::from_bytes(value).ok_or(RLPError::RLPErrorType),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accrue() {
+        let mut bloom = Bloom::new();
+        assert!(bloom.is_empty());
+        bloom.accrue(b"Transfer");
+        let set: u32 = bloom.as_bytes().iter().map(|b| b.count_ones()).sum();
+        assert!(set >= 1 && set as usize <= BLOOM_BITS_PER_INPUT);
+        assert!(bloom.contains_input(b"Transfer"));
+        assert!(!bloom.contains_input(b"Approval"));
+    }
+
+    #[test]
+    fn test_known_bits() {
+        // keccak256("") starts with c5d2 4601 86f7, bits 0x5d2, 0x601 and 0x6f7
+        let bloom = Bloom::from_input(b"");
+        let bytes = bloom.as_bytes();
+        assert_eq!(bytes[BLOOM_LEN - 1 - 0x5d2 / 8], 1u8 << (0x5d2 % 8));
+        assert_eq!(bytes[BLOOM_LEN - 1 - 0x601 / 8], 1u8 << (0x601 % 8));
+        assert_eq!(bytes[BLOOM_LEN - 1 - 0x6f7 / 8], 1u8 << (0x6f7 % 8));
+    }
+
+    #[test]
+    fn test_or() {
+        let a = Bloom::from_input(b"a");
+        let b = Bloom::from_input(b"b");
+        let mut both = a | b;
+        assert!(both.contains_bloom(&a) && both.contains_bloom(&b));
+        both |= Bloom::from_input(b"c");
+        assert!(both.contains_input(b"c"));
+        assert!(!a.contains_bloom(&both));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let bloom = Bloom::from_input(b"topic");
+        assert_eq!(Bloom::deserialize(&bloom.serialize().unwrap()), Ok(bloom));
+        assert_eq!(Bloom::deserialize(&RLP::RLPItem(vec![0u8; 255])), Err(RLPError::RLPErrorType));
+    }
+}
@@ -0,0 +1,166 @@
+//! secp256k1 ECDSA keys with public key recovery.
+//!
+//! Messages are hashed with Keccak-256 before signing and signatures are
+//! `r || s || v` with `v` the recovery id, the layout used by existing secp256k1 wallets.
+//! Like those wallets only the low `s` of the two valid signatures of a message is accepted,
+//! so a signature can not be changed without the key.
+//! The address of a key is the Keccak-256 of its uncompressed public key without the `0x04` prefix.
+
+use ring::rand::{SecureRandom, SystemRandom};
+use secp256k1;
+
+use address::{Address, ADDRESS_LEN};
+use hash::Keccak256Hasher;
+use hash::Hasher;
+use key::{SchemeId, SignatureScheme, TaggedSignature};
+
+/// Lenth of a raw secp256k1 secret key
+pub const SECP256K1_SECRET_LEN: usize = 32usize;
+
+/// `r || s || v`
+pub const SECP256K1_SIGNATURE_LEN: usize = 65usize;
+
+pub struct Secp256k1KeyPair {
+    secret: secp256k1::SecretKey,
+    public: secp256k1::PublicKey
+}
+
+impl Secp256k1KeyPair {
+    pub fn public_key_compressed(&self) -> [u8; 33] {
+        self.public.serialize_compressed()
+    }
+
+    /// Public key of the signer of `msg`, `None` for malformed or high `s` signatures
+    pub fn recover(msg: &[u8], sig: &TaggedSignature) -> Option<[u8; 65]> {
+        let bytes = sig.as_bytes();
+        if sig.scheme() != SchemeId::Secp256k1 || bytes.len() != SECP256K1_SIGNATURE_LEN {
+            return None;
+        }
+        let signature = secp256k1::Signature::parse_slice(&bytes[0..64]).ok()?;
+        if signature.s.is_high() {
+            return None;
+        }
+        // wallets add 27 to the recovery id
+        let recovery_id = secp256k1::RecoveryId::parse_rpc(bytes[64])
+            .or_else(|_| secp256k1::RecoveryId::parse(bytes[64]))
+            .ok()?;
+        secp256k1::recover(&message(msg), &signature, &recovery_id)
+            .map(|public| public.serialize())
+            .ok()
+    }
+}
+
+fn message(msg: &[u8]) -> secp256k1::Message {
+    secp256k1::Message::parse(&Keccak256Hasher.digest(msg))
+}
+
+fn address_of(uncompressed: &[u8; 65]) -> Address {
+    let mut bytes = [0u8; ADDRESS_LEN];
+    bytes.copy_from_slice(&Keccak256Hasher.digest(&uncompressed[1..]));
+    Address::new(SchemeId::Secp256k1, bytes)
+}
+
+impl SignatureScheme for Secp256k1KeyPair {
+    fn scheme() -> SchemeId {
+        SchemeId::Secp256k1
+    }
+
+    fn generate() -> Result<(Self, Vec<u8>), String> {
+        let rng = SystemRandom::new();
+        let mut secret = [0u8; SECP256K1_SECRET_LEN];
+        // out of range scalars are astronomically rare, just draw again
+        loop {
+            rng.fill(&mut secret).map_err(|_| "Failed to generate secret".to_string())?;
+            if let Ok(key_pair) = Secp256k1KeyPair::restore(&secret) {
+                return Ok((key_pair, secret.to_vec()));
+            }
+        }
+    }
+
+    fn restore(secret: &[u8]) -> Result<Self, String> {
+        let secret = secp256k1::SecretKey::parse_slice(secret)
+            .map_err(|e| format!("{}{:?}", "Invalid secp256k1 secret: ", e))?;
+        Ok(Secp256k1KeyPair {
+            public: secp256k1::PublicKey::from_secret_key(&secret),
+            secret: secret
+        })
+    }
+
+    fn address(&self) -> Address {
+        address_of(&self.public.serialize())
+    }
+
+    fn sign(&self, msg: &[u8]) -> TaggedSignature {
+        let (signature, recovery_id) = secp256k1::sign(&message(msg), &self.secret);
+        let mut bytes = signature.serialize().to_vec();
+        bytes.push(recovery_id.serialize());
+        TaggedSignature::new(SchemeId::Secp256k1, bytes)
+    }
+
+    fn verify(addr: &Address, msg: &[u8], sig: &TaggedSignature) -> bool {
+        if addr.scheme() != SchemeId::Secp256k1 {
+            return false;
+        }
+        match Secp256k1KeyPair::recover(msg, sig) {
+            Some(public) => address_of(&public) == *addr,
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod ecdsa {
+    use super::*;
+    use key::verify_signature;
+    use rlp::inspect::{from_hex, to_hex};
+
+    #[test]
+    fn test_sign_and_recover() {
+        let (key_pair, secret) = Secp256k1KeyPair::generate().unwrap();
+        let addr = key_pair.address();
+        assert_eq!(addr.scheme(), SchemeId::Secp256k1);
+        assert_eq!(Secp256k1KeyPair::restore(&secret).unwrap().address(), addr);
+
+        let sig = key_pair.sign(b"transfer");
+        assert_eq!(sig.as_bytes().len(), SECP256K1_SIGNATURE_LEN);
+        assert!(verify_signature(&addr, b"transfer", &sig));
+        assert!(!verify_signature(&addr, b"transfer all", &sig));
+
+        let (other, _) = Secp256k1KeyPair::generate().unwrap();
+        assert!(!verify_signature(&other.address(), b"transfer", &sig));
+    }
+
+    #[test]
+    fn test_wallet_signature() {
+        // secret key 1, its public key is the generator
+        let key_pair = Secp256k1KeyPair::restore(&from_hex(&format!("{:064x}", 1)).unwrap()).unwrap();
+        assert_eq!(to_hex(&key_pair.public_key_compressed()),
+                   "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+
+        // the same signature with v in the 27/28 form
+        let sig = key_pair.sign(b"hello");
+        let mut bytes = sig.as_bytes().to_vec();
+        bytes[64] = bytes[64] + 27;
+        let wallet_sig = TaggedSignature::new(SchemeId::Secp256k1, bytes);
+        assert!(verify_signature(&key_pair.address(), b"hello", &wallet_sig));
+        assert!(!verify_signature(&key_pair.address(), b"hello", &TaggedSignature::new(SchemeId::Secp256k1, vec![0u8; 10])));
+    }
+
+    #[test]
+    fn test_high_s() {
+        let (key_pair, _) = Secp256k1KeyPair::generate().unwrap();
+        let sig = key_pair.sign(b"hello");
+        let bytes = sig.as_bytes();
+        let mut signature = secp256k1::Signature::parse_slice(&bytes[0..64]).unwrap();
+        assert!(!signature.s.is_high());
+
+        // (r, n - s, v ^ 1) recovers the same key but is refused
+        signature.s = -signature.s.clone();
+        let mut flipped = signature.serialize().to_vec();
+        flipped.push(bytes[64] ^ 1);
+        let flipped = TaggedSignature::new(SchemeId::Secp256k1, flipped);
+        assert!(verify_signature(&key_pair.address(), b"hello", &sig));
+        assert_eq!(Secp256k1KeyPair::recover(b"hello", &flipped), None);
+        assert!(!verify_signature(&key_pair.address(), b"hello", &flipped));
+    }
+}
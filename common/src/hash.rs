@@ -1,11 +1,14 @@
+use crypto::blake2b::Blake2b;
 use crypto::digest::Digest;
 use crypto::sha2::Sha256;
+use crypto::sha3::Sha3;
 
 use rlp::RLPSerialize;
-use rlp::encoder::Encoder;
+use rlp::encoder::encode_to_vec;
+use rlp::types::EncodedRLP;
 
-/// macro gen_hash! takes (*_str => '&str' type data) and (*_raw => '&[u8]' type data) as input
-/// it generates a 'String' type output
+use std::str::FromStr;
+use std::sync::RwLock;
 
 /// Hash lenth
 pub const HASH_LEN: usize = 32usize;
@@ -13,19 +16,6 @@ pub const HASH_LEN: usize = 32usize;
 /// Hash structure
 pub type Hash = [u8; HASH_LEN];
 
-macro_rules! gen_hash {
-    (sha256_str => $e:expr) => ({
-        let mut sha = Sha256::new();
-        sha.input_str($e);
-        sha.result_str()
-    });
-    (sha256_raw => $e:expr) => ({
-        let mut sha = Sha256::new();
-        sha.input($e);
-        sha.result_str()
-    })
-}
-
 #[macro_export]
 macro_rules! zero_hash {
     () => ({
@@ -40,22 +30,140 @@ macro_rules! hash_len {
     });
 }
 
-/// Interface for hashable objects
-pub trait SerializableAndSHA256Hashable: RLPSerialize {
-    #[inline]
-    fn encrype_sha256(&self) -> Option<(Hash, Vec<u8>)>;
+/// A 256-bit hash function
+pub trait Hasher: Send + Sync {
+    /// Raw digest of `data`
+    fn digest(&self, data: &[u8]) -> Hash;
+}
+
+/// Feed `data` into a fresh digest and take its raw output
+#[inline]
+fn raw_digest<D: Digest>(mut d: D, data: &[u8]) -> Hash {
+    let mut result: Hash = zero_hash!();
+    d.input(data);
+    d.result(&mut result);
+    result
+}
+
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn digest(&self, data: &[u8]) -> Hash {
+        raw_digest(Sha256::new(), data)
+    }
+}
+
+/// The original Keccak padding used by Ethereum, not FIPS-202 SHA3-256
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    fn digest(&self, data: &[u8]) -> Hash {
+        raw_digest(Sha3::keccak256(), data)
+    }
+}
+
+/// Blake2b with a 32 bytes output and no key
+pub struct Blake2b256Hasher;
+
+impl Hasher for Blake2b256Hasher {
+    fn digest(&self, data: &[u8]) -> Hash {
+        raw_digest(Blake2b::new(HASH_LEN), data)
+    }
 }
 
-impl<T> SerializableAndSHA256Hashable for T where T: RLPSerialize {
+static SHA256_HASHER: Sha256Hasher = Sha256Hasher;
+static KECCAK256_HASHER: Keccak256Hasher = Keccak256Hasher;
+static BLAKE2B256_HASHER: Blake2b256Hasher = Blake2b256Hasher;
+
+/// Hash functions a chain can be configured with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Keccak256,
+    Blake2b256
+}
+
+impl HashAlgorithm {
+    pub fn hasher(&self) -> &'static dyn Hasher {
+        match *self {
+            HashAlgorithm::Sha256 => &SHA256_HASHER,
+            HashAlgorithm::Keccak256 => &KECCAK256_HASHER,
+            HashAlgorithm::Blake2b256 => &BLAKE2B256_HASHER
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Hash {
+        self.hasher().digest(data)
+    }
+
+    /// Name used in configuration files
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Keccak256 => "keccak256",
+            HashAlgorithm::Blake2b256 => "blake2b256"
+        }
+    }
+}
+
+impl Default for HashAlgorithm {
+    fn default() -> Self {
+        HashAlgorithm::Sha256
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "keccak256" | "keccak-256" => Ok(HashAlgorithm::Keccak256),
+            "blake2b256" | "blake2b-256" => Ok(HashAlgorithm::Blake2b256),
+            _ => Err(())
+        }
+    }
+}
+
+lazy_static! {
+    /// Hash function used for blocks, transactions and trie nodes.
+    /// Every node of a chain must agree on it, set it once at startup.
+    static ref CHAIN_HASH_ALGORITHM: RwLock<HashAlgorithm> = RwLock::new(HashAlgorithm::default());
+}
+
+pub fn chain_hash_algorithm() -> HashAlgorithm {
+    *CHAIN_HASH_ALGORITHM.read().unwrap()
+}
+
+pub fn set_chain_hash_algorithm(algorithm: HashAlgorithm) {
+    *CHAIN_HASH_ALGORITHM.write().unwrap() = algorithm;
+}
+
+/// Digest of `data` with the chain-wide hash function
+pub fn chain_digest(data: &[u8]) -> Hash {
+    chain_hash_algorithm().digest(data)
+}
+
+/// Interface for objects hashed over their encoded RLP
+pub trait SerializableAndHashable: RLPSerialize {
+    /// Hash with the chain-wide hash function, along with the encoding that was hashed
+    fn encode_and_hash(&self) -> Option<(Hash, EncodedRLP)>;
+
+    fn encode_and_hash_with(&self, hasher: &dyn Hasher) -> Option<(Hash, EncodedRLP)>;
+}
+
+impl<T> SerializableAndHashable for T where T: RLPSerialize {
     #[inline]
-    fn encrype_sha256(&self) -> Option<(Hash, Vec<u8>)> {
+    fn encode_and_hash(&self) -> Option<(Hash, EncodedRLP)> {
+        self.encode_and_hash_with(chain_hash_algorithm().hasher())
+    }
+
+    #[inline]
+    fn encode_and_hash_with(&self, hasher: &dyn Hasher) -> Option<(Hash, EncodedRLP)> {
         match self.serialize() {
             Ok(r) => {
-                let encoded_rlp = Encoder::new().encode(&r);
-                let data: String = gen_hash!(sha256_raw => &encoded_rlp);
-                let mut result: Hash = [0; HASH_LEN];
-                result.clone_from_slice(&data.as_bytes()[0 .. HASH_LEN]);
-                Some((result, encoded_rlp))
+                let encoded_rlp = encode_to_vec(&r);
+                Some((hasher.digest(&encoded_rlp), encoded_rlp))
             },
             Err(_) => {
                 None
@@ -66,6 +174,50 @@ impl<T> SerializableAndSHA256Hashable for T where T: RLPSerialize {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use rlp::types::RLP;
+
+    fn hex(hash: &Hash) -> String {
+        hash.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_known_digests() {
+        assert_eq!(hex(&Sha256Hasher.digest(b"abc")),
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hex(&Keccak256Hasher.digest(b"")),
+                   "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(hex(&Blake2b256Hasher.digest(b"abc")),
+                   "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319");
+    }
+
+    #[test]
+    fn test_encrype() {
+        let rlp: RLP = "dog".to_string().into();
+        let (hash, encoded) = rlp.encode_and_hash_with(&Sha256Hasher).unwrap();
+        assert_eq!(encoded, encode_to_vec(&rlp));
+        assert_eq!(hash, Sha256Hasher.digest(&encoded));
+    }
+
+    #[test]
+    fn test_algorithm_names() {
+        for algorithm in vec![HashAlgorithm::Sha256, HashAlgorithm::Keccak256, HashAlgorithm::Blake2b256] {
+            assert_eq!(algorithm.name().parse::<HashAlgorithm>(), Ok(algorithm));
+        }
+        assert_eq!("Keccak-256".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Keccak256));
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
+    /// Tests run in parallel, so the chain-wide setting is only read here, never changed
     #[test]
-    fn test_encrype() {}
-}
\ No newline at end of file
+    fn test_chain_setting() {
+        let rlp: RLP = "dog".to_string().into();
+        let encoded = encode_to_vec(&rlp);
+        let chain = chain_hash_algorithm();
+        assert_eq!(rlp.encode_and_hash().unwrap().0, chain.digest(&encoded));
+        assert_eq!(chain_digest(&encoded), chain.digest(&encoded));
+        assert_eq!(rlp.encode_and_hash_with(HashAlgorithm::Blake2b256.hasher()).unwrap().0,
+                   Blake2b256Hasher.digest(&encoded));
+        assert_ne!(HashAlgorithm::Keccak256.digest(&encoded), HashAlgorithm::Sha256.digest(&encoded));
+    }
+}
@@ -1,7 +1,24 @@
 use address::*;
+use ecdsa::Secp256k1KeyPair;
+use keystore;
+use mnemonic::{Mnemonic, Seed};
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha512;
 use ring::{rand, signature};
 use ring::signature::ED25519_PKCS8_V2_LEN as PKCS_LEN;
 use untrusted::Input as Input;
+use ed25519_dalek;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::scalar::Scalar;
+use rlp::RLPSerialize;
+use rlp::types::*;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
 
 pub const PUBLIC_KEY_LEN: usize = 32;
 
@@ -10,6 +27,13 @@ pub type Secret = [u8; PKCS_LEN];
 
 pub type Signature = signature::Signature;
 
+/// Lenth of an ed25519 private seed
+pub const PRIVATE_SEED_LEN: usize = 32usize;
+
+/// PKCS#8 v2 layout produced by ring, the seed and the public key are spliced in
+const PKCS8_PREFIX: [u8; 16] = [0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20];
+const PKCS8_PUBLIC_KEY_TAG: [u8; 5] = [0xa1, 0x23, 0x03, 0x21, 0x00];
+
 /// KeyPair which store public key and secret
 pub struct KeyPair {
     pair: signature::Ed25519KeyPair
@@ -23,7 +47,7 @@ pub trait KeyPairOp<'a, 'b> {
     /// Get the public key
     fn gen_rand_keypair() -> Result<(KeyPair, Secret), String>;
     fn restore_keypair(input: &'a [u8]) -> Result<KeyPair, String>;
-    fn restore_keypair_from_file() -> Result<KeyPair, String>;
+    fn restore_keypair_from_file(path: &'a Path, password: &'a str) -> Result<KeyPair, String>;
 
     fn sign_msg(&self, msg: &'a [u8]) -> signature::Signature;
     fn verify_sig(addr: &Address, msg: &'a [u8], sig: &signature::Signature) -> bool;
@@ -69,17 +93,8 @@ impl<'a, 'b> KeyPairOp<'a, 'b> for KeyPair {
     }
 
     #[inline]
-    fn restore_keypair_from_file() -> Result<KeyPair, String> {
-        //TODO:
-        let input: Vec<u8> = vec![1,2,3];
-        match signature::Ed25519KeyPair::from_pkcs8(Input::from(&input[..])) {
-            Err(why) => {
-                Err(String::from(format!("{}{}", "Failed to generate key_pair: ", why)))
-            }
-            Ok(key_pair) => {
-                Ok(KeyPair { pair: key_pair })
-            }
-        }
+    fn restore_keypair_from_file(path: &'a Path, password: &'a str) -> Result<KeyPair, String> {
+        keystore::unlock_file(path, password).map_err(|e| e.to_string())
     }
 
     #[inline]
@@ -89,22 +104,606 @@ impl<'a, 'b> KeyPairOp<'a, 'b> for KeyPair {
 
     #[inline]
     fn verify_sig(addr: &Address, msg: &'a [u8], sig: &signature::Signature) -> bool {
-        let peer_public_key_bytes = addr.to_key().unwrap();
-        let sig_bytes = sig.as_ref();
+        match addr.to_key() {
+            Some(key) => ed25519_verify(&key, msg, sig.as_ref()),
+            None => false
+        }
+    }
+}
+
+fn ed25519_verify(key: &PublicKey, msg: &[u8], sig: &[u8]) -> bool {
+    if !ed25519_strict(key, sig) {
+        return false;
+    }
+    let peer_public_key = Input::from(key);
+    let msg = Input::from(msg);
+    let sig = Input::from(sig);
+
+    match signature::verify(&signature::ED25519, peer_public_key, msg, sig) {
+        Ok(_) => true,
+        Err(_) => false
+    }
+}
 
-        let peer_public_key = Input::from(&peer_public_key_bytes);
-        let msg = Input::from(msg);
-        let sig = Input::from(sig_bytes);
+/// Encoding rules every ed25519 signature must follow, whether it is checked alone or in a batch.
+/// The public key and `R` must be canonical points of the prime order subgroup and `S` a
+/// canonical scalar. Under them the cofactorless equation of ring and the randomized batch
+/// equation of dalek accept the same signatures, so both paths agree on every block.
+fn ed25519_strict(key: &[u8], sig: &[u8]) -> bool {
+    if key.len() != PUBLIC_KEY_LEN || sig.len() != 64 {
+        return false;
+    }
+    let prime_order = |bytes: &[u8]| {
+        let compressed = CompressedEdwardsY::from_slice(bytes);
+        match compressed.decompress() {
+            Some(point) => point.compress() == compressed && !point.is_small_order() && point.is_torsion_free(),
+            None => false
+        }
+    };
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&sig[32..]);
+    prime_order(key) && prime_order(&sig[..32]) && Scalar::from_canonical_bytes(s).is_some()
+}
 
-        match signature::verify(&signature::ED25519, peer_public_key, msg, sig) {
-            Ok(_) => true,
-            Err(_) => false
+/// Signature schemes an account key can use, the tag is part of
+/// every [Address] and [TaggedSignature]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemeId {
+    Ed25519,
+    Secp256k1
+}
+
+impl SchemeId {
+    pub fn tag(&self) -> u8 {
+        match *self {
+            SchemeId::Ed25519 => 0x00u8,
+            SchemeId::Secp256k1 => 0x01u8
         }
     }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x00u8 => Some(SchemeId::Ed25519),
+            0x01u8 => Some(SchemeId::Secp256k1),
+            _ => None
+        }
+    }
+}
+
+/// Signature bytes along with the scheme which produced them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedSignature {
+    scheme: SchemeId,
+    bytes: Vec<u8>
+}
+
+impl TaggedSignature {
+    pub fn new(scheme: SchemeId, bytes: Vec<u8>) -> Self {
+        TaggedSignature {
+            scheme: scheme,
+            bytes: bytes
+        }
+    }
+
+    pub fn scheme(&self) -> SchemeId {
+        self.scheme
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Encoded as a single item, the scheme tag followed by the signature bytes
+impl RLPSerialize for TaggedSignature {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        let mut data: Vec<u8> = Vec::with_capacity(1 + self.bytes.len());
+        data.push(self.scheme.tag());
+        data.extend_from_slice(&self.bytes);
+        Ok(RLP::RLPItem(data))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        match rlp {
+            &RLP::RLPItem(ref value) if !value.is_empty() => {
+                match SchemeId::from_tag(value[0]) {
+                    Some(scheme) => Ok(TaggedSignature::new(scheme, value[1..].to_vec())),
+                    None => Err(RLPError::RLPErrorTagType)
+                }
+            },
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+}
+
+/// # SignatureScheme
+/// **Usage**
+/// - a signing key of one scheme, e.g. [KeyPair] for ed25519 or [Secp256k1KeyPair]
+/// - `verify` only needs the [Address], so each scheme decides what its address bytes are:
+///   the public key itself for ed25519, a digest of the recovered public key for secp256k1
+pub trait SignatureScheme: Sized {
+    fn scheme() -> SchemeId;
+
+    /// A fresh key and the secret to store it with
+    fn generate() -> Result<(Self, Vec<u8>), String>;
+
+    fn restore(secret: &[u8]) -> Result<Self, String>;
+
+    fn address(&self) -> Address;
+
+    fn sign(&self, msg: &[u8]) -> TaggedSignature;
+
+    /// False when either `addr` or `sig` is of another scheme
+    fn verify(addr: &Address, msg: &[u8], sig: &TaggedSignature) -> bool;
+}
+
+impl SignatureScheme for KeyPair {
+    fn scheme() -> SchemeId {
+        SchemeId::Ed25519
+    }
+
+    fn generate() -> Result<(Self, Vec<u8>), String> {
+        KeyPair::gen_rand_keypair().map(|(key_pair, secret)| (key_pair, secret.to_vec()))
+    }
+
+    fn restore(secret: &[u8]) -> Result<Self, String> {
+        KeyPair::restore_keypair(secret)
+    }
+
+    fn address(&self) -> Address {
+        Address::from(self.public_key_str())
+    }
+
+    fn sign(&self, msg: &[u8]) -> TaggedSignature {
+        TaggedSignature::new(SchemeId::Ed25519, self.sign_msg(msg).as_ref().to_vec())
+    }
+
+    fn verify(addr: &Address, msg: &[u8], sig: &TaggedSignature) -> bool {
+        if sig.scheme() != SchemeId::Ed25519 {
+            return false;
+        }
+        match addr.to_key() {
+            Some(key) => ed25519_verify(&key, msg, sig.as_bytes()),
+            None => false
+        }
+    }
+}
+
+/// Verify with whichever scheme `sig` is tagged with
+pub fn verify_signature(addr: &Address, msg: &[u8], sig: &TaggedSignature) -> bool {
+    if addr.scheme() != sig.scheme() {
+        return false;
+    }
+    match sig.scheme() {
+        SchemeId::Ed25519 => KeyPair::verify(addr, msg, sig),
+        SchemeId::Secp256k1 => Secp256k1KeyPair::verify(addr, msg, sig)
+    }
+}
+
+/// One entry of a batch, see [verify_batch]
+#[derive(Debug, Clone)]
+pub struct SignedMessage {
+    pub addr: Address,
+    pub msg: Vec<u8>,
+    pub sig: TaggedSignature
+}
+
+impl SignedMessage {
+    pub fn new(addr: Address, msg: Vec<u8>, sig: TaggedSignature) -> Self {
+        SignedMessage {
+            addr: addr,
+            msg: msg,
+            sig: sig
+        }
+    }
+}
+
+/// Below this many entries bisecting a failed batch costs more than checking one by one
+const BATCH_BISECT_MIN: usize = 4usize;
+
+/// Verify many signatures at once, e.g. every transaction of a block.
+/// Ed25519 entries are checked together with a single multi-scalar equation,
+/// entries of other schemes one by one. Ed25519 entries breaking [ed25519_strict]
+/// are invalid without entering the equation, as they are for [verify_signature].
+/// When the batch fails it is bisected, and every invalid entry is confirmed
+/// by [verify_signature] before its index is reported.
+pub fn verify_batch(entries: &[SignedMessage]) -> Result<(), Vec<usize>> {
+    let mut invalid: Vec<usize> = vec![];
+    let mut ed25519: Vec<usize> = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.sig.scheme() == SchemeId::Ed25519 && entry.addr.scheme() == SchemeId::Ed25519 {
+            if ed25519_strict(entry.addr.as_bytes(), entry.sig.as_bytes()) {
+                ed25519.push(index);
+            } else {
+                invalid.push(index);
+            }
+        } else if !verify_signature(&entry.addr, &entry.msg, &entry.sig) {
+            invalid.push(index);
+        }
+    }
+
+    find_invalid_ed25519(entries, &ed25519, &mut invalid);
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        invalid.sort();
+        Err(invalid)
+    }
+}
+
+/// [verify_batch] spread over `threads` threads.
+/// Every index of a share whose verifier panicked is reported invalid
+pub fn verify_batch_parallel(entries: &[SignedMessage], threads: usize) -> Result<(), Vec<usize>> {
+    let chunk_size = (entries.len() + threads.max(1) - 1) / threads.max(1);
+    if threads <= 1 || chunk_size == 0 || entries.len() <= chunk_size {
+        return verify_batch(entries);
+    }
+
+    // the threads borrow their share of `entries`, nothing is copied
+    let results: Vec<(usize, thread::Result<Result<(), Vec<usize>>>)> = thread::scope(|scope| {
+        let handles: Vec<_> = entries.chunks(chunk_size)
+            .enumerate()
+            .map(|(i, chunk)| (i * chunk_size, scope.spawn(move || verify_batch(chunk))))
+            .collect();
+        handles.into_iter().map(|(offset, handle)| (offset, handle.join())).collect()
+    });
+
+    let mut invalid: Vec<usize> = vec![];
+    for (offset, result) in results {
+        match result {
+            Ok(Ok(())) => {},
+            Ok(Err(indices)) => invalid.extend(indices.into_iter().map(|i| i + offset)),
+            // a panicking verifier proves nothing, and running it again could take this thread down
+            Err(_) => invalid.extend(offset..(offset + chunk_size).min(entries.len()))
+        }
+    }
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+fn find_invalid_ed25519(entries: &[SignedMessage], indices: &[usize], invalid: &mut Vec<usize>) {
+    if indices.is_empty() || ed25519_batch(entries, indices) {
+        return;
+    }
+    if indices.len() < BATCH_BISECT_MIN {
+        for index in indices {
+            let entry = &entries[*index];
+            if !verify_signature(&entry.addr, &entry.msg, &entry.sig) {
+                invalid.push(*index);
+            }
+        }
+        return;
+    }
+    let (left, right) = indices.split_at(indices.len() / 2);
+    find_invalid_ed25519(entries, left, invalid);
+    find_invalid_ed25519(entries, right, invalid);
+}
+
+/// True only when every entry of `indices` is valid
+fn ed25519_batch(entries: &[SignedMessage], indices: &[usize]) -> bool {
+    let mut messages: Vec<&[u8]> = Vec::with_capacity(indices.len());
+    let mut signatures: Vec<ed25519_dalek::Signature> = Vec::with_capacity(indices.len());
+    let mut public_keys: Vec<ed25519_dalek::PublicKey> = Vec::with_capacity(indices.len());
+    for index in indices {
+        let entry = &entries[*index];
+        match (ed25519_dalek::PublicKey::from_bytes(entry.addr.as_bytes()),
+               ed25519_dalek::Signature::try_from(entry.sig.as_bytes())) {
+            (Ok(key), Ok(sig)) => {
+                public_keys.push(key);
+                signatures.push(sig);
+            },
+            _ => return false
+        }
+        messages.push(&entry.msg);
+    }
+    ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok()
+}
+
+impl KeyPair {
+    /// Key pair of an ed25519 private seed, along with its PKCS#8 secret
+    pub fn from_private_seed(seed: &[u8; PRIVATE_SEED_LEN]) -> Result<(KeyPair, Secret), String> {
+        let pair = signature::Ed25519KeyPair::from_seed_unchecked(Input::from(seed))
+            .map_err(|why| format!("{}{}", "Failed to generate key_pair: ", why))?;
+
+        let mut secret: Secret = [0u8; PKCS_LEN];
+        let public_start = PKCS8_PREFIX.len() + PRIVATE_SEED_LEN + PKCS8_PUBLIC_KEY_TAG.len();
+        secret[..PKCS8_PREFIX.len()].copy_from_slice(&PKCS8_PREFIX);
+        secret[PKCS8_PREFIX.len()..PKCS8_PREFIX.len() + PRIVATE_SEED_LEN].copy_from_slice(seed);
+        secret[PKCS8_PREFIX.len() + PRIVATE_SEED_LEN..public_start].copy_from_slice(&PKCS8_PUBLIC_KEY_TAG);
+        secret[public_start..].copy_from_slice(pair.public_key_bytes());
+        Ok((KeyPair { pair: pair }, secret))
+    }
+}
+
+/// Indices at or above this offset are hardened
+pub const HARDENED_OFFSET: u32 = 0x8000_0000u32;
+
+const SLIP10_CURVE_KEY: &'static [u8] = b"ed25519 seed";
+
+/// # DerivationPath
+/// **Usage**
+/// - written as `m/44'/0'/7'`, `h` or `H` may be used in place of `'`
+/// - ed25519 only supports hardened derivation, so every index must be hardened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath {
+    indices: Vec<u32>
+}
+
+impl DerivationPath {
+    /// Path from hardened indices, `index` is given without the [HARDENED_OFFSET]
+    pub fn hardened(indices: &[u32]) -> Result<Self, String> {
+        let mut result: Vec<u32> = Vec::with_capacity(indices.len());
+        for index in indices {
+            if *index >= HARDENED_OFFSET {
+                return Err(format!("derivation index {} out of range", index));
+            }
+            result.push(index + HARDENED_OFFSET);
+        }
+        Ok(DerivationPath { indices: result })
+    }
+
+    /// Raw indices, offset included
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(format!("derivation path {:?} must start with m", s));
+        }
+        let mut indices: Vec<u32> = vec![];
+        for part in parts {
+            let number = if part.ends_with('\'') || part.ends_with('h') || part.ends_with('H') {
+                &part[..part.len() - 1]
+            } else {
+                return Err(format!("derivation index {:?} is not hardened", part));
+            };
+            match number.parse::<u32>() {
+                Ok(index) if index < HARDENED_OFFSET => indices.push(index),
+                _ => return Err(format!("invalid derivation index {:?}", part))
+            }
+        }
+        DerivationPath::hardened(&indices)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.indices {
+            write!(f, "/{}'", index - HARDENED_OFFSET)?;
+        }
+        Ok(())
+    }
+}
+
+/// # ExtendedKey
+/// **Usage**
+/// - SLIP-0010 ed25519 derivation, one [Seed] regenerates the same family of [KeyPair]s
+/// ## Examples
+/// ```
+/// use common::key::{ExtendedKey, KeyPairOp};
+/// use common::mnemonic::Mnemonic;
+///
+/// let mnemonic = Mnemonic::generate(12).unwrap();
+/// let (key_pair, _) = ExtendedKey::from_mnemonic(&mnemonic, "")
+///     .derive(&"m/44'/0'/0'".parse().unwrap())
+///     .key_pair()
+///     .unwrap();
+/// let (again, _) = ExtendedKey::from_mnemonic(&mnemonic, "")
+///     .derive(&"m/44'/0'/0'".parse().unwrap())
+///     .key_pair()
+///     .unwrap();
+/// assert_eq!(key_pair.public_key_str(), again.public_key_str());
+/// ```
+#[derive(Clone)]
+pub struct ExtendedKey {
+    private_seed: [u8; PRIVATE_SEED_LEN],
+    chain_code: [u8; 32]
+}
+
+impl ExtendedKey {
+    /// Master key of `seed`
+    pub fn master(seed: &[u8]) -> Self {
+        ExtendedKey::from_hmac(SLIP10_CURVE_KEY, seed)
+    }
+
+    pub fn from_mnemonic(mnemonic: &Mnemonic, passphrase: &str) -> Self {
+        let seed: Seed = mnemonic.to_seed(passphrase);
+        ExtendedKey::master(&seed)
+    }
+
+    fn from_hmac(key: &[u8], data: &[u8]) -> Self {
+        let mut mac = Hmac::new(Sha512::new(), key);
+        mac.input(data);
+        let output = mac.result();
+        let code = output.code();
+
+        let mut private_seed = [0u8; PRIVATE_SEED_LEN];
+        let mut chain_code = [0u8; 32];
+        private_seed.copy_from_slice(&code[0..32]);
+        chain_code.copy_from_slice(&code[32..64]);
+        ExtendedKey {
+            private_seed: private_seed,
+            chain_code: chain_code
+        }
+    }
+
+    /// Hardened child, the [HARDENED_OFFSET] is added to `index` when missing
+    pub fn derive_child(&self, index: u32) -> Self {
+        let index = index | HARDENED_OFFSET;
+        let mut data: Vec<u8> = Vec::with_capacity(1 + PRIVATE_SEED_LEN + 4);
+        data.push(0u8);
+        data.extend_from_slice(&self.private_seed);
+        data.extend_from_slice(&[(index >> 24) as u8, (index >> 16) as u8, (index >> 8) as u8, index as u8]);
+        ExtendedKey::from_hmac(&self.chain_code, &data)
+    }
+
+    pub fn derive(&self, path: &DerivationPath) -> Self {
+        path.indices().iter().fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    pub fn private_seed(&self) -> &[u8; PRIVATE_SEED_LEN] {
+        &self.private_seed
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn key_pair(&self) -> Result<(KeyPair, Secret), String> {
+        KeyPair::from_private_seed(&self.private_seed)
+    }
+
+    pub fn address(&self) -> Result<Address, String> {
+        self.key_pair().map(|(key_pair, _)| Address::from(key_pair.public_key_str()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use rlp::inspect::{from_hex, to_hex};
+    use crypto::digest::Digest;
+    use curve25519_dalek::constants::{ED25519_BASEPOINT_TABLE, EIGHT_TORSION};
+    use curve25519_dalek::edwards::EdwardsPoint;
+    use curve25519_dalek::traits::Identity;
+
     #[test]
     fn create_key_pair() {}
+
+    #[test]
+    fn test_tagged_signature() {
+        let (key_pair, _) = <KeyPair as SignatureScheme>::generate().unwrap();
+        let addr = SignatureScheme::address(&key_pair);
+        let sig = SignatureScheme::sign(&key_pair, b"msg");
+        assert!(verify_signature(&addr, b"msg", &sig));
+        assert!(!verify_signature(&addr, b"other", &sig));
+        assert!(!verify_signature(&Address::new(SchemeId::Secp256k1, *addr.as_bytes()), b"msg", &sig));
+        assert_eq!(TaggedSignature::deserialize(&sig.serialize().unwrap()), Ok(sig));
+        assert!(!KeyPair::verify_sig(&Address::new(SchemeId::Secp256k1, [0u8; 32]), b"msg", &key_pair.sign_msg(b"msg")));
+    }
+
+    fn signed_batch(len: usize) -> Vec<SignedMessage> {
+        let (key_pair, _) = <KeyPair as SignatureScheme>::generate().unwrap();
+        let (secp, _) = Secp256k1KeyPair::generate().unwrap();
+        (0..len).map(|i| {
+            let msg = format!("tx {}", i).into_bytes();
+            if i % 5 == 4 {
+                SignedMessage::new(secp.address(), msg.clone(), secp.sign(&msg))
+            } else {
+                SignedMessage::new(SignatureScheme::address(&key_pair), msg.clone(), SignatureScheme::sign(&key_pair, &msg))
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let mut entries = signed_batch(20);
+        assert_eq!(verify_batch(&entries), Ok(()));
+        assert_eq!(verify_batch(&[]), Ok(()));
+
+        entries[3].msg = b"forged".to_vec();
+        entries[9].msg = b"forged".to_vec();
+        entries[17].sig = TaggedSignature::new(SchemeId::Ed25519, vec![0u8; 10]);
+        assert_eq!(verify_batch(&entries), Err(vec![3, 9, 17]));
+    }
+
+    /// Signature of `msg` with the nonce point `R + torsion`, made with the secret scalar `a`
+    fn torsion_signed(a: Scalar, msg: &[u8], torsion: EdwardsPoint) -> SignedMessage {
+        let hash = |parts: &[&[u8]]| {
+            let mut hasher = crypto::sha2::Sha512::new();
+            for part in parts {
+                hasher.input(part);
+            }
+            let mut wide = [0u8; 64];
+            hasher.result(&mut wide);
+            Scalar::from_bytes_mod_order_wide(&wide)
+        };
+        let public = (&a * &ED25519_BASEPOINT_TABLE).compress();
+        let r = hash(&[b"nonce", msg]);
+        let big_r = (&r * &ED25519_BASEPOINT_TABLE + torsion).compress();
+        let k = hash(&[big_r.as_bytes(), public.as_bytes(), msg]);
+        let s = r + k * a;
+
+        let mut sig = big_r.to_bytes().to_vec();
+        sig.extend_from_slice(s.as_bytes());
+        SignedMessage::new(Address::from(public.to_bytes()), msg.to_vec(), TaggedSignature::new(SchemeId::Ed25519, sig))
+    }
+
+    #[test]
+    fn test_torsion() {
+        let a = Scalar::from_bytes_mod_order([7u8; 32]);
+        let honest = torsion_signed(a, b"msg", EdwardsPoint::identity());
+        assert!(verify_signature(&honest.addr, &honest.msg, &honest.sig));
+
+        // valid under the cofactored equation only, a random batch would accept it one time in eight
+        let torsion = torsion_signed(a, b"msg", EIGHT_TORSION[1]);
+        assert!(!verify_signature(&torsion.addr, &torsion.msg, &torsion.sig));
+
+        // identity key, identity R and zero S satisfy the cofactorless equation for any message
+        let mut weak = vec![0u8; 64];
+        weak[0] = 1;
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let small = SignedMessage::new(Address::from(identity), b"any".to_vec(), TaggedSignature::new(SchemeId::Ed25519, weak));
+        assert!(!verify_signature(&small.addr, &small.msg, &small.sig));
+
+        let mut entries = signed_batch(8);
+        entries.insert(2, torsion);
+        entries.push(small);
+        for _ in 0..16 {
+            assert_eq!(verify_batch(&entries), Err(vec![2, 9]));
+        }
+    }
+
+    #[test]
+    fn test_verify_batch_parallel() {
+        let mut entries = signed_batch(40);
+        assert_eq!(verify_batch_parallel(&entries, 4), Ok(()));
+        entries[0].msg = b"forged".to_vec();
+        entries[33].msg = b"forged".to_vec();
+        assert_eq!(verify_batch_parallel(&entries, 4), Err(vec![0, 33]));
+        assert_eq!(verify_batch_parallel(&entries, 64), Err(vec![0, 33]));
+        assert_eq!(verify_batch_parallel(&entries, 0), Err(vec![0, 33]));
+    }
+
+    #[test]
+    fn test_private_seed_pkcs8() {
+        let (key_pair, secret) = KeyPair::from_private_seed(&[7u8; PRIVATE_SEED_LEN]).unwrap();
+        let restored = KeyPair::restore_keypair(&secret[..]).unwrap();
+        assert_eq!(restored.public_key_str(), key_pair.public_key_str());
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let path: DerivationPath = "m/44'/0h/7H".parse().unwrap();
+        assert_eq!(path.indices(), &[44 + HARDENED_OFFSET, HARDENED_OFFSET, 7 + HARDENED_OFFSET]);
+        assert_eq!(path.to_string(), "m/44'/0'/7'");
+        assert!("m/44'/0".parse::<DerivationPath>().is_err());
+        assert!("44'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+        assert_eq!("m".parse::<DerivationPath>().unwrap().indices().len(), 0);
+    }
+
+    #[test]
+    fn test_slip10_vector() {
+        let master = ExtendedKey::master(&from_hex("000102030405060708090a0b0c0d0e0f").unwrap());
+        assert_eq!(to_hex(master.chain_code()), "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb");
+        assert_eq!(to_hex(master.private_seed()), "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7");
+        let (key_pair, _) = master.key_pair().unwrap();
+        assert_eq!(to_hex(&key_pair.public_key_str()), "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed");
+
+        let child = master.derive(&"m/0'".parse().unwrap());
+        assert_eq!(to_hex(child.chain_code()), "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69");
+        assert_eq!(to_hex(child.private_seed()), "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,449 @@
+//! Encrypted on-disk storage of ed25519 identities.
+//!
+//! **File layout**
+//! - one `<address>.json` file per identity inside the keystore directory
+//! - the PKCS#8 secret is encrypted with AES-128-CTR under the first half of an scrypt derived key
+//! - `mac` is the SHA-256 of the second half of the derived key followed by the ciphertext,
+//!   it tells a wrong passphrase apart from a corrupted file before anything is decrypted
+
+use crypto::aes::{ctr, KeySize};
+use crypto::scrypt::{scrypt, ScryptParams};
+use crypto::util::fixed_time_eq;
+use ring::rand::{SecureRandom, SystemRandom};
+use rlp::inspect::{from_hex, to_hex};
+use serde_json;
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use address::Address;
+use hash::HashAlgorithm;
+use key::*;
+
+/// Layout version written by this module
+pub const KEYSTORE_VERSION: u32 = 1u32;
+
+const CIPHER: &'static str = "aes-128-ctr";
+const KDF: &'static str = "scrypt";
+const FILE_EXTENSION: &'static str = "json";
+const DERIVED_KEY_LEN: usize = 32usize;
+const SALT_LEN: usize = 32usize;
+const IV_LEN: usize = 16usize;
+/// Memory an scrypt derivation may take, `log_n` up to 17 with the default `r`.
+/// A file asking for more is refused instead of exhausting the node.
+const MAX_KDF_MEMORY: u64 = 256 * 1024 * 1024;
+/// rust-crypto panics when `r * p` reaches 2^30
+const MAX_KDF_R_P: u64 = (1 << 30) - 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyStoreError {
+    IO(io::ErrorKind),
+    /// the file is not a keystore this module understands
+    Format(String),
+    UnsupportedVersion(u32),
+    WrongPassword,
+    /// the decrypted secret is not a valid PKCS#8 ed25519 document
+    InvalidSecret(String),
+    NotFound(Address),
+    /// the keystore holds no key to pick
+    NoIdentity,
+    /// the keystore holds this many keys and none was named
+    AmbiguousIdentity(usize)
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyStoreError::IO(ref kind) => write!(f, "keystore io error: {:?}", kind),
+            KeyStoreError::Format(ref why) => write!(f, "malformed keystore file: {}", why),
+            KeyStoreError::UnsupportedVersion(v) => write!(f, "unsupported keystore version {}", v),
+            KeyStoreError::WrongPassword => write!(f, "wrong password"),
+            KeyStoreError::InvalidSecret(ref why) => write!(f, "invalid secret: {}", why),
+            KeyStoreError::NotFound(ref addr) => write!(f, "no key for {}", addr),
+            KeyStoreError::NoIdentity => write!(f, "keystore holds no key"),
+            KeyStoreError::AmbiguousIdentity(n) => write!(f, "keystore holds {} keys, name the one to use", n)
+        }
+    }
+}
+
+impl From<io::Error> for KeyStoreError {
+    fn from(e: io::Error) -> Self {
+        KeyStoreError::IO(e.kind())
+    }
+}
+
+/// Cost of the passphrase derivation, stored in every file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32
+}
+
+impl Default for KdfParams {
+    /// About 16MB of memory per derivation
+    fn default() -> Self {
+        KdfParams { log_n: 14, r: 8, p: 1 }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    address: String,
+    crypto: KeyFileCrypto
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyFileCrypto {
+    cipher: String,
+    iv: String,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: KdfParams,
+    salt: String,
+    mac: String
+}
+
+impl KeyFile {
+    fn seal(addr: &Address, secret: &[u8], password: &str, params: KdfParams) -> Result<Self, KeyStoreError> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut iv = [0u8; IV_LEN];
+        rng.fill(&mut salt).and_then(|_| rng.fill(&mut iv))
+            .map_err(|_| KeyStoreError::IO(io::ErrorKind::Other))?;
+
+        let derived = derive_key(password, &salt, &params)?;
+        let mut ciphertext = vec![0u8; secret.len()];
+        ctr(KeySize::KeySize128, &derived[0..16], &iv).process(secret, &mut ciphertext);
+
+        Ok(KeyFile {
+            version: KEYSTORE_VERSION,
+            address: addr.to_string(),
+            crypto: KeyFileCrypto {
+                cipher: CIPHER.to_string(),
+                iv: to_hex(&iv),
+                mac: to_hex(&mac(&derived, &ciphertext)),
+                ciphertext: to_hex(&ciphertext),
+                kdf: KDF.to_string(),
+                kdfparams: params,
+                salt: to_hex(&salt)
+            }
+        })
+    }
+
+    fn open(&self, password: &str) -> Result<Vec<u8>, KeyStoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeyStoreError::UnsupportedVersion(self.version));
+        }
+        let c = &self.crypto;
+        if c.cipher != CIPHER || c.kdf != KDF {
+            return Err(KeyStoreError::Format(format!("unsupported {} with {}", c.cipher, c.kdf)));
+        }
+        let salt = hex_field(&c.salt, "salt")?;
+        let iv = hex_field(&c.iv, "iv")?;
+        let ciphertext = hex_field(&c.ciphertext, "ciphertext")?;
+        let expected_mac = hex_field(&c.mac, "mac")?;
+        if iv.len() != IV_LEN {
+            return Err(KeyStoreError::Format("iv".to_string()));
+        }
+
+        let derived = derive_key(password, &salt, &c.kdfparams)?;
+        if !fixed_time_eq(&mac(&derived, &ciphertext), &expected_mac) {
+            return Err(KeyStoreError::WrongPassword);
+        }
+        let mut secret = vec![0u8; ciphertext.len()];
+        ctr(KeySize::KeySize128, &derived[0..16], &iv).process(&ciphertext, &mut secret);
+        Ok(secret)
+    }
+
+    fn read(path: &Path) -> Result<Self, KeyStoreError> {
+        let mut content = String::new();
+        File::open(path)?.read_to_string(&mut content)?;
+        serde_json::from_str(&content).map_err(|e| KeyStoreError::Format(e.to_string()))
+    }
+
+    /// Write next to the target and rename, an interrupted write never leaves a half file behind.
+    /// Only the owner may read the file on unix
+    fn write(&self, path: &Path) -> Result<(), KeyStoreError> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| KeyStoreError::Format(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        {
+            // a leftover of an interrupted write may carry other permissions
+            let _ = fs::remove_file(&tmp);
+            let mut file = create_private(&tmp)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Check `params` before handing them to rust-crypto, which panics on some of them
+fn check_kdf_params(params: &KdfParams) -> Result<(), KeyStoreError> {
+    let (r, p) = (params.r as u64, params.p as u64);
+    if params.log_n == 0 || params.log_n >= 32 || r == 0 || p == 0 || r * p > MAX_KDF_R_P {
+        return Err(KeyStoreError::Format("kdfparams".to_string()));
+    }
+    // the 128 * r byte blocks of the lookup table and of the p lanes
+    let memory = (1u64 << params.log_n).checked_add(p).and_then(|blocks| blocks.checked_mul(128 * r));
+    match memory {
+        Some(bytes) if bytes <= MAX_KDF_MEMORY => Ok(()),
+        _ => Err(KeyStoreError::Format("kdfparams above the memory limit".to_string()))
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> Result<[u8; DERIVED_KEY_LEN], KeyStoreError> {
+    check_kdf_params(params)?;
+    let mut derived = [0u8; DERIVED_KEY_LEN];
+    scrypt(password.as_bytes(), salt, &ScryptParams::new(params.log_n, params.r, params.p), &mut derived);
+    Ok(derived)
+}
+
+fn mac(derived: &[u8; DERIVED_KEY_LEN], ciphertext: &[u8]) -> [u8; 32] {
+    let mut data = derived[16..DERIVED_KEY_LEN].to_vec();
+    data.extend_from_slice(ciphertext);
+    HashAlgorithm::Sha256.digest(&data)
+}
+
+fn hex_field(text: &str, name: &str) -> Result<Vec<u8>, KeyStoreError> {
+    from_hex(text).map_err(|_| KeyStoreError::Format(name.to_string()))
+}
+
+fn restore(secret: &[u8]) -> Result<KeyPair, KeyStoreError> {
+    KeyPair::restore_keypair(secret).map_err(KeyStoreError::InvalidSecret)
+}
+
+/// Unlock a single keystore file
+pub fn unlock_file(path: &Path, password: &str) -> Result<KeyPair, KeyStoreError> {
+    let secret = KeyFile::read(path)?.open(password)?;
+    restore(&secret)
+}
+
+/// # KeyStore
+/// **Usage**
+/// - a directory of encrypted identities, raw [Secret] bytes are never written to disk
+/// - identities are looked up by [Address]
+/// ## Examples
+/// ```no_run
+/// use common::keystore::KeyStore;
+///
+/// let store = KeyStore::open("./keys").unwrap();
+/// let addr = store.create("passphrase").unwrap();
+/// let key_pair = store.unlock(&addr, "passphrase").unwrap();
+/// ```
+pub struct KeyStore {
+    dir: PathBuf,
+    params: KdfParams
+}
+
+impl KeyStore {
+    /// Open `dir`, creating it when missing
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, KeyStoreError> {
+        KeyStore::open_with_params(dir, KdfParams::default())
+    }
+
+    /// `params` only applies to files written from now on
+    pub fn open_with_params<P: AsRef<Path>>(dir: P, params: KdfParams) -> Result<Self, KeyStoreError> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(KeyStore {
+            dir: dir.as_ref().to_path_buf(),
+            params: params
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_of(&self, addr: &Address) -> PathBuf {
+        self.dir.join(format!("{}.{}", addr, FILE_EXTENSION))
+    }
+
+    fn read(&self, addr: &Address) -> Result<KeyFile, KeyStoreError> {
+        let path = self.path_of(addr);
+        if !path.is_file() {
+            return Err(KeyStoreError::NotFound(addr.clone()));
+        }
+        KeyFile::read(&path)
+    }
+
+    /// Generate a new identity and store it under `password`
+    pub fn create(&self, password: &str) -> Result<Address, KeyStoreError> {
+        let (_, secret) = KeyPair::gen_rand_keypair().map_err(KeyStoreError::InvalidSecret)?;
+        self.import(&secret, password)
+    }
+
+    /// Store an existing PKCS#8 secret, the file of the same identity is replaced
+    pub fn import(&self, secret: &[u8], password: &str) -> Result<Address, KeyStoreError> {
+        let addr = Address::from(restore(secret)?.public_key_str());
+        KeyFile::seal(&addr, secret, password, self.params)?.write(&self.path_of(&addr))?;
+        Ok(addr)
+    }
+
+    /// Addresses of every identity in the directory, files of other networks are skipped
+    pub fn list(&self) -> Result<Vec<Address>, KeyStoreError> {
+        let mut result: Vec<Address> = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(FILE_EXTENSION) {
+                continue;
+            }
+            if let Some(addr) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) {
+                result.push(addr);
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    /// Identity to act as, `addr` when given and stored, otherwise the only key of the directory
+    pub fn identity(&self, addr: Option<&Address>) -> Result<Address, KeyStoreError> {
+        let mut keys = self.list()?;
+        match addr {
+            Some(addr) if keys.contains(addr) => Ok(addr.clone()),
+            Some(addr) => Err(KeyStoreError::NotFound(addr.clone())),
+            None if keys.len() == 1 => Ok(keys.remove(0)),
+            None if keys.is_empty() => Err(KeyStoreError::NoIdentity),
+            None => Err(KeyStoreError::AmbiguousIdentity(keys.len()))
+        }
+    }
+
+    pub fn unlock(&self, addr: &Address, password: &str) -> Result<KeyPair, KeyStoreError> {
+        restore(&self.export(addr, password)?)
+    }
+
+    /// Re-encrypt with a fresh salt and iv
+    pub fn change_password(&self, addr: &Address, old: &str, new: &str) -> Result<(), KeyStoreError> {
+        let secret = self.export(addr, old)?;
+        KeyFile::seal(addr, &secret, new, self.params)?.write(&self.path_of(addr))
+    }
+
+    /// The decrypted PKCS#8 secret, for backups or moving to another keystore
+    pub fn export(&self, addr: &Address, password: &str) -> Result<Vec<u8>, KeyStoreError> {
+        self.read(addr)?.open(password)
+    }
+
+    pub fn remove(&self, addr: &Address, password: &str) -> Result<(), KeyStoreError> {
+        self.export(addr, password)?;
+        fs::remove_file(self.path_of(addr))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod keystore {
+    use super::*;
+    use std::env;
+
+    fn temp_store(name: &str) -> KeyStore {
+        let dir = env::temp_dir().join(format!("keystore-{}-{}", name, ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        KeyStore::open_with_params(&dir, KdfParams { log_n: 4, r: 8, p: 1 }).unwrap()
+    }
+
+    #[test]
+    fn test_create_and_unlock() {
+        let store = temp_store("unlock");
+        let addr = store.create("secret").unwrap();
+        assert_eq!(store.list().unwrap(), vec![addr.clone()]);
+
+        let key_pair = store.unlock(&addr, "secret").unwrap();
+        assert_eq!(Address::from(key_pair.public_key_str()), addr);
+        assert!(store.unlock(&addr, "wrong").err() == Some(KeyStoreError::WrongPassword));
+
+        // nothing of the secret is stored in clear
+        let secret = store.export(&addr, "secret").unwrap();
+        let mut content = String::new();
+        File::open(store.path_of(&addr)).unwrap().read_to_string(&mut content).unwrap();
+        assert!(!content.contains(&to_hex(&secret)));
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let store = temp_store("mode");
+        let addr = store.create("secret").unwrap();
+        let mode = fs::metadata(store.path_of(&addr)).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_change_password() {
+        let store = temp_store("change");
+        let addr = store.create("old").unwrap();
+        let secret = store.export(&addr, "old").unwrap();
+        assert!(store.change_password(&addr, "bad", "new").is_err());
+        store.change_password(&addr, "old", "new").unwrap();
+        assert!(store.export(&addr, "old").err() == Some(KeyStoreError::WrongPassword));
+        assert_eq!(store.export(&addr, "new").unwrap(), secret);
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_import_and_errors() {
+        let store = temp_store("import");
+        let (key_pair, secret) = KeyPair::gen_rand_keypair().unwrap();
+        let addr = store.import(&secret[..], "pw").unwrap();
+        assert_eq!(addr, Address::from(key_pair.public_key_str()));
+        assert!(store.import(&[1u8, 2, 3], "pw").is_err());
+
+        let missing = Address::from([9u8; PUBLIC_KEY_LEN]);
+        assert!(store.unlock(&missing, "pw").err() == Some(KeyStoreError::NotFound(missing.clone())));
+
+        let mut file = KeyFile::read(&store.path_of(&addr)).unwrap();
+        file.version = 99;
+        file.write(&store.path_of(&addr)).unwrap();
+        assert!(store.unlock(&addr, "pw").err() == Some(KeyStoreError::UnsupportedVersion(99)));
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_identity() {
+        let store = temp_store("identity");
+        assert_eq!(store.identity(None), Err(KeyStoreError::NoIdentity));
+        let first = store.create("pw").unwrap();
+        assert_eq!(store.identity(None), Ok(first.clone()));
+
+        let second = store.create("pw").unwrap();
+        assert_eq!(store.identity(None), Err(KeyStoreError::AmbiguousIdentity(2)));
+        assert_eq!(store.identity(Some(&second)), Ok(second.clone()));
+        let missing = Address::from([9u8; PUBLIC_KEY_LEN]);
+        assert_eq!(store.identity(Some(&missing)), Err(KeyStoreError::NotFound(missing.clone())));
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_kdf_limits() {
+        assert!(check_kdf_params(&KdfParams::default()).is_ok());
+        assert!(check_kdf_params(&KdfParams { log_n: 17, r: 8, p: 1 }).is_ok());
+        // memory above the limit
+        assert!(check_kdf_params(&KdfParams { log_n: 18, r: 8, p: 1 }).is_err());
+        assert!(check_kdf_params(&KdfParams { log_n: 31, r: 8, p: 1 }).is_err());
+        assert!(check_kdf_params(&KdfParams { log_n: 4, r: 1 << 20, p: 1 }).is_err());
+        // r * p from 2^30 on would panic inside scrypt
+        assert!(check_kdf_params(&KdfParams { log_n: 1, r: 1 << 15, p: 1 << 15 }).is_err());
+        assert!(check_kdf_params(&KdfParams { log_n: 4, r: 0, p: 1 }).is_err());
+
+        let mut file = KeyFile::seal(&Address::from([1u8; PUBLIC_KEY_LEN]), b"secret", "pw", KdfParams { log_n: 4, r: 8, p: 1 }).unwrap();
+        file.crypto.kdfparams = KdfParams { log_n: 30, r: 8, p: 1 };
+        assert_eq!(file.open("pw").err(), Some(KeyStoreError::Format("kdfparams above the memory limit".to_string())));
+    }
+}
@@ -1,17 +1,32 @@
 pub mod address;
+pub mod bloom;
+pub mod ecdsa;
 pub mod hash;
 pub mod key;
+pub mod keystore;
+pub mod merkle;
+pub mod mnemonic;
+pub mod node_config;
 pub mod thread;
 pub mod observe;
 
 pub extern crate crypto;
+pub extern crate ed25519_dalek;
+pub extern crate curve25519_dalek;
 pub extern crate rand;
 pub extern crate ring;
 pub extern crate rust_base58;
+pub extern crate secp256k1;
 pub extern crate untrusted;
 pub extern crate siphasher;
+pub extern crate serde;
+#[macro_use]
+pub extern crate serde_derive;
+pub extern crate serde_json;
 #[macro_use]
 pub extern crate lazy_static;
 
 pub extern crate gen_message;
-pub extern crate rlp;
\ No newline at end of file
+pub extern crate rlp;
+#[macro_use]
+extern crate rlp_derive;
\ No newline at end of file
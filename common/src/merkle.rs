@@ -0,0 +1,220 @@
+//! Ordered binary Merkle tree over serialized items, e.g. the transactions or receipts of a block.
+//!
+//! **Layout**
+//! - leaves are `H(0x00 || item)` and inner nodes `H(0x01 || left || right)`, so a leaf can never
+//!   be passed off as an inner node
+//! - a node without a sibling is carried up to the next level unchanged
+//! - the root of no items is the zero hash
+//! - `H` is the chain hash function unless a [Hasher] is given
+
+use rlp::RLPSerialize;
+use rlp::encoder::encode_to_vec;
+use rlp::types::*;
+
+use hash::*;
+
+const LEAF_PREFIX: u8 = 0x00u8;
+const NODE_PREFIX: u8 = 0x01u8;
+
+fn leaf_hash(hasher: &dyn Hasher, item: &[u8]) -> Hash {
+    let mut data: Vec<u8> = Vec::with_capacity(1 + item.len());
+    data.push(LEAF_PREFIX);
+    data.extend_from_slice(item);
+    hasher.digest(&data)
+}
+
+fn node_hash(hasher: &dyn Hasher, left: &Hash, right: &Hash) -> Hash {
+    let mut data: Vec<u8> = Vec::with_capacity(1 + 2 * HASH_LEN);
+    data.push(NODE_PREFIX);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    hasher.digest(&data)
+}
+
+/// # MerkleTree
+/// **Usage**
+/// - build once, then take the root and as many proofs as needed
+/// ## Examples
+/// ```
+/// use common::merkle::{MerkleTree, verify_proof};
+///
+/// let items = vec![b"tx0".to_vec(), b"tx1".to_vec(), b"tx2".to_vec()];
+/// let tree = MerkleTree::new(&items);
+/// let proof = tree.proof(2).unwrap();
+/// assert!(verify_proof(&tree.root(), b"tx2", &proof));
+/// assert!(!verify_proof(&tree.root(), b"tx1", &proof));
+/// ```
+pub struct MerkleTree {
+    /// leaf hashes first, the root level last
+    levels: Vec<Vec<Hash>>
+}
+
+impl MerkleTree {
+    pub fn new<T: AsRef<[u8]>>(items: &[T]) -> Self {
+        MerkleTree::new_with(items, chain_hash_algorithm().hasher())
+    }
+
+    pub fn new_with<T: AsRef<[u8]>>(items: &[T], hasher: &dyn Hasher) -> Self {
+        let mut levels: Vec<Vec<Hash>> = vec![items.iter().map(|item| leaf_hash(hasher, item.as_ref())).collect()];
+        while levels[levels.len() - 1].len() > 1 {
+            let next: Vec<Hash> = levels[levels.len() - 1].chunks(2)
+                .map(|pair| if pair.len() == 2 { node_hash(hasher, &pair[0], &pair[1]) } else { pair[0] })
+                .collect();
+            levels.push(next);
+        }
+        MerkleTree { levels: levels }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn root(&self) -> Hash {
+        match self.levels[self.levels.len() - 1].first() {
+            Some(root) => *root,
+            None => zero_hash!()
+        }
+    }
+
+    /// Inclusion proof of the item at `index`
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut siblings: Vec<Hash> = vec![];
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = i ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            i = i / 2;
+        }
+        Some(MerkleProof {
+            index: index,
+            leaf_count: self.len(),
+            siblings: siblings
+        })
+    }
+}
+
+/// Siblings on the path from an item to the root, bottom up.
+/// `leaf_count` tells which levels have a sibling, the root does not commit to it.
+#[derive(Debug, Clone, PartialEq, Eq, RLPSerialize)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaf_count: usize,
+    pub siblings: Vec<Hash>
+}
+
+pub fn merkle_root<T: AsRef<[u8]>>(items: &[T]) -> Hash {
+    MerkleTree::new(items).root()
+}
+
+/// Root over the RLP encoding of each item
+pub fn merkle_root_of<T: RLPSerialize>(items: &[T]) -> Result<Hash, RLPError> {
+    let mut encoded: Vec<EncodedRLP> = Vec::with_capacity(items.len());
+    for item in items {
+        encoded.push(encode_to_vec(&item.serialize()?));
+    }
+    Ok(merkle_root(&encoded))
+}
+
+pub fn verify_proof(root: &Hash, item: &[u8], proof: &MerkleProof) -> bool {
+    verify_proof_with(root, item, proof, chain_hash_algorithm().hasher())
+}
+
+/// True when `item` is the leaf `proof.index` of a tree of `proof.leaf_count` items under `root`
+pub fn verify_proof_with(root: &Hash, item: &[u8], proof: &MerkleProof, hasher: &dyn Hasher) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+    let mut hash = leaf_hash(hasher, item);
+    let mut siblings = proof.siblings.iter();
+    let mut i = proof.index;
+    let mut count = proof.leaf_count;
+    while count > 1 {
+        if i % 2 == 1 {
+            match siblings.next() {
+                Some(sibling) => hash = node_hash(hasher, sibling, &hash),
+                None => return false
+            }
+        } else if i + 1 < count {
+            match siblings.next() {
+                Some(sibling) => hash = node_hash(hasher, &hash, sibling),
+                None => return false
+            }
+        }
+        i = i / 2;
+        count = (count + 1) / 2;
+    }
+    siblings.next().is_none() && hash == *root
+}
+
+#[cfg(test)]
+mod merkle {
+    use super::*;
+
+    // the chain setting is global and other tests change it
+    const SHA256: &'static dyn Hasher = &Sha256Hasher;
+
+    fn items(n: usize) -> Vec<Vec<u8>> {
+        (0..n).map(|i| format!("item {}", i).into_bytes()).collect()
+    }
+
+    #[test]
+    fn test_root() {
+        assert_eq!(merkle_root::<Vec<u8>>(&[]), zero_hash!());
+
+        let hasher = HashAlgorithm::Sha256.hasher();
+        let items = items(3);
+        let left = node_hash(hasher, &leaf_hash(hasher, &items[0]), &leaf_hash(hasher, &items[1]));
+        let root = node_hash(hasher, &left, &leaf_hash(hasher, &items[2]));
+        assert_eq!(MerkleTree::new_with(&items, hasher).root(), root);
+        assert_eq!(MerkleTree::new_with(&items[..1], hasher).root(), leaf_hash(hasher, &items[0]));
+    }
+
+    #[test]
+    fn test_proofs() {
+        for n in 1..18 {
+            let items = items(n);
+            let tree = MerkleTree::new_with(&items, SHA256);
+            let root = tree.root();
+            for (i, item) in items.iter().enumerate() {
+                let proof = tree.proof(i).unwrap();
+                assert!(verify_proof_with(&root, item, &proof, SHA256), "{} of {}", i, n);
+                assert_eq!(MerkleProof::deserialize(&proof.serialize().unwrap()), Ok(proof.clone()));
+                if n > 1 {
+                    let other = (i + 1) % n;
+                    assert!(!verify_proof_with(&root, &items[other], &proof, SHA256));
+                }
+            }
+            assert!(tree.proof(n).is_none());
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof() {
+        let items = items(5);
+        let tree = MerkleTree::new_with(&items, SHA256);
+        let proof = tree.proof(1).unwrap();
+
+        let mut moved = proof.clone();
+        moved.index = 0;
+        assert!(!verify_proof_with(&tree.root(), &items[1], &moved, SHA256));
+
+        let mut short = proof.clone();
+        short.siblings.pop();
+        assert!(!verify_proof_with(&tree.root(), &items[1], &short, SHA256));
+
+        let mut long = proof.clone();
+        long.siblings.push(zero_hash!());
+        assert!(!verify_proof_with(&tree.root(), &items[1], &long, SHA256));
+
+        // the last item has no sibling at the bottom level
+        let mut resized = tree.proof(4).unwrap();
+        assert!(verify_proof_with(&tree.root(), &items[4], &resized, SHA256));
+        resized.leaf_count = 6;
+        assert!(!verify_proof_with(&tree.root(), &items[4], &resized, SHA256));
+    }
+}
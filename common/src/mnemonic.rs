@@ -0,0 +1,213 @@
+//! BIP-39 mnemonics with the english wordlist.
+//!
+//! Words and passphrases are used as given, without NFKD normalization,
+//! which only makes a difference for non ascii passphrases.
+
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha512;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use std::fmt;
+use std::str::FromStr;
+
+use hash::HashAlgorithm;
+
+/// Lenth of the seed fed into key derivation
+pub const SEED_LEN: usize = 64usize;
+
+pub type Seed = [u8; SEED_LEN];
+
+const WORD_BITS: usize = 11usize;
+const PBKDF2_ROUNDS: u32 = 2048u32;
+const SALT_PREFIX: &'static str = "mnemonic";
+
+lazy_static! {
+    static ref WORDLIST: Vec<&'static str> = include_str!("bip39_english.txt").lines().collect();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// only 12, 15, 18, 21 and 24 words are valid
+    InvalidWordCount(usize),
+    /// entropy must be 16 to 32 bytes in steps of 4
+    InvalidEntropyLength(usize),
+    UnknownWord(String),
+    InvalidChecksum,
+    Random
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MnemonicError::InvalidWordCount(n) => write!(f, "invalid mnemonic word count {}", n),
+            MnemonicError::InvalidEntropyLength(n) => write!(f, "invalid entropy lenth {}", n),
+            MnemonicError::UnknownWord(ref word) => write!(f, "unknown mnemonic word {:?}", word),
+            MnemonicError::InvalidChecksum => write!(f, "mnemonic checksum mismatch"),
+            MnemonicError::Random => write!(f, "system random source failed")
+        }
+    }
+}
+
+/// # Mnemonic
+/// **Usage**
+/// - a backup phrase for a whole family of keys, see [ExtendedKey](../key/struct.ExtendedKey.html)
+/// - parsing checks every word and the checksum, so typos are caught before any key is derived
+/// ## Examples
+/// ```
+/// use common::mnemonic::Mnemonic;
+///
+/// let mnemonic = Mnemonic::generate(24).unwrap();
+/// let restored: Mnemonic = mnemonic.phrase().parse().unwrap();
+/// assert_eq!(restored.to_seed("").to_vec(), mnemonic.to_seed("").to_vec());
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    entropy: Vec<u8>
+}
+
+impl Mnemonic {
+    /// Fresh mnemonic from the system random source
+    pub fn generate(word_count: usize) -> Result<Self, MnemonicError> {
+        if word_count < 12 || word_count > 24 || word_count % 3 != 0 {
+            return Err(MnemonicError::InvalidWordCount(word_count));
+        }
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        SystemRandom::new().fill(&mut entropy).map_err(|_| MnemonicError::Random)?;
+        Mnemonic::from_entropy(&entropy)
+    }
+
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, MnemonicError> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(MnemonicError::InvalidEntropyLength(entropy.len()));
+        }
+        Ok(Mnemonic { entropy: entropy.to_vec() })
+    }
+
+    /// Parse and validate a phrase, words may be separated by any whitespace
+    pub fn from_phrase(phrase: &str) -> Result<Self, MnemonicError> {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if words.len() < 12 || words.len() > 24 || words.len() % 3 != 0 {
+            return Err(MnemonicError::InvalidWordCount(words.len()));
+        }
+
+        let mut bits: Vec<bool> = Vec::with_capacity(words.len() * WORD_BITS);
+        for word in &words {
+            let index = WORDLIST.binary_search(word)
+                .map_err(|_| MnemonicError::UnknownWord(word.to_string()))?;
+            for i in (0..WORD_BITS).rev() {
+                bits.push((index >> i) & 1 == 1);
+            }
+        }
+
+        let checksum_bits = bits.len() / 33;
+        let entropy: Vec<u8> = bits[..bits.len() - checksum_bits].chunks(8)
+            .map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | (*bit as u8)))
+            .collect();
+        let mnemonic = Mnemonic { entropy: entropy };
+        if mnemonic.checksum_bits() != &bits[bits.len() - checksum_bits..] {
+            return Err(MnemonicError::InvalidChecksum);
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    pub fn words(&self) -> Vec<&'static str> {
+        let mut bits: Vec<bool> = Vec::with_capacity(self.entropy.len() * 8 + 8);
+        for byte in &self.entropy {
+            for i in (0..8).rev() {
+                bits.push((byte >> i) & 1 == 1);
+            }
+        }
+        bits.extend(self.checksum_bits());
+        bits.chunks(WORD_BITS)
+            .map(|word| word.iter().fold(0usize, |acc, bit| (acc << 1) | (*bit as usize)))
+            .map(|index| WORDLIST[index])
+            .collect()
+    }
+
+    pub fn phrase(&self) -> String {
+        self.words().join(" ")
+    }
+
+    /// PBKDF2-HMAC-SHA512 of the phrase, salted with `passphrase`
+    pub fn to_seed(&self, passphrase: &str) -> Seed {
+        let mut seed: Seed = [0u8; SEED_LEN];
+        let mut mac = Hmac::new(Sha512::new(), self.phrase().as_bytes());
+        let salt = format!("{}{}", SALT_PREFIX, passphrase);
+        pbkdf2(&mut mac, salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+        seed
+    }
+
+    /// First `entropy bits / 32` bits of the SHA-256 of the entropy
+    fn checksum_bits(&self) -> Vec<bool> {
+        let hash = HashAlgorithm::Sha256.digest(&self.entropy);
+        (0..self.entropy.len() / 4).map(|i| (hash[i / 8] >> (7 - i % 8)) & 1 == 1).collect()
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = MnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, MnemonicError> {
+        Mnemonic::from_phrase(s)
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.phrase())
+    }
+}
+
+/// The phrase is as sensitive as the keys it derives, keep it out of logs
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mnemonic({} words)", self.entropy.len() * 3 / 4)
+    }
+}
+
+#[cfg(test)]
+mod mnemonic {
+    use super::*;
+    use rlp::inspect::{from_hex, to_hex};
+
+    #[test]
+    fn test_wordlist() {
+        assert_eq!(WORDLIST.len(), 1 << WORD_BITS);
+        assert_eq!(WORDLIST[0], "abandon");
+        assert_eq!(WORDLIST[2047], "zoo");
+    }
+
+    #[test]
+    fn test_vectors() {
+        let vectors = vec![
+            ("00000000000000000000000000000000",
+             "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+             "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"),
+            ("7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+             "legal winner thank year wave sausage worth useful legal winner thank yellow",
+             "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607")
+        ];
+        for (entropy, phrase, seed) in vectors {
+            let mnemonic = Mnemonic::from_entropy(&from_hex(entropy).unwrap()).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(Mnemonic::from_phrase(phrase).unwrap(), mnemonic);
+            assert_eq!(to_hex(&mnemonic.to_seed("TREZOR")), seed);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(Mnemonic::from_phrase("abandon abandon"), Err(MnemonicError::InvalidWordCount(2)));
+        let typo = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abuot";
+        assert_eq!(Mnemonic::from_phrase(typo), Err(MnemonicError::UnknownWord("abuot".to_string())));
+        let swapped = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert_eq!(Mnemonic::from_phrase(swapped), Err(MnemonicError::InvalidChecksum));
+        assert_eq!(Mnemonic::generate(13), Err(MnemonicError::InvalidWordCount(13)));
+        assert_eq!(Mnemonic::from_entropy(&[0u8; 15]), Err(MnemonicError::InvalidEntropyLength(15)));
+    }
+}
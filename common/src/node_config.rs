@@ -0,0 +1,644 @@
+//! Typed configuration of a node, merged from four layers, each overriding the one before:
+//! - built-in defaults
+//! - a JSON file, `config/application.json` unless `--config` or `GEN_CONFIG` names another
+//! - environment variables, `GEN_` followed by the key in capitals with `_` for `.`,
+//!   e.g. `GEN_NETWORK_SERVER_ADDR` for `network.server_addr`
+//! - command line flags, `--network.server_addr=0.0.0.0:40000` or `--network.server_addr 0.0.0.0:40000`
+//!
+//! Every value is checked against the key it sets, errors name the key, the value and the
+//! layer it came from. Load and [NodeConfig::install] the configuration once at startup,
+//! the modules then build their own settings from [node_config].
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use serde_json;
+use serde_json::Value;
+
+use address::*;
+use hash::*;
+
+/// File read when neither `--config` nor `GEN_CONFIG` is given, it may be missing
+pub const DEFAULT_CONFIG_PATH: &'static str = "config/application.json";
+
+/// Prefix of the environment variables read
+pub const ENV_PREFIX: &'static str = "GEN_";
+
+const CONFIG_FLAG: &'static str = "config";
+
+/// Keys in the order they are applied, the chain settings come first
+/// since parsing addresses depends on them
+const KEYS: [&'static str; 22] = [
+    "chain.testnet",
+    "chain.address_version",
+    "chain.hash_algorithm",
+    "account.keystore",
+    "account.address",
+    "network.server_addr",
+    "network.event_queue_size",
+    "network.max_allowed_peers",
+    "network.max_blocklist_size",
+    "network.max_waitinglist_size",
+    "network.min_required_peer",
+    "network.update_timebase",
+    "network.connect_timeout",
+    "network.peer_expire",
+    "network.bootstrap_peers",
+    "db.path",
+    "db.create_if_missing",
+    "db.max_open_files",
+    "vm.time_limit",
+    "vm.max_stack_size",
+    "consensus.server_addr",
+    "consensus.events_size"
+];
+
+/// Layer a value was taken from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    Env(String),
+    Flag
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::File(ref path) => write!(f, "file {}", path.display()),
+            Source::Env(ref name) => write!(f, "environment variable {}", name),
+            Source::Flag => write!(f, "command line")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// the config file could not be read
+    IO(PathBuf, io::ErrorKind),
+    /// the config file is not a JSON object
+    Format(PathBuf, String),
+    UnknownKey(String, Source),
+    /// a flag without its value
+    MissingValue(String),
+    InvalidValue { key: String, value: String, source: Source, reason: String },
+    /// settings which are valid alone but not together
+    Inconsistent(String)
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::IO(ref path, ref kind) => write!(f, "can not read {}: {:?}", path.display(), kind),
+            ConfigError::Format(ref path, ref why) => write!(f, "malformed config file {}: {}", path.display(), why),
+            ConfigError::UnknownKey(ref key, ref source) => write!(f, "unknown setting {} in {}", key, source),
+            ConfigError::MissingValue(ref flag) => write!(f, "flag --{} needs a value", flag),
+            ConfigError::InvalidValue { ref key, ref value, ref source, ref reason } => {
+                write!(f, "{} = {} from {}: {}", key, value, source, reason)
+            },
+            ConfigError::Inconsistent(ref why) => write!(f, "inconsistent settings: {}", why)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSettings {
+    pub testnet: bool,
+    /// version byte of textual addresses, follows `testnet` unless set explicitly
+    pub address_version: u8,
+    pub hash_algorithm: HashAlgorithm
+}
+
+/// Identity of the node, see [Address::load]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSettings {
+    /// keystore directory holding the key of the node
+    pub keystore: PathBuf,
+    /// key to use, needed only when the keystore holds several
+    pub address: Option<Address>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkSettings {
+    pub server_addr: SocketAddr,
+    pub event_queue_size: usize,
+    pub max_allowed_peers: usize,
+    pub max_blocklist_size: usize,
+    pub max_waitinglist_size: usize,
+    pub min_required_peer: usize,
+    /// milliseconds
+    pub update_timebase: i64,
+    /// milliseconds
+    pub connect_timeout: i64,
+    /// milliseconds
+    pub peer_expire: i64,
+    /// `addr` or `account@addr`
+    pub bootstrap_peers: Vec<(Option<Address>, SocketAddr)>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DBSettings {
+    pub path: PathBuf,
+    pub create_if_missing: bool,
+    /// -1 keeps every file open
+    pub max_open_files: i32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VMSettings {
+    /// milliseconds a call may run
+    pub time_limit: usize,
+    /// nested contract calls
+    pub max_stack_size: usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsensusSettings {
+    pub server_addr: SocketAddr,
+    pub events_size: usize
+}
+
+/// # NodeConfig
+/// **Usage**
+/// - `NodeConfig::load(env::args().skip(1))` at startup, then [NodeConfig::install]
+/// ## Examples
+/// ```
+/// use common::node_config::NodeConfig;
+///
+/// let env = vec![("GEN_NETWORK_MAX_ALLOWED_PEERS".to_string(), "64".to_string())];
+/// let args = vec!["--network.max_allowed_peers=128".to_string()];
+/// let config = NodeConfig::load_with(env, args).unwrap();
+/// assert_eq!(config.network.max_allowed_peers, 128);
+///
+/// let wrong = NodeConfig::load_with(vec![], vec!["--network.max_allowed_peers=many".to_string()]);
+/// assert_eq!(wrong.unwrap_err().to_string(),
+///            "network.max_allowed_peers = many from command line: expected an unsigned integer");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeConfig {
+    pub chain: ChainSettings,
+    pub account: AccountSettings,
+    pub network: NetworkSettings,
+    pub db: DBSettings,
+    pub vm: VMSettings,
+    pub consensus: ConsensusSettings
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        NodeConfig {
+            chain: ChainSettings {
+                testnet: false,
+                address_version: MAINNET_ADDRESS_VERSION,
+                hash_algorithm: HashAlgorithm::default()
+            },
+            account: AccountSettings {
+                keystore: PathBuf::from("keystore"),
+                address: None
+            },
+            network: NetworkSettings {
+                server_addr: SocketAddr::from(([0, 0, 0, 0], 40000)),
+                event_queue_size: 1024,
+                max_allowed_peers: 512,
+                max_blocklist_size: 1024,
+                max_waitinglist_size: 1024,
+                min_required_peer: 5,
+                update_timebase: 3000,
+                connect_timeout: 3000,
+                peer_expire: 60000,
+                bootstrap_peers: vec![]
+            },
+            db: DBSettings {
+                path: PathBuf::from("rocksdb/dir"),
+                create_if_missing: false,
+                max_open_files: 32
+            },
+            vm: VMSettings {
+                time_limit: 1000,
+                max_stack_size: 16
+            },
+            consensus: ConsensusSettings {
+                server_addr: SocketAddr::from(([0, 0, 0, 0], 40100)),
+                events_size: 1024
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref NODE_CONFIG: RwLock<Option<NodeConfig>> = RwLock::new(None);
+}
+
+/// Installed configuration, the defaults until [NodeConfig::install] is called
+pub fn node_config() -> NodeConfig {
+    NODE_CONFIG.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Settings collected from the layers, a later layer replaces the value of an earlier one
+struct Layers {
+    values: BTreeMap<String, (Value, Source)>
+}
+
+impl Layers {
+    fn set(&mut self, key: String, value: Value, source: Source) -> Result<(), ConfigError> {
+        if !KEYS.contains(&key.as_str()) {
+            return Err(ConfigError::UnknownKey(key, source));
+        }
+        self.values.insert(key, (value, source));
+        Ok(())
+    }
+
+    fn merge_file(&mut self, path: &Path, required: bool) -> Result<(), ConfigError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(()),
+            Err(e) => return Err(ConfigError::IO(path.to_path_buf(), e.kind()))
+        };
+        let json: Value = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::Format(path.to_path_buf(), e.to_string()))?;
+        if !json.is_object() {
+            return Err(ConfigError::Format(path.to_path_buf(), "expected an object".to_string()));
+        }
+        let mut leaves: Vec<(String, Value)> = vec![];
+        flatten("", json, &mut leaves);
+        for (key, value) in leaves {
+            self.set(key, value, Source::File(path.to_path_buf()))?;
+        }
+        Ok(())
+    }
+
+    /// Variables of unknown keys are ignored, the prefix is not reserved to this node
+    fn merge_env(&mut self, env: &BTreeMap<String, String>) {
+        for key in KEYS.iter() {
+            let name = env_name(key);
+            if let Some(value) = env.get(&name) {
+                self.values.insert(key.to_string(), (Value::String(value.to_owned()), Source::Env(name)));
+            }
+        }
+    }
+
+    fn merge_flags(&mut self, flags: Vec<(String, String)>) -> Result<(), ConfigError> {
+        for (key, value) in flags {
+            self.set(key, Value::String(value), Source::Flag)?;
+        }
+        Ok(())
+    }
+}
+
+fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// Dotted keys of the leaves of `value`
+fn flatten(prefix: &str, value: Value, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                let key = if prefix.is_empty() { name } else { format!("{}.{}", prefix, name) };
+                flatten(&key, value, out);
+            }
+        },
+        value => out.push((prefix.to_string(), value))
+    }
+}
+
+/// Split `--key=value` and `--key value`, a boolean key alone means true
+fn parse_flags(args: Vec<String>) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut flags: Vec<(String, String)> = vec![];
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(ConfigError::UnknownKey(arg, Source::Flag));
+        }
+        let flag = arg[2..].to_string();
+        if let Some(pos) = flag.find('=') {
+            flags.push((flag[..pos].to_string(), flag[pos + 1..].to_string()));
+            continue;
+        }
+        let has_value = args.peek().map_or(false, |next| !next.starts_with("--"));
+        if has_value {
+            let value = args.next().unwrap();
+            flags.push((flag, value));
+        } else if flag == "chain.testnet" || flag == "db.create_if_missing" {
+            flags.push((flag, "true".to_string()));
+        } else {
+            return Err(ConfigError::MissingValue(flag));
+        }
+    }
+    Ok(flags)
+}
+
+fn text_of(value: &Value) -> String {
+    match *value {
+        Value::String(ref s) => s.to_owned(),
+        ref other => other.to_string()
+    }
+}
+
+fn parse_number<T: FromStr>(value: &Value) -> Result<T, ()> {
+    match *value {
+        Value::Number(_) | Value::String(_) => text_of(value).trim().parse::<T>().map_err(|_| ()),
+        _ => Err(())
+    }
+}
+
+fn parse_usize(value: &Value) -> Result<usize, String> {
+    match parse_number::<usize>(value) {
+        Ok(0) => Err("must be positive".to_string()),
+        Ok(n) => Ok(n),
+        Err(_) => Err("expected an unsigned integer".to_string())
+    }
+}
+
+fn parse_millis(value: &Value) -> Result<i64, String> {
+    match parse_number::<i64>(value) {
+        Ok(n) if n > 0 => Ok(n),
+        Ok(_) => Err("must be a positive number of milliseconds".to_string()),
+        Err(_) => Err("expected milliseconds".to_string())
+    }
+}
+
+fn parse_bool(value: &Value) -> Result<bool, String> {
+    match *value {
+        Value::Bool(b) => Ok(b),
+        Value::String(ref s) => match s.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(true),
+            "false" | "0" | "no" => Ok(false),
+            _ => Err("expected true or false".to_string())
+        },
+        _ => Err("expected true or false".to_string())
+    }
+}
+
+fn parse_socket_addr(text: &str) -> Result<SocketAddr, String> {
+    SocketAddr::from_str(text.trim()).map_err(|_| format!("{} is not an ip:port address", text.trim()))
+}
+
+fn parse_address_version(value: &Value) -> Result<u8, String> {
+    let text = text_of(value);
+    let text = text.trim();
+    let parsed = if text.starts_with("0x") {
+        u8::from_str_radix(&text[2..], 16)
+    } else {
+        text.parse::<u8>()
+    };
+    parsed.map_err(|_| "expected a byte, e.g. 0x26".to_string())
+}
+
+/// A list in a file, a comma separated string from the environment or a flag
+fn parse_list(value: &Value) -> Result<Vec<String>, String> {
+    match *value {
+        Value::Array(ref items) => items.iter().map(|item| match *item {
+            Value::String(ref s) => Ok(s.to_owned()),
+            _ => Err("expected a list of strings".to_string())
+        }).collect(),
+        Value::String(ref s) => Ok(s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string()).collect()),
+        _ => Err("expected a list".to_string())
+    }
+}
+
+fn parse_peer(text: &str, version: u8) -> Result<(Option<Address>, SocketAddr), String> {
+    match text.find('@') {
+        Some(pos) => {
+            let account = Address::from_str_with_version(&text[..pos], version)
+                .map_err(|e| format!("peer {}: {}", text, e))?;
+            Ok((Some(account), parse_socket_addr(&text[pos + 1..])?))
+        },
+        None => Ok((None, parse_socket_addr(text)?))
+    }
+}
+
+impl NodeConfig {
+    /// # load(1)
+    /// **Usage**
+    /// - merge the defaults, the config file, the process environment and `args`
+    /// **Parameters**
+    /// - 1. ***args***: command line flags without the program name
+    /// **Return**: the validated configuration or the first [[ConfigError]]
+    pub fn load<A: IntoIterator<Item = String>>(args: A) -> Result<Self, ConfigError> {
+        NodeConfig::load_with(env::vars(), args)
+    }
+
+    /// Same as [NodeConfig::load] with the environment given
+    pub fn load_with<E, A>(env: E, args: A) -> Result<Self, ConfigError>
+        where E: IntoIterator<Item = (String, String)>, A: IntoIterator<Item = String> {
+        let env: BTreeMap<String, String> = env.into_iter().collect();
+        let mut flags = parse_flags(args.into_iter().collect())?;
+
+        let flag_path = flags.iter().position(|flag| flag.0 == CONFIG_FLAG).map(|pos| flags.remove(pos).1);
+        let env_path = env.get(&env_name(CONFIG_FLAG)).cloned();
+        let (path, required) = match flag_path.or(env_path) {
+            Some(path) => (PathBuf::from(path), true),
+            None => (PathBuf::from(DEFAULT_CONFIG_PATH), false)
+        };
+
+        let mut layers = Layers { values: BTreeMap::new() };
+        layers.merge_file(&path, required)?;
+        layers.merge_env(&env);
+        layers.merge_flags(flags)?;
+
+        let mut config = NodeConfig::default();
+        for key in KEYS.iter() {
+            if let Some(&(ref value, ref source)) = layers.values.get(*key) {
+                config.apply(key, value).map_err(|reason| ConfigError::InvalidValue {
+                    key: key.to_string(),
+                    value: text_of(value),
+                    source: source.clone(),
+                    reason: reason
+                })?;
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        match key {
+            "chain.testnet" => {
+                self.chain.testnet = parse_bool(value)?;
+                self.chain.address_version = match self.chain.testnet {
+                    true => TESTNET_ADDRESS_VERSION,
+                    false => MAINNET_ADDRESS_VERSION
+                };
+            },
+            "chain.address_version" => self.chain.address_version = parse_address_version(value)?,
+            "chain.hash_algorithm" => {
+                self.chain.hash_algorithm = HashAlgorithm::from_str(&text_of(value))
+                    .map_err(|_| "expected sha256, keccak256 or blake2b256".to_string())?;
+            },
+            "account.keystore" => {
+                let path = text_of(value);
+                if path.trim().is_empty() {
+                    return Err("must not be empty".to_string());
+                }
+                self.account.keystore = PathBuf::from(path);
+            },
+            "account.address" => {
+                let text = text_of(value);
+                self.account.address = match text.trim() {
+                    "" => None,
+                    text => Some(Address::from_str_with_version(text, self.chain.address_version)
+                        .map_err(|e| e.to_string())?)
+                };
+            },
+            "network.server_addr" => self.network.server_addr = parse_socket_addr(&text_of(value))?,
+            "network.event_queue_size" => self.network.event_queue_size = parse_usize(value)?,
+            "network.max_allowed_peers" => self.network.max_allowed_peers = parse_usize(value)?,
+            "network.max_blocklist_size" => self.network.max_blocklist_size = parse_usize(value)?,
+            "network.max_waitinglist_size" => self.network.max_waitinglist_size = parse_usize(value)?,
+            "network.min_required_peer" => self.network.min_required_peer = parse_usize(value)?,
+            "network.update_timebase" => self.network.update_timebase = parse_millis(value)?,
+            "network.connect_timeout" => self.network.connect_timeout = parse_millis(value)?,
+            "network.peer_expire" => self.network.peer_expire = parse_millis(value)?,
+            "network.bootstrap_peers" => {
+                let version = self.chain.address_version;
+                self.network.bootstrap_peers = parse_list(value)?.iter()
+                    .map(|peer| parse_peer(peer, version))
+                    .collect::<Result<Vec<_>, String>>()?;
+            },
+            "db.path" => {
+                let path = text_of(value);
+                if path.trim().is_empty() {
+                    return Err("must not be empty".to_string());
+                }
+                self.db.path = PathBuf::from(path);
+            },
+            "db.create_if_missing" => self.db.create_if_missing = parse_bool(value)?,
+            "db.max_open_files" => {
+                self.db.max_open_files = match parse_number::<i32>(value) {
+                    Ok(n) if n == -1 || n > 0 => n,
+                    _ => return Err("expected a positive number or -1".to_string())
+                };
+            },
+            "vm.time_limit" => self.vm.time_limit = parse_usize(value)?,
+            "vm.max_stack_size" => self.vm.max_stack_size = parse_usize(value)?,
+            "consensus.server_addr" => self.consensus.server_addr = parse_socket_addr(&text_of(value))?,
+            "consensus.events_size" => self.consensus.events_size = parse_usize(value)?,
+            _ => return Err("unknown setting".to_string())
+        }
+        Ok(())
+    }
+
+    /// Checks between settings, each value was checked alone while loading
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let net = &self.network;
+        if net.min_required_peer > net.max_allowed_peers {
+            return Err(ConfigError::Inconsistent(format!(
+                "network.min_required_peer ({}) exceeds network.max_allowed_peers ({})",
+                net.min_required_peer, net.max_allowed_peers)));
+        }
+        if net.server_addr == self.consensus.server_addr {
+            return Err(ConfigError::Inconsistent(format!(
+                "network.server_addr and consensus.server_addr are both {}", net.server_addr)));
+        }
+        if let Some(peer) = net.bootstrap_peers.iter().find(|peer| peer.1 == net.server_addr) {
+            return Err(ConfigError::Inconsistent(format!(
+                "network.bootstrap_peers lists the node itself at {}", peer.1)));
+        }
+        Ok(())
+    }
+
+    /// Make this the configuration of the process and apply the chain settings
+    pub fn install(self) {
+        set_chain_hash_algorithm(self.chain.hash_algorithm);
+        set_address_version(self.chain.address_version);
+        *NODE_CONFIG.write().unwrap() = Some(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = env::temp_dir().join(format!("gen-node-config-{}.json", name));
+        File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_layers() {
+        let path = temp_file("layers", r#"{
+            "chain": { "testnet": true, "hash_algorithm": "keccak256" },
+            "network": { "max_allowed_peers": 10, "min_required_peer": 2, "bootstrap_peers": ["10.0.0.1:40000"] },
+            "db": { "path": "/var/gen/db" }
+        }"#);
+        let config = NodeConfig::load_with(
+            env(&[("GEN_CONFIG", &path), ("GEN_NETWORK_MAX_ALLOWED_PEERS", "20"), ("GEN_DB_PATH", "/tmp/db")]),
+            args(&["--db.path", "/data/db", "--vm.time_limit=50", "--db.create_if_missing", "--account.keystore=/data/keys"])
+        ).unwrap();
+
+        assert!(config.chain.testnet);
+        assert_eq!(config.chain.address_version, TESTNET_ADDRESS_VERSION);
+        assert_eq!(config.chain.hash_algorithm, HashAlgorithm::Keccak256);
+        assert_eq!(config.network.max_allowed_peers, 20);
+        assert_eq!(config.network.min_required_peer, 2);
+        assert_eq!(config.network.bootstrap_peers, vec![(None, SocketAddr::from(([10, 0, 0, 1], 40000)))]);
+        assert_eq!(config.db.path, PathBuf::from("/data/db"));
+        assert!(config.db.create_if_missing);
+        assert_eq!(config.vm.time_limit, 50);
+        assert_eq!(config.account.keystore, PathBuf::from("/data/keys"));
+        assert_eq!(config.account.address, None);
+        assert_eq!(config.consensus, NodeConfig::default().consensus);
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad = temp_file("unknown", r#"{ "network": { "max_peers": 3 } }"#);
+        assert_eq!(NodeConfig::load_with(vec![], args(&["--config", &bad])),
+                   Err(ConfigError::UnknownKey("network.max_peers".to_string(), Source::File(PathBuf::from(&bad)))));
+
+        let missing = env::temp_dir().join("gen-node-config-missing.json");
+        assert_eq!(NodeConfig::load_with(vec![], args(&["--config", missing.to_str().unwrap()])),
+                   Err(ConfigError::IO(missing, io::ErrorKind::NotFound)));
+
+        let err = NodeConfig::load_with(env(&[("GEN_NETWORK_SERVER_ADDR", "localhost")]), vec![]).unwrap_err();
+        assert_eq!(err.to_string(),
+                   "network.server_addr = localhost from environment variable GEN_NETWORK_SERVER_ADDR: \
+                    localhost is not an ip:port address");
+
+        assert_eq!(NodeConfig::load_with(vec![], args(&["--vm.time_limit"])),
+                   Err(ConfigError::MissingValue("vm.time_limit".to_string())));
+        match NodeConfig::load_with(vec![], args(&["--network.min_required_peer=600"])) {
+            Err(ConfigError::Inconsistent(why)) => assert!(why.contains("network.min_required_peer (600)")),
+            other => panic!("{:?}", other)
+        }
+        match NodeConfig::load_with(vec![], args(&["--network.bootstrap_peers=nobody@10.0.0.1:1"])) {
+            Err(ConfigError::InvalidValue { ref key, .. }) => assert_eq!(key, "network.bootstrap_peers"),
+            other => panic!("{:?}", other)
+        }
+        match NodeConfig::load_with(vec![], args(&["--account.address=nobody"])) {
+            Err(ConfigError::InvalidValue { ref key, .. }) => assert_eq!(key, "account.address"),
+            other => panic!("{:?}", other)
+        }
+    }
+
+    /// Other tests read the chain settings in parallel, only the values they already
+    /// see are installed
+    #[test]
+    fn test_install() {
+        let config = NodeConfig::default();
+        assert_eq!(config.chain.hash_algorithm, chain_hash_algorithm());
+        assert_eq!(config.chain.address_version, address_version());
+        config.clone().install();
+        assert_eq!(node_config(), config);
+        assert_eq!(chain_hash_algorithm(), HashAlgorithm::default());
+        assert_eq!(address_version(), MAINNET_ADDRESS_VERSION);
+    }
+}
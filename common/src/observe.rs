@@ -1,5 +1,7 @@
 use gen_message::*;
 
+use std::sync::{Arc, Mutex, Condvar};
+
 pub trait Observe {
     fn subscribe(&mut self);
 
@@ -8,4 +10,23 @@ pub trait Observe {
     fn receive_async(&mut self) -> Option<Message>;
 
     fn receive_sync(&mut self) -> Message;
-}
\ No newline at end of file
+
+    /// channel taken by `subscribe`, the thread runtime blocks on its condvar
+    fn channel(&self) -> Option<Arc<(Mutex<MessageChannel>, Condvar)>>;
+
+    /// Have the messages published on `topic` delivered to the subscribed channel
+    fn listen<T>(&mut self, topic: &Topic<T>) where Self: Sized {
+        if let Some(ch_pair) = self.channel() {
+            MESSAGE_CENTER
+                .lock()
+                .unwrap()
+                .subscribe_channel(topic.name().to_string(), ch_pair);
+        }
+    }
+
+    /// Next payload of `topic` waiting on the channel, without blocking
+    fn receive<T>(&mut self, topic: &Topic<T>) -> Option<T>
+        where T: Clone + Send + Sync + 'static, Self: Sized {
+        self.channel().and_then(|ch_pair| ch_pair.0.lock().unwrap().accept_typed(topic))
+    }
+}
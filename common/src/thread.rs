@@ -1,64 +1,340 @@
+use std::any::Any;
 use std::io::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::mpsc::{channel, Receiver, Sender, RecvTimeoutError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use observe::*;
-use gen_message::Message;
+use gen_message::{Message, MessageChannel, Priority, Topic};
 
+/// Default period of [Thread::run] in milliseconds
 pub const LOOP_PERIOD: u64 = 100u64;
 
-#[derive(Copy, Clone)]
+/// Messages handled in one go before [Thread::run] gets its turn,
+/// a busy channel must not starve a controller doing socket I/O in `run`
+const MAX_MESSAGES_PER_WAKEUP: usize = 64usize;
+
+/// Status changes sent through the channel of a controller, handled by the runtime
+/// ahead of any other message
+pub const THREAD_CONTROL: Topic<ThreadStatus> = Topic::with_priority("thread-control", Priority::Control);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ThreadStatus {
     Running,
     Stop,
     Pause
 }
 
-pub trait Thread {
-    fn launch<T>(name: String) where T: Observe + Thread, Self: Sized {
-        // TODO: make stack size configuable
-        thread::Builder::new().stack_size(64 * 1024 * 1024).name(name.to_owned()).spawn(move || {
-            let mut context = T::new(name);
-
-            match &mut context {
-                &mut Ok(ref mut context_ref) => {
-                    context_ref.subscribe();
-                    context_ref.set_status(ThreadStatus::Running);
-                    loop {
-                        let ret = context_ref.run();
-                        if let Some(msg) = context_ref.receive_async() {
-                            let forward_msg = msg.clone();
-                            match msg.msg.as_ref() {
-                                "start" => {
-                                    context_ref.set_status(ThreadStatus::Running);
-                                },
-                                "pause" => {
-                                    context_ref.set_status(ThreadStatus::Pause);
-                                },
-                                "stop" => {
-                                    context_ref.set_status(ThreadStatus::Stop);
-                                },
-                                _ => {
-                                    context_ref.msg_handler(forward_msg);
-                                },
+/// How a controller thread ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadExit {
+    /// stopped, or `run` returned false
+    Finished,
+    /// `new` failed and the controller never ran
+    Failed(String),
+    /// the controller panicked
+    Panicked(String)
+}
+
+struct ControlState {
+    status: ThreadStatus,
+    request: Option<ThreadStatus>,
+    channel: Option<Arc<(Mutex<MessageChannel>, Condvar)>>,
+    finished: bool
+}
+
+/// State shared by a controller thread and its [ThreadHandle]
+struct ThreadControl {
+    state: Mutex<ControlState>,
+    cond: Condvar
+}
+
+impl ThreadControl {
+    fn new() -> Self {
+        ThreadControl {
+            state: Mutex::new(ControlState {
+                status: ThreadStatus::Stop,
+                request: None,
+                channel: None,
+                finished: false
+            }),
+            cond: Condvar::new()
+        }
+    }
+
+    fn status(&self) -> ThreadStatus {
+        self.state.lock().unwrap().status
+    }
+
+    fn set_status(&self, status: ThreadStatus) {
+        self.state.lock().unwrap().status = status;
+    }
+
+    fn attach(&self, channel: Option<Arc<(Mutex<MessageChannel>, Condvar)>>) {
+        self.state.lock().unwrap().channel = channel;
+    }
+
+    fn request(&self, status: ThreadStatus) {
+        let channel = {
+            let mut state = self.state.lock().unwrap();
+            state.request = Some(status);
+            state.channel.clone()
+        };
+        self.cond.notify_all();
+        // holding the channel lock makes sure the runtime is either before its
+        // check for work or already waiting, so the wakeup is not lost
+        if let Some(pair) = channel {
+            let _guard = pair.0.lock().unwrap();
+            pair.1.notify_all();
+        }
+    }
+
+    fn take_request(&self) -> Option<ThreadStatus> {
+        self.state.lock().unwrap().request.take()
+    }
+
+    /// Block until a message or a request arrives, or `timeout` passes
+    fn wait(&self, timeout: Option<Duration>) {
+        let channel = self.state.lock().unwrap().channel.clone();
+        match channel {
+            Some(pair) => {
+                let guard = pair.0.lock().unwrap();
+                if guard.queue_size() > 0 || self.state.lock().unwrap().request.is_some() {
+                    return;
+                }
+                match timeout {
+                    Some(timeout) => { drop(pair.1.wait_timeout(guard, timeout).unwrap()); },
+                    None => { drop(pair.1.wait(guard).unwrap()); }
+                }
+            },
+            None => {
+                let state = self.state.lock().unwrap();
+                if state.request.is_some() {
+                    return;
+                }
+                match timeout {
+                    Some(timeout) => { drop(self.cond.wait_timeout(state, timeout).unwrap()); },
+                    None => { drop(self.cond.wait(state).unwrap()); }
+                }
+            }
+        }
+    }
+}
+
+/// Marks the thread finished however it ends, unwinding included
+struct ExitGuard {
+    control: Arc<ThreadControl>,
+    notify: Option<(usize, Sender<usize>)>
+}
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.control.state.lock() {
+            state.status = ThreadStatus::Stop;
+            state.channel = None;
+            state.finished = true;
+        }
+        self.control.cond.notify_all();
+        if let Some((id, ref notify)) = self.notify {
+            let _ = notify.send(id);
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.to_owned()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// # ThreadHandle
+/// **Usage**
+/// - returned by [Thread::launch], controls the controller thread
+/// - `stop`, `pause` and `resume` wake the thread right away
+/// - dropping the handle detaches the thread
+pub struct ThreadHandle {
+    name: String,
+    control: Arc<ThreadControl>,
+    join: Option<thread::JoinHandle<ThreadExit>>,
+    exit: Option<ThreadExit>
+}
+
+impl ThreadHandle {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn status(&self) -> ThreadStatus {
+        self.control.status()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.control.state.lock().unwrap().finished
+    }
+
+    pub fn stop(&self) {
+        self.control.request(ThreadStatus::Stop);
+    }
+
+    /// Stop calling `run`, messages are still handled
+    pub fn pause(&self) {
+        self.control.request(ThreadStatus::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.control.request(ThreadStatus::Running);
+    }
+
+    /// Wait for the thread to end, `None` if it still runs after `timeout`
+    pub fn join_timeout(&mut self, timeout: Duration) -> Option<ThreadExit> {
+        let deadline = Instant::now() + timeout;
+        {
+            let mut state = self.control.state.lock().unwrap();
+            while !state.finished {
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                state = self.control.cond.wait_timeout(state, deadline - now).unwrap().0;
+            }
+        }
+        Some(self.reap())
+    }
+
+    pub fn join(mut self) -> ThreadExit {
+        self.reap()
+    }
+
+    /// Stop the thread and wait at most `timeout` for it
+    pub fn shutdown(&mut self, timeout: Duration) -> Option<ThreadExit> {
+        self.stop();
+        self.join_timeout(timeout)
+    }
+
+    fn reap(&mut self) -> ThreadExit {
+        if let Some(join) = self.join.take() {
+            self.exit = Some(match join.join() {
+                Ok(exit) => exit,
+                Err(payload) => ThreadExit::Panicked(panic_message(&payload))
+            });
+        }
+        self.exit.clone().unwrap_or(ThreadExit::Finished)
+    }
+}
+
+fn update_status<T: Thread>(context: &mut T, control: &ThreadControl, status: ThreadStatus) -> ThreadStatus {
+    context.set_status(status);
+    control.set_status(status);
+    status
+}
+
+/// Handle messages and requests as they come, call `run` every period while running.
+/// At most [MAX_MESSAGES_PER_WAKEUP] messages are handled between two chances to run
+fn event_loop<T: Observe + Thread>(context: &mut T, control: &ThreadControl) {
+    let mut status = update_status(context, control, ThreadStatus::Running);
+    let mut next_run = Instant::now();
+    loop {
+        if let Some(request) = control.take_request() {
+            status = update_status(context, control, request);
+        }
+        let mut handled = 0usize;
+        while status != ThreadStatus::Stop && handled < MAX_MESSAGES_PER_WAKEUP {
+            let msg = match context.receive_async() {
+                Some(msg) => msg,
+                None => break
+            };
+            handled += 1;
+            match THREAD_CONTROL.read(&msg) {
+                Some(request) => {
+                    status = update_status(context, control, request);
+                },
+                None => {
+                    context.msg_handler(msg);
+                }
+            }
+        }
+
+        let timeout = match status {
+            ThreadStatus::Stop => return,
+            ThreadStatus::Pause => None,
+            ThreadStatus::Running => {
+                match context.period() {
+                    Some(period) => {
+                        if Instant::now() >= next_run {
+                            if !context.run() {
+                                return;
                             }
+                            next_run = Instant::now() + period;
                         }
-
-                        if !ret {
-                            break;
+                        let now = Instant::now();
+                        Some(if next_run > now { next_run - now } else { Duration::from_millis(0) })
+                    },
+                    None => {
+                        if !context.run() {
+                            return;
                         }
-                        thread::sleep(Duration::from_millis(LOOP_PERIOD));
+                        None
                     }
-                },
-                &mut Err(ref e) => {
-                    println!("Error: {:?}", e);
                 }
             }
-            let _ = context.and_then(|mut context| {
-                context.unsubscribe();
-                Ok(context)
-            });
-        }).unwrap();
+        };
+        control.wait(timeout);
+    }
+}
+
+fn spawn<T>(name: String, notify: Option<(usize, Sender<usize>)>) -> ThreadHandle
+    where T: Observe + Thread + 'static {
+    let control = Arc::new(ThreadControl::new());
+    let shared = control.clone();
+    let thread_name = name.to_owned();
+    // TODO: make stack size configuable
+    let join = thread::Builder::new().stack_size(64 * 1024 * 1024).name(name.to_owned()).spawn(move || {
+        let _guard = ExitGuard { control: shared.clone(), notify: notify };
+        let mut context = match T::new(thread_name) {
+            Ok(context) => context,
+            Err(e) => return ThreadExit::Failed(format!("{}", e))
+        };
+
+        context.subscribe();
+        shared.attach(context.channel());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| event_loop(&mut context, &shared)));
+        shared.attach(None);
+        context.unsubscribe();
+
+        match result {
+            Ok(_) => ThreadExit::Finished,
+            Err(payload) => ThreadExit::Panicked(panic_message(&payload))
+        }
+    }).unwrap();
+
+    ThreadHandle {
+        name: name,
+        control: control,
+        join: Some(join),
+        exit: None
+    }
+}
+
+pub trait Thread {
+    /// # launch(1)
+    /// **Usage**
+    /// - start the controller `T` on a new thread named `name`
+    /// - the thread sleeps until a message arrives on its channel, a request comes
+    ///   from the handle or the next [Thread::period] is due
+    /// **Return**: [ThreadHandle]
+    fn launch<T>(name: String) -> ThreadHandle where T: Observe + Thread + 'static, Self: Sized {
+        spawn::<T>(name, None)
+    }
+
+    /// Interval between two calls of `run`, `None` calls it only after messages
+    fn period(&self) -> Option<Duration> {
+        Some(Duration::from_millis(LOOP_PERIOD))
     }
 
     /// run loop
@@ -73,3 +349,350 @@ pub trait Thread {
     /// init instance
     fn new(name: String) -> Result<Self> where Self: Sized;
 }
+
+/// Restart limits of a [Supervisor]
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    /// restarts allowed within `period` before a controller is given up
+    pub max_restarts: usize,
+    pub period: Duration
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 3usize,
+            period: Duration::from_secs(60)
+        }
+    }
+}
+
+struct Child {
+    name: String,
+    handle: Option<ThreadHandle>,
+    restarts: Vec<Instant>,
+    relaunch: Box<dyn Fn(String, usize, Sender<usize>) -> ThreadHandle + Send>
+}
+
+/// # Supervisor
+/// **Usage**
+/// - launch controllers with `supervise` and call `watch` from the owning thread
+/// - a controller which panics is launched again, unless it already restarted
+///   `max_restarts` times within the policy period
+/// - controllers which stop or fail in `new` are not restarted
+pub struct Supervisor {
+    policy: RestartPolicy,
+    children: Vec<Child>,
+    notify: Sender<usize>,
+    exits: Receiver<usize>
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        let (notify, exits) = channel();
+        Supervisor {
+            policy: policy,
+            children: vec![],
+            notify: notify,
+            exits: exits
+        }
+    }
+
+    /// Launch `T` under supervision, returns its id
+    pub fn supervise<T>(&mut self, name: String) -> usize where T: Observe + Thread + 'static {
+        let id = self.children.len();
+        let relaunch = |name: String, id: usize, notify: Sender<usize>| spawn::<T>(name, Some((id, notify)));
+        self.children.push(Child {
+            name: name.to_owned(),
+            handle: Some(relaunch(name, id, self.notify.clone())),
+            restarts: vec![],
+            relaunch: Box::new(relaunch)
+        });
+        id
+    }
+
+    /// Handle of a running controller
+    pub fn handle(&self, id: usize) -> Option<&ThreadHandle> {
+        self.children.get(id).and_then(|child| child.handle.as_ref())
+    }
+
+    /// Number of controllers still running
+    pub fn running(&self) -> usize {
+        self.children.iter().filter(|child| child.handle.is_some()).count()
+    }
+
+    /// Wait up to `timeout` for controllers to end and restart the ones which panicked.
+    /// Returns the name and exit of every controller which ended.
+    pub fn watch(&mut self, timeout: Duration) -> Vec<(String, ThreadExit)> {
+        let mut ended: Vec<usize> = vec![];
+        match self.exits.recv_timeout(timeout) {
+            Ok(id) => ended.push(id),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return vec![]
+        }
+        while let Ok(id) = self.exits.try_recv() {
+            ended.push(id);
+        }
+
+        let mut exits: Vec<(String, ThreadExit)> = vec![];
+        for id in ended {
+            let policy = self.policy;
+            let notify = self.notify.clone();
+            let child = &mut self.children[id];
+            let exit = match child.handle.take() {
+                Some(handle) => handle.join(),
+                None => continue
+            };
+            if let ThreadExit::Panicked(_) = exit {
+                let now = Instant::now();
+                child.restarts.retain(|at| now.duration_since(*at) < policy.period);
+                if child.restarts.len() < policy.max_restarts {
+                    child.restarts.push(now);
+                    child.handle = Some((child.relaunch)(child.name.to_owned(), id, notify));
+                }
+            }
+            exits.push((child.name.to_owned(), exit));
+        }
+        exits
+    }
+
+    /// Stop every controller and wait at most `timeout` for all of them.
+    /// Returns false if some are still running.
+    pub fn shutdown(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        for child in &self.children {
+            if let Some(ref handle) = child.handle {
+                handle.stop();
+            }
+        }
+        let mut done = true;
+        for child in &mut self.children {
+            let now = Instant::now();
+            let left = if deadline > now { deadline - now } else { Duration::from_millis(0) };
+            let ended = match child.handle {
+                Some(ref mut handle) => handle.join_timeout(left).is_some(),
+                None => true
+            };
+            if ended {
+                child.handle = None;
+            } else {
+                done = false;
+            }
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gen_message::MESSAGE_CENTER;
+
+    #[derive(Clone)]
+    enum ProbeCommand {
+        Echo(u16),
+        Panic
+    }
+
+    const PROBE: Topic<ProbeCommand> = Topic::new("probe");
+
+    struct Probe {
+        name: String,
+        ch_pair: Option<Arc<(Mutex<MessageChannel>, Condvar)>>
+    }
+
+    impl Observe for Probe {
+        fn subscribe(&mut self) {
+            self.ch_pair = Some(MESSAGE_CENTER.lock().unwrap().subscribe(self.name.to_owned()));
+        }
+
+        fn unsubscribe(&mut self) {
+            if let Some(ch_pair) = self.ch_pair.take() {
+                let uid = ch_pair.0.lock().unwrap().uid.clone();
+                MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+            }
+        }
+
+        fn receive_async(&mut self) -> Option<Message> {
+            self.ch_pair.as_ref().and_then(|ch_pair| ch_pair.0.lock().unwrap().accept_msg_async())
+        }
+
+        fn receive_sync(&mut self) -> Message {
+            let ch_pair = self.ch_pair.clone().expect("No channel subscribed");
+            let (ref lock, ref cvar) = *ch_pair;
+            let mut channel = lock.lock().unwrap();
+            loop {
+                if let Some(msg) = channel.accept_msg_async() {
+                    return msg;
+                }
+                channel = cvar.wait(channel).unwrap();
+            }
+        }
+
+        fn channel(&self) -> Option<Arc<(Mutex<MessageChannel>, Condvar)>> {
+            self.ch_pair.clone()
+        }
+    }
+
+    impl Thread for Probe {
+        fn new(name: String) -> Result<Self> {
+            if name.ends_with("broken") {
+                return Err(Error::new(ErrorKind::Other, "no config"));
+            }
+            Ok(Probe { name: name, ch_pair: None })
+        }
+
+        // only messages wake the probe up
+        fn period(&self) -> Option<Duration> {
+            None
+        }
+
+        fn run(&mut self) -> bool {
+            true
+        }
+
+        fn msg_handler(&mut self, msg: Message) {
+            match PROBE.read(&msg) {
+                Some(ProbeCommand::Echo(op)) => {
+                    MESSAGE_CENTER.lock().unwrap().send(format!("{}-reply", self.name), Message::new(op, "echo".to_string()));
+                },
+                Some(ProbeCommand::Panic) => panic!("probe panicked"),
+                None => {}
+            }
+        }
+
+        fn set_status(&mut self, _status: ThreadStatus) {}
+    }
+
+    fn wait_subscribed(name: &str, subscribed: bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while MESSAGE_CENTER.lock().unwrap().channels_exist_by_name(name.to_string()) != subscribed {
+            assert!(Instant::now() < deadline, "{} never changed subscription", name);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn receive(ch_pair: &Arc<(Mutex<MessageChannel>, Condvar)>, timeout: Duration) -> Option<Message> {
+        let deadline = Instant::now() + timeout;
+        let mut guard = ch_pair.0.lock().unwrap();
+        loop {
+            if let Some(msg) = guard.accept_msg_async() {
+                return Some(msg);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            guard = ch_pair.1.wait_timeout(guard, deadline - now).unwrap().0;
+        }
+    }
+
+    #[test]
+    fn test_wakeup_and_stop() {
+        let reply = MESSAGE_CENTER.lock().unwrap().subscribe("probe-wake-reply".to_string());
+        let mut handle = Probe::launch::<Probe>("probe-wake".to_string());
+        wait_subscribed("probe-wake", true);
+        assert_eq!(handle.status(), ThreadStatus::Running);
+
+        MESSAGE_CENTER.lock().unwrap().send_typed("probe-wake".to_string(), &PROBE, ProbeCommand::Echo(7));
+        assert_eq!(receive(&reply, Duration::from_secs(5)).map(|msg| msg.op), Some(7));
+
+        handle.pause();
+        handle.resume();
+        assert_eq!(handle.join_timeout(Duration::from_millis(10)), None);
+        MESSAGE_CENTER.lock().unwrap().send_typed("probe-wake".to_string(), &THREAD_CONTROL, ThreadStatus::Stop);
+        assert_eq!(handle.join_timeout(Duration::from_secs(5)), Some(ThreadExit::Finished));
+        assert_eq!(handle.status(), ThreadStatus::Stop);
+        wait_subscribed("probe-wake", false);
+    }
+
+    /// A channel which is never empty
+    struct Flooded {
+        handled: usize,
+        runs: usize
+    }
+
+    impl Observe for Flooded {
+        fn subscribe(&mut self) {}
+
+        fn unsubscribe(&mut self) {}
+
+        fn receive_async(&mut self) -> Option<Message> {
+            Some(Message::new(1, "flood".to_string()))
+        }
+
+        fn receive_sync(&mut self) -> Message {
+            Message::new(1, "flood".to_string())
+        }
+
+        fn channel(&self) -> Option<Arc<(Mutex<MessageChannel>, Condvar)>> {
+            None
+        }
+    }
+
+    impl Thread for Flooded {
+        fn new(_name: String) -> Result<Self> {
+            Ok(Flooded { handled: 0, runs: 0 })
+        }
+
+        fn period(&self) -> Option<Duration> {
+            Some(Duration::from_millis(0))
+        }
+
+        fn run(&mut self) -> bool {
+            self.runs += 1;
+            self.runs < 3
+        }
+
+        fn msg_handler(&mut self, _msg: Message) {
+            self.handled += 1;
+        }
+
+        fn set_status(&mut self, _status: ThreadStatus) {}
+    }
+
+    #[test]
+    fn test_run_under_load() {
+        let mut flooded = Flooded::new("flooded".to_string()).unwrap();
+        event_loop(&mut flooded, &ThreadControl::new());
+        assert_eq!(flooded.runs, 3);
+        assert_eq!(flooded.handled, 3 * MAX_MESSAGES_PER_WAKEUP);
+    }
+
+    #[test]
+    fn test_receive_sync() {
+        let mut probe = Probe::new("probe-sync".to_string()).unwrap();
+        probe.subscribe();
+        let sender = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            MESSAGE_CENTER.lock().unwrap().send("probe-sync".to_string(), Message::new(3, "sync".to_string()));
+        });
+        assert_eq!(probe.receive_sync().op, 3);
+        sender.join().unwrap();
+        probe.unsubscribe();
+    }
+
+    #[test]
+    fn test_failed_init() {
+        let handle = Probe::launch::<Probe>("probe-broken".to_string());
+        assert_eq!(handle.join(), ThreadExit::Failed("no config".to_string()));
+    }
+
+    #[test]
+    fn test_supervisor_restart() {
+        let mut supervisor = Supervisor::new(RestartPolicy { max_restarts: 1, period: Duration::from_secs(60) });
+        supervisor.supervise::<Probe>("probe-restart".to_string());
+        wait_subscribed("probe-restart", true);
+
+        MESSAGE_CENTER.lock().unwrap().send_typed("probe-restart".to_string(), &PROBE, ProbeCommand::Panic);
+        assert_eq!(supervisor.watch(Duration::from_secs(5)),
+                   vec![("probe-restart".to_string(), ThreadExit::Panicked("probe panicked".to_string()))]);
+        assert_eq!(supervisor.running(), 1);
+        wait_subscribed("probe-restart", true);
+
+        // the second panic is over the limit
+        MESSAGE_CENTER.lock().unwrap().send_typed("probe-restart".to_string(), &PROBE, ProbeCommand::Panic);
+        assert_eq!(supervisor.watch(Duration::from_secs(5)).len(), 1);
+        assert_eq!(supervisor.running(), 0);
+        assert!(supervisor.shutdown(Duration::from_secs(5)));
+    }
+}
@@ -1,19 +1,25 @@
 use std::fmt;
+use authority::{Authority, AuthorityError};
 use common::hash::*;
 use common::address::*;
 use storage::{Storage, CHUNK};
+use transaction::Transaction;
 use std::cell::{RefCell, Cell};
-use rlp::RLPSerialize;
-use rlp::types::*;
+use rlp::types::RLPError;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RLPSerialize)]
 pub struct Account {
     balance: u32,
+    /// nonce the next transaction of the account must carry
+    nonce: u64,
     name: String,
     storage_root: Hash,
     storage: Storage,
     code_hash: Hash,
-    address_hash: RefCell<Option<Address>>
+    #[rlp(skip)]
+    address_hash: RefCell<Option<Address>>,
+    /// `None` for a plain account controlled by the key of its address
+    authority: Option<Authority>
 }
 
 impl Account {
@@ -21,11 +27,13 @@ impl Account {
         // TODO: check account name  
         Account{
             balance: 0u32,
+            nonce: 0u64,
             name: account_name.to_string(),
             storage_root: zero_hash!(),
             storage,
             code_hash: zero_hash!(),
-            address_hash: RefCell::new(None)
+            address_hash: RefCell::new(None),
+            authority: None
         }
     }
 
@@ -38,14 +46,23 @@ impl Account {
         self.balance
     }
 
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Address of the account, the one its storage belongs to
+    pub fn address(&self) -> Address {
+        self.address_hash.borrow_mut().get_or_insert_with(|| self.storage.address().clone()).clone()
+    }
+
     /// Get the storage of the account.
     pub fn storage(&self) -> Storage  {
         self.storage.to_owned()
     }
 
     /// set the value of the trie's storage with provided `key`.
-    pub fn set_storage(&mut self, key: Hash, val: CHUNK)  {
-        self.storage.update(key, val);
+    pub fn set_storage(&mut self, key: Hash, val: CHUNK) -> Result<(), RLPError> {
+        self.storage.update(key, val)
     }
 
     /// Increase account balance.
@@ -53,6 +70,31 @@ impl Account {
         self.balance = self.balance + x;
     }
 
+    /// Keys which may act for this account, the key of its address unless replaced
+    pub fn authority(&self) -> Authority {
+        match self.authority {
+            Some(ref authority) => authority.clone(),
+            None => Authority::single(self.address())
+        }
+    }
+
+    /// Replace the authority with the one carried by `tx`.
+    /// `tx` must be sent by this account with its next nonce, carry an authority,
+    /// and satisfy the current authority. The nonce is consumed on success.
+    pub fn update_authority(&mut self, tx: &Transaction) -> Result<(), AuthorityError> {
+        if *tx.sender() != self.address() {
+            return Err(AuthorityError::WrongSender);
+        }
+        if tx.nonce() != self.nonce {
+            return Err(AuthorityError::WrongNonce { expected: self.nonce, found: tx.nonce() });
+        }
+        let authority = tx.new_authority().ok_or(AuthorityError::Mismatch)?;
+        tx.verify_authority(&self.authority())?;
+        self.authority = Some(authority.clone());
+        self.nonce = self.nonce + 1;
+        Ok(())
+    }
+
     /// Decrease account balance.
     /// TODO: when balance is less than x
     pub fn sub_balance(&mut self, x: u32) {
@@ -61,29 +103,88 @@ impl Account {
     }
 }
 
-impl RLPSerialize for Account {
-    fn serialize(&self) -> Result<RLP, RLPError> {
-        unimplemented!()
-    }
-
-    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        unimplemented!()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use authority::KeyWeight;
+    use rlp::RLPSerialize;
+    use common::key::{KeyPair, SignatureScheme};
+
+    fn keys(n: usize) -> Vec<KeyPair> {
+        (0..n).map(|_| <KeyPair as SignatureScheme>::generate().unwrap().0).collect()
+    }
+
+    fn signed(tx: Box<Transaction>, signers: &[&KeyPair]) -> Transaction {
+        let mut tx = *tx;
+        let payload = tx.signing_payload().unwrap();
+        for key in signers {
+            tx.add_signature(SignatureScheme::sign(*key, &payload));
+        }
+        tx
+    }
 
     #[test]
     fn new_account() {
-        //let account = Account::new("test", Storage::new());
-        //assert_eq!(account.balance, 0u32);
+        let account = Account::new("test", Storage::new(Address::from([1u8; 32])));
+        assert_eq!(account.balance, 0u32);
+        assert_eq!(account.nonce(), 0u64);
+        assert_eq!(account.address(), Address::from([1u8; 32]));
     }
 
     #[test]
     fn fmt() {
-        //println!("{:?}", Account::new("test", Storage::new()));
+        println!("{:?}", Account::new("test", Storage::new(Address::from([1u8; 32]))));
     }
 
+    #[test]
+    fn test_authority() {
+        let owner = keys(1).remove(0);
+        let addr = SignatureScheme::address(&owner);
+        let mut account = Account::new("test", Storage::new(addr.clone()));
+        assert_eq!(account.authority(), Authority::single(addr.clone()));
+
+        let council = keys(2);
+        let authority = Authority::new(2, council.iter().map(|k| KeyWeight::new(SignatureScheme::address(k), 1)).collect()).unwrap();
+        let tx = signed(Transaction::change_authority(0, addr.clone(), authority.clone(), 21000, None), &[&owner]);
+        assert_eq!(account.update_authority(&tx), Ok(()));
+        assert_eq!(account.authority(), authority);
+        assert_eq!(account.nonce(), 1);
+
+        // the old key alone no longer acts for the account
+        let back = Authority::single(addr.clone());
+        let tx = signed(Transaction::change_authority(1, addr.clone(), back.clone(), 21000, None), &[&owner]);
+        assert_eq!(account.update_authority(&tx), Err(AuthorityError::IrrelevantSignature(0)));
+        let tx = signed(Transaction::change_authority(1, addr.clone(), back.clone(), 21000, None), &[&council[0], &council[1]]);
+        assert_eq!(account.update_authority(&tx), Ok(()));
+        assert_eq!(account.authority(), back);
+
+        // the authority is persisted with the account
+        let decoded = Account::deserialize(&account.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.authority(), back);
+        assert_eq!(decoded.nonce(), 2);
+        assert_eq!(decoded.address(), addr);
+    }
+
+    #[test]
+    fn test_update_authority_refused() {
+        let owner = keys(1).remove(0);
+        let addr = SignatureScheme::address(&owner);
+        let mut account = Account::new("test", Storage::new(addr.clone()));
+        let authority = Authority::single(Address::from([2u8; 32]));
+
+        // sent by another account, signed by the owner
+        let other = Address::from([3u8; 32]);
+        let tx = signed(Transaction::change_authority(0, other, authority.clone(), 21000, None), &[&owner]);
+        assert_eq!(account.update_authority(&tx), Err(AuthorityError::WrongSender));
+
+        // a plain transaction carries no authority
+        let plain = signed(Transaction::new(0, addr.clone(), addr.clone(), None, 21000, None, &vec![]), &[&owner]);
+        assert_eq!(account.update_authority(&plain), Err(AuthorityError::Mismatch));
+
+        // replayed once applied
+        let tx = signed(Transaction::change_authority(0, addr.clone(), authority.clone(), 21000, None), &[&owner]);
+        assert_eq!(account.update_authority(&tx), Ok(()));
+        assert_eq!(account.authority(), authority);
+        assert_eq!(account.update_authority(&tx), Err(AuthorityError::WrongNonce { expected: 1, found: 0 }));
+    }
 }
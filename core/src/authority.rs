@@ -0,0 +1,206 @@
+use common::address::Address;
+use common::key::{verify_signature, TaggedSignature};
+use rlp::types::*;
+
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum AuthorityError {
+    /// the summed weight of the signers stays below the threshold
+    Unsatisfied { weight: u32, threshold: u32 },
+    /// a signature does not belong to any remaining key of the authority
+    IrrelevantSignature(usize),
+    /// threshold of zero, duplicated keys or a threshold no set of keys can reach
+    Invalid(&'static str),
+    /// the transaction is not sent by the account it changes
+    WrongSender,
+    /// the transaction nonce is not the next one of the account, a replay or a gap
+    WrongNonce { expected: u64, found: u64 },
+    /// the transaction carries no authority to install
+    Mismatch,
+    Encoding(RLPError)
+}
+
+impl fmt::Display for AuthorityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AuthorityError::Unsatisfied { weight, threshold } => {
+                write!(f, "signatures weigh {} out of a threshold of {}", weight, threshold)
+            },
+            AuthorityError::IrrelevantSignature(index) => write!(f, "signature {} matches no key", index),
+            AuthorityError::Invalid(why) => write!(f, "invalid authority: {}", why),
+            AuthorityError::WrongSender => write!(f, "transaction is sent by another account"),
+            AuthorityError::WrongNonce { expected, found } => {
+                write!(f, "transaction nonce is {}, expected {}", found, expected)
+            },
+            AuthorityError::Mismatch => write!(f, "transaction carries no authority"),
+            AuthorityError::Encoding(ref e) => write!(f, "can not encode signed payload: {}", e)
+        }
+    }
+}
+
+/// A key and the weight its signature counts for
+#[derive(Debug, Clone, PartialEq, Eq, RLPSerialize)]
+pub struct KeyWeight {
+    pub key: Address,
+    pub weight: u16
+}
+
+impl KeyWeight {
+    pub fn new(key: Address, weight: u16) -> Self {
+        KeyWeight {
+            key: key,
+            weight: weight
+        }
+    }
+}
+
+/// # Authority
+/// **Usage**
+/// - who may act for an account, a weighted set of keys plus a threshold
+/// - a set of signatures satisfies it when every signature comes from a distinct key
+///   of the set and their weights add up to the threshold
+/// - keys are kept sorted, so equal authorities encode to the same bytes,
+///   a decoded authority must be valid and in that order
+#[derive(Debug, Clone, PartialEq, Eq, RLPSerialize)]
+#[rlp(check = "Authority::check_decoded")]
+pub struct Authority {
+    threshold: u32,
+    keys: Vec<KeyWeight>
+}
+
+impl Authority {
+    pub fn new(threshold: u32, mut keys: Vec<KeyWeight>) -> Result<Self, AuthorityError> {
+        if threshold == 0 {
+            return Err(AuthorityError::Invalid("zero threshold"));
+        }
+        keys.sort_by(|a, b| a.key.cmp(&b.key));
+        if keys.windows(2).any(|pair| pair[0].key == pair[1].key) {
+            return Err(AuthorityError::Invalid("duplicated key"));
+        }
+        if keys.iter().any(|k| k.weight == 0) {
+            return Err(AuthorityError::Invalid("zero weight"));
+        }
+        let total: u32 = keys.iter().map(|k| k.weight as u32).sum();
+        if total < threshold {
+            return Err(AuthorityError::Invalid("threshold above the total weight"));
+        }
+        Ok(Authority {
+            threshold: threshold,
+            keys: keys
+        })
+    }
+
+    /// A single key with a threshold of one, the authority of a plain account
+    pub fn single(key: Address) -> Self {
+        Authority {
+            threshold: 1u32,
+            keys: vec![KeyWeight::new(key, 1u16)]
+        }
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn keys(&self) -> &[KeyWeight] {
+        &self.keys
+    }
+
+    /// Summed weight of the keys which signed `msg`.
+    /// Every signature must match a key, and a key is only counted once.
+    pub fn weight_of(&self, msg: &[u8], sigs: &[TaggedSignature]) -> Result<u32, AuthorityError> {
+        let mut used = vec![false; self.keys.len()];
+        let mut weight = 0u32;
+        for (index, sig) in sigs.iter().enumerate() {
+            let matched = (0..self.keys.len())
+                .find(|i| !used[*i] && verify_signature(&self.keys[*i].key, msg, sig));
+            match matched {
+                Some(i) => {
+                    used[i] = true;
+                    weight = weight + self.keys[i].weight as u32;
+                },
+                None => return Err(AuthorityError::IrrelevantSignature(index))
+            }
+        }
+        Ok(weight)
+    }
+
+    /// Only what `new` builds may be decoded, in the canonical order
+    fn check_decoded(&self) -> Result<(), RLPError> {
+        match Authority::new(self.threshold, self.keys.clone()) {
+            Ok(ref canonical) if canonical == self => Ok(()),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+
+    pub fn check(&self, msg: &[u8], sigs: &[TaggedSignature]) -> Result<(), AuthorityError> {
+        let weight = self.weight_of(msg, sigs)?;
+        if weight < self.threshold {
+            return Err(AuthorityError::Unsatisfied { weight: weight, threshold: self.threshold });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp::RLPSerialize;
+    use common::key::{KeyPair, SignatureScheme};
+    use common::ecdsa::Secp256k1KeyPair;
+
+    fn treasury() -> (Vec<KeyPair>, Secp256k1KeyPair, Authority) {
+        let keys: Vec<KeyPair> = (0..3).map(|_| <KeyPair as SignatureScheme>::generate().unwrap().0).collect();
+        let (hsm, _) = Secp256k1KeyPair::generate().unwrap();
+        let authority = Authority::new(4, vec![
+            KeyWeight::new(SignatureScheme::address(&keys[0]), 1),
+            KeyWeight::new(SignatureScheme::address(&keys[1]), 1),
+            KeyWeight::new(SignatureScheme::address(&keys[2]), 2),
+            KeyWeight::new(hsm.address(), 3)
+        ]).unwrap();
+        (keys, hsm, authority)
+    }
+
+    #[test]
+    fn test_threshold() {
+        let (keys, hsm, authority) = treasury();
+        let msg = b"pay 100";
+        let sign = |i: usize| SignatureScheme::sign(&keys[i], msg);
+
+        assert_eq!(authority.check(msg, &[sign(0), sign(2)]),
+                   Err(AuthorityError::Unsatisfied { weight: 3, threshold: 4 }));
+        assert_eq!(authority.check(msg, &[sign(0), sign(1), sign(2)]), Ok(()));
+        assert_eq!(authority.check(msg, &[hsm.sign(msg), sign(1)]), Ok(()));
+
+        // the same key twice counts once and the second copy matches nothing
+        assert_eq!(authority.check(msg, &[sign(2), sign(2)]), Err(AuthorityError::IrrelevantSignature(1)));
+        // signature over another message
+        assert_eq!(authority.check(msg, &[sign(0), SignatureScheme::sign(&keys[2], b"pay 1000"), sign(1)]),
+                   Err(AuthorityError::IrrelevantSignature(1)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (key, _) = <KeyPair as SignatureScheme>::generate().unwrap();
+        let addr = SignatureScheme::address(&key);
+        assert!(Authority::new(0, vec![KeyWeight::new(addr.clone(), 1)]).is_err());
+        assert!(Authority::new(3, vec![KeyWeight::new(addr.clone(), 2)]).is_err());
+        assert!(Authority::new(1, vec![KeyWeight::new(addr.clone(), 1), KeyWeight::new(addr.clone(), 1)]).is_err());
+        assert!(Authority::new(1, vec![KeyWeight::new(addr.clone(), 0), KeyWeight::new(Address::from([1u8; 32]), 1)]).is_err());
+        assert_eq!(Authority::single(addr.clone()), Authority::new(1, vec![KeyWeight::new(addr, 1)]).unwrap());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let (_, _, authority) = treasury();
+        let rlp = authority.serialize().unwrap();
+        assert_eq!(Authority::deserialize(&rlp), Ok(authority.clone()));
+
+        // keys out of order are refused
+        let mut keys = authority.keys().to_vec();
+        keys.reverse();
+        let reordered = RLP::RLPList(vec![authority.threshold().serialize().unwrap(), keys.serialize().unwrap()]);
+        assert_eq!(Authority::deserialize(&reordered), Err(RLPError::RLPErrorType));
+    }
+}
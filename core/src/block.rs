@@ -1,8 +1,11 @@
 use common::hash::*;
 use common::address::*;
+use common::bloom::Bloom;
+use common::merkle::merkle_root_of;
 use num::bigint::BigInt;
-use rlp::RLPSerialize;
 use rlp::types::*;
+use receipt::Receipt;
+use transaction::Transaction;
 
 pub mod nounce {
     /// A BlockNonce is a 64-bit hash which proves (combined with the
@@ -15,7 +18,7 @@ pub mod nounce {
 ///
 ///
 ///
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, RLPSerialize)]
 pub struct Block {
     pub parent: Hash,
     pub uncle: Hash,
@@ -23,7 +26,7 @@ pub struct Block {
     pub root: Hash,
     pub tx_root: Hash,
     pub receipt_root: Hash,
-    //pub logs_bloom: Bloom<Log>,
+    pub logs_bloom: Bloom,
     pub difficulty: BigInt,
     pub number: BigInt,
     pub gas_used: u64,
@@ -33,18 +36,128 @@ pub struct Block {
     pub nounce: nounce::BlockNounce
 }
 
-impl RLPSerialize for Block {
-    fn serialize(&self) -> Result<RLP, RLPError> {
-        unimplemented!()
+impl Block {
+    /// Attach the `receipts` of the block's transactions, in block order.
+    /// `receipt_root` and `logs_bloom` both follow them.
+    pub fn set_receipts(&mut self, receipts: &[Receipt]) -> Result<(), RLPError> {
+        self.fill_receipt_root(receipts)?;
+        self.fill_logs_bloom(receipts);
+        Ok(())
     }
 
-    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        unimplemented!()
+    /// Set the bloom to the union of the blooms of `receipts`
+    fn fill_logs_bloom(&mut self, receipts: &[Receipt]) {
+        self.logs_bloom = receipts.iter().fold(Bloom::new(), |bloom, r| bloom | r.logs_bloom);
+    }
+
+    /// Set `tx_root` to the Merkle root of the encoded `txs`, in block order.
+    /// Proofs against it come from `MerkleTree` built over the same encodings.
+    pub fn fill_tx_root(&mut self, txs: &[Transaction]) -> Result<(), RLPError> {
+        self.tx_root = merkle_root_of(txs)?;
+        Ok(())
+    }
+
+    /// Set `receipt_root` to the Merkle root of the encoded `receipts`, in block order
+    fn fill_receipt_root(&mut self, receipts: &[Receipt]) -> Result<(), RLPError> {
+        self.receipt_root = merkle_root_of(receipts)?;
+        Ok(())
     }
 }
 
 # [cfg(test)]
 mod tests {
+    use super::*;
+    use num::Zero;
+    use rlp::RLPSerialize;
+    use common::merkle::{MerkleTree, verify_proof};
+    use rlp::encoder::encode_to_vec;
+
     # [test]
-    fn test_block() {}
-}
\ No newline at end of file
+    fn test_block() {
+        let mut block = empty_block();
+        block.number = BigInt::from(7);
+        block.extra = b"genesis".to_vec();
+        block.nounce = [9u8; 8];
+
+        let decoded = Block::deserialize(&block.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.coinbase, block.coinbase);
+        assert_eq!(decoded.number, block.number);
+        assert_eq!(decoded.extra, block.extra);
+        assert_eq!(decoded.nounce, block.nounce);
+        assert_eq!(decoded.logs_bloom, block.logs_bloom);
+        assert_eq!(Block::deserialize(&RLP::RLPList(vec![])).err(), Some(RLPError::RLPErrorWrongNumParams));
+    }
+
+    fn empty_block() -> Block {
+        Block {
+            parent: zero_hash!(),
+            uncle: zero_hash!(),
+            coinbase: Address::from([1u8; 32]),
+            root: zero_hash!(),
+            tx_root: zero_hash!(),
+            receipt_root: zero_hash!(),
+            logs_bloom: Bloom::new(),
+            difficulty: BigInt::zero(),
+            number: BigInt::zero(),
+            gas_used: 0,
+            time: BigInt::zero(),
+            extra: vec![],
+            digest: zero_hash!(),
+            nounce: [0u8; 8]
+        }
+    }
+
+    # [test]
+    fn test_fill_logs_bloom() {
+        let mut block = empty_block();
+        let mut first = Receipt::new(&vec![], false, 0);
+        first.logs_bloom = Bloom::from_input(b"a");
+        let mut second = Receipt::new(&vec![], false, 0);
+        second.logs_bloom = Bloom::from_input(b"b");
+
+        block.fill_logs_bloom(&[first, second]);
+        assert!(block.logs_bloom.contains_input(b"a"));
+        assert!(block.logs_bloom.contains_input(b"b"));
+        assert!(!block.logs_bloom.contains_input(b"c"));
+    }
+
+    # [test]
+    fn test_set_receipts() {
+        let mut block = empty_block();
+        let mut receipt = Receipt::new(&vec![], false, 21000);
+        receipt.logs_bloom = Bloom::from_input(b"a");
+        let receipts = vec![receipt, Receipt::new(&vec![], true, 42000)];
+
+        block.set_receipts(&receipts).unwrap();
+        assert_eq!(block.receipt_root, merkle_root_of(&receipts).unwrap());
+        assert!(block.logs_bloom.contains_input(b"a"));
+
+        block.set_receipts(&[]).unwrap();
+        assert_eq!(block.receipt_root, zero_hash!());
+        assert!(block.logs_bloom.is_empty());
+    }
+
+    # [test]
+    fn test_fill_roots() {
+        let txs: Vec<Transaction> = (0..3u64)
+            .map(|n| *Transaction::new(n, Address::from([1u8; 32]), Address::from([2u8; 32]), None, 21000, None, &vec![]))
+            .collect();
+        let receipts = vec![Receipt::new(&vec![], false, 21000), Receipt::new(&vec![], true, 42000)];
+
+        let mut block = empty_block();
+        block.fill_tx_root(&txs).unwrap();
+        block.fill_receipt_root(&receipts).unwrap();
+        assert_ne!(block.tx_root, zero_hash!());
+        assert_ne!(block.tx_root, block.receipt_root);
+
+        // a light client holding only the header checks the second transaction
+        let encoded: Vec<Vec<u8>> = txs.iter().map(|tx| encode_to_vec(&tx.serialize().unwrap())).collect();
+        let tree = MerkleTree::new(&encoded);
+        let proof = tree.proof(1).unwrap();
+        assert!(verify_proof(&block.tx_root, &encoded[1], &proof));
+        assert!(!verify_proof(&block.tx_root, &encoded[2], &proof));
+
+        block.fill_tx_root(&[]).unwrap();
+        assert_eq!(block.tx_root, zero_hash!());
+    }
+}
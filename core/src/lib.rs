@@ -1,5 +1,6 @@
 pub mod account;
 pub mod action;
+pub mod authority;
 pub mod block;
 pub mod log;
 pub mod mpt;
@@ -14,6 +15,8 @@ pub extern crate chrono;
 pub extern crate db;
 #[macro_use]
 pub extern crate rlp;
+#[macro_use]
+extern crate rlp_derive;
 pub extern crate num;
 pub extern crate parity_wasm;
 pub extern crate wasmi;
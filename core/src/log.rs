@@ -1,11 +1,9 @@
 use common::address::Address;
-use common::hash::Hash;
-use rlp::RLPSerialize;
-use rlp::types::*;
-///
-///
-///
-#[derive(Debug)]
+use common::bloom::Bloom;
+use common::hash::*;
+/// Only the consensus fields are encoded, the location fields are derived
+/// and come back zeroed
+#[derive(Debug, RLPSerialize)]
 pub struct Log {
     /// address of the contract that generated the event
     pub contract_address: Address,
@@ -15,30 +13,73 @@ pub struct Log {
     pub data: Vec<u8>,
 
     /// block in which the transaction was included
+    #[rlp(skip)]
     pub block_number: u64,
     /// hash of the transaction
+    #[rlp(skip)]
     pub tx_hash: Hash,
     /// index of the transaction in the block
+    #[rlp(skip)]
     pub tx_index: u32,
     /// hash of the block in which the transaction was included
+    #[rlp(skip)]
     pub block_hash: Hash,
     /// index of the log in the receipt
+    #[rlp(skip)]
     pub index: u32
 }
 
-impl RLPSerialize for Log {
-    fn serialize(&self) -> Result<RLP, RLPError> {
-        unimplemented!()
-    }
-
-    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        unimplemented!()
+impl Log {
+    /// Bloom of the contract address and every topic
+    pub fn bloom(&self) -> Bloom {
+        let mut bloom = Bloom::from_input(&self.contract_address.to_tagged_bytes());
+        for topic in &self.topics {
+            bloom.accrue(topic);
+        }
+        bloom
     }
 }
 
-
 # [cfg(test)]
 mod tests {
+    use super::*;
+    use rlp::RLPSerialize;
+
     # [test]
-    fn test_log() {}
+    fn test_log() {
+        let log = Log {
+            contract_address: Address::from([1u8; 32]),
+            topics: vec![[2u8; 32]],
+            data: vec![9u8; 40],
+            block_number: 12,
+            tx_hash: [5u8; 32],
+            tx_index: 1,
+            block_hash: [6u8; 32],
+            index: 2
+        };
+        let decoded = Log::deserialize(&log.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.contract_address, log.contract_address);
+        assert_eq!(decoded.topics, log.topics);
+        assert_eq!(decoded.data, log.data);
+        assert_eq!(decoded.block_number, 0);
+    }
+
+    # [test]
+    fn test_log_bloom() {
+        let log = Log {
+            contract_address: Address::from([1u8; 32]),
+            topics: vec![[2u8; 32], [3u8; 32]],
+            data: vec![],
+            block_number: 0,
+            tx_hash: [0u8; 32],
+            tx_index: 0,
+            block_hash: [0u8; 32],
+            index: 0
+        };
+        let bloom = log.bloom();
+        assert!(bloom.contains_input(&Address::from([1u8; 32]).to_tagged_bytes()));
+        assert!(bloom.contains_input(&[2u8; 32]));
+        assert!(bloom.contains_input(&[3u8; 32]));
+        assert!(!bloom.contains_input(&[4u8; 32]));
+    }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use common::hash::*;
 use db::manager::*;
 use rlp::RLPSerialize;
+use rlp::types::*;
 use std::cmp::min;
 use std::marker::PhantomData;
 use std::sync::Mutex;
@@ -19,14 +20,18 @@ impl<T> Trie<T> where T: RLPSerialize + Clone {
         get_helper(&self.root, &vec2nibble(path), self.db)
     }
 
-    pub fn delete(&mut self, path: &Vec<u8>) {
-        self.root = delete_helper::<T>(&self.root, &vec2nibble(path), self.db);
+    pub fn delete(&mut self, path: &Vec<u8>) -> Result<(), RLPError> {
+        self.root = delete_helper::<T>(&self.root, &vec2nibble(path), self.db)?;
+        Ok(())
     }
 
-    pub fn update(&mut self, path: &Vec<u8>, v: &T) {
-        self.root = update_helper(&self.root, &vec2nibble(path), v, self.db);
+    pub fn update(&mut self, path: &Vec<u8>, v: &T) -> Result<(), RLPError> {
+        self.root = update_helper(&self.root, &vec2nibble(path), v, self.db)?;
+        Ok(())
     }
 
+    /// Empty trie stored in `db`.
+    /// Only tries on [SHARED_MANAGER] survive an RLP round trip, a decoded trie is always reopened there.
     pub fn new(db: &'static Mutex<DBManager>) -> Trie<T> {
         Trie::<T> { root: zero_hash!(), db: db, phantom: PhantomData }
     }
@@ -36,6 +41,17 @@ impl<T> Trie<T> where T: RLPSerialize + Clone {
     }
 }
 
+/// A trie is encoded as its root, it is reopened on [SHARED_MANAGER] whatever store it was created on
+impl<T> RLPSerialize for Trie<T> where T: RLPSerialize + Clone {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        self.root.serialize()
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        Ok(Trie::<T> { root: TrieKey::deserialize(rlp)?, db: &SHARED_MANAGER, phantom: PhantomData })
+    }
+}
+
 const PATH_MAX_LEN: usize = 64usize;
 
 macro_rules! mpt_db_delete {
@@ -46,7 +62,7 @@ macro_rules! mpt_db_delete {
 
 macro_rules! mpt_db_update {
     ($node:expr, $db:expr) => {
-        $db.lock().unwrap().put($node)
+        $db.lock().unwrap().put($node)?
     };
 }
 
@@ -129,9 +145,9 @@ fn get_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &Mute
     }
 }
 
-fn delete_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &Mutex<DBManager>) -> TrieKey {
+fn delete_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &Mutex<DBManager>) -> Result<TrieKey, RLPError> {
     let node_type = mpt_db_fetch!(node, db);
-    match node_type {
+    Ok(match node_type {
         Some(TrieNode::BranchNode::<T> { ref branches, ref value }) => {
             let mut new_branches: [TrieKey; MAX_BRANCHE_NUM] = [zero_hash!(); MAX_BRANCHE_NUM];
             for i in 0..MAX_BRANCHE_NUM {
@@ -144,7 +160,7 @@ fn delete_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &M
                 let nibble = next_nibble!(path);
                 assert!((nibble as u8) < MAX_NIBBLE_VALUE, "Invalid nibble");
                 let next_node = &branches[nibble];
-                let new_node = delete_helper::<T>(next_node, &path[1..path.len()].to_vec(), db);
+                let new_node = delete_helper::<T>(next_node, &path[1..path.len()].to_vec(), db)?;
                 new_branches[nibble] = new_node;
                 let new_branch_node = &TrieNode::<T>::new_branch_node(&new_branches, value.as_ref());
                 mpt_db_replace!(node, new_branch_node, db)
@@ -172,7 +188,7 @@ fn delete_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &M
                 ret_node.copy_from_slice(&node[0..HASH_LEN]);
                 ret_node
             } else {
-                let new_child_node = delete_helper::<T>(key, &remain_path, db);
+                let new_child_node = delete_helper::<T>(key, &remain_path, db)?;
                 let new_extension_node = &TrieNode::<T>::new_extension_node(encoded_path, &new_child_node);
                 mpt_db_replace!(node, new_extension_node, db)
             }
@@ -181,12 +197,12 @@ fn delete_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, db: &M
             zero_hash!()
         }
         _ => panic!("Unknown error!")
-    }
+    })
 }
 
-fn update_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, v: &T, db: &Mutex<DBManager>) -> TrieKey {
+fn update_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, v: &T, db: &Mutex<DBManager>) -> Result<TrieKey, RLPError> {
     let node_type = mpt_db_fetch!(node, db);
-    match node_type {
+    Ok(match node_type {
         Some(TrieNode::BranchNode::<T> { ref branches, ref value }) => {
             if path.len() == 0 {
                 let new_branch_node = &TrieNode::new_branch_node(branches, Some(v));
@@ -195,7 +211,7 @@ fn update_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, v: &T,
                 let nibble = next_nibble!(path);
                 assert!((nibble as u8) < MAX_NIBBLE_VALUE, "Invalid nibble");
                 let next_node = branches[nibble];
-                let new_child_key = update_helper(&next_node, &path[1..path.len()].to_vec(), v, db);
+                let new_child_key = update_helper(&next_node, &path[1..path.len()].to_vec(), v, db)?;
                 let mut new_branches = [zero_hash!(); MAX_BRANCHE_NUM];
                 new_branches.copy_from_slice(&branches[0..MAX_BRANCHE_NUM]);
                 new_branches[nibble] = new_child_key;
@@ -204,10 +220,10 @@ fn update_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, v: &T,
             }
         }
         Some(TrieNode::LeafNode::<T> { encoded_path: _, value: _ }) => {
-            update_kv_node_helper(node, path, v, db)
+            update_kv_node_helper(node, path, v, db)?
         }
         Some(TrieNode::ExtensionNode::<T> { encoded_path: _, key: _ }) => {
-            update_kv_node_helper(node, path, v, db)
+            update_kv_node_helper(node, path, v, db)?
         }
         None => {
             let encoded_path = encode_path(&path, true);
@@ -215,12 +231,12 @@ fn update_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, v: &T,
             mpt_db_replace!(node, new_leaf_node, db)
         }
         _ => panic!("Unknown error!")
-    }
+    })
 }
 
-fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, new_value: &T, db: &Mutex<DBManager>) -> TrieKey {
+fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>, new_value: &T, db: &Mutex<DBManager>) -> Result<TrieKey, RLPError> {
     let node_type = mpt_db_fetch!(node, db);
-    match node_type {
+    Ok(match node_type {
         // if the node is a leaf node
         Some(TrieNode::LeafNode::<T> { ref encoded_path, ref value }) => {
             // decode the path for the node
@@ -230,11 +246,13 @@ fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>
             } else {
                 // compute the shared path of the node path and the input path, then split remain paths
                 let (shared_path, remain_cur_path, remain_path) = cmp_path(cur_path, path);
+                // the same path only replaces the value of the leaf
+                if remain_path.len() == remain_cur_path.len() && remain_path.len() == 0 {
+                    let new_leaf_node = &TrieNode::new_leaf_node(encoded_path, new_value);
+                    return Ok(mpt_db_replace!(node, new_leaf_node, db));
+                }
                 // compute new nodes for remain paths, attach them to a new branch node
-                let branch_key = if remain_path.len() == remain_cur_path.len() && remain_path.len() == 0 {
-                    let new_leaf_node = &TrieNode::new_leaf_node(path, new_value);
-                    mpt_db_replace!(node, new_leaf_node, db)
-                } else if remain_cur_path.len() == 0 {
+                let branch_key = if remain_cur_path.len() == 0 {
                     let mut new_branches = [zero_hash!(); MAX_BRANCHE_NUM];
                     let encoded_path = encode_path(&remain_path[1..remain_path.len()].to_vec(), true);
                     let new_leaf_node = &TrieNode::new_leaf_node(&encoded_path, new_value);
@@ -247,7 +265,7 @@ fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>
                     let encoded_path = encode_path(&remain_cur_path[1..remain_cur_path.len()].to_vec(), true);
                     let new_leaf_node = &TrieNode::new_leaf_node(&encoded_path, value);
                     let child_key = mpt_db_update!(new_leaf_node, db);
-                    new_branches[next_nibble!(remain_path)] = child_key;
+                    new_branches[next_nibble!(remain_cur_path)] = child_key;
                     let new_branch_node = &TrieNode::new_branch_node(&new_branches, Some(new_value));
                     mpt_db_replace!(node, new_branch_node, db)
                 } else {
@@ -280,9 +298,9 @@ fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>
                 let (shared_path, remain_cur_path, remain_path) = cmp_path(cur_path, path);
                 // compute new nodes for remain paths, attach them to a new branch node
                 let branch_key = if remain_path.len() == remain_cur_path.len() && remain_path.len() == 0 {
-                    update_helper(key, &remain_path, new_value, db)
+                    update_helper(key, &remain_path, new_value, db)?
                 } else if remain_cur_path.len() == 0 {
-                    update_helper(key, &remain_path, new_value, db)
+                    update_helper(key, &remain_path, new_value, db)?
                 } else if remain_path.len() == 0 {
                     let mut new_branches = [zero_hash!(); MAX_BRANCHE_NUM];
                     if remain_cur_path.len() == 1 {
@@ -318,13 +336,12 @@ fn update_kv_node_helper<T: RLPSerialize + Clone>(node: &TrieKey, path: &Vec<u8>
             }
         }
         _ => panic!("Input node is not a kv node.")
-    }
+    })
 }
 
 #[cfg(test)]
 mod trie {
     use super::*;
-    use rlp::types::*;
 
     #[derive(Clone)]
     struct TestObject {
@@ -362,7 +379,7 @@ mod trie {
             0x7, 0x2, 0x6, 0xc, 0x6, 0x4
         ];
         let val = "Welcome dude".to_string();
-        trie.update(&path, &val);
+        trie.update(&path, &val).unwrap();
 
         let value = trie.get(&path).unwrap();
         assert_eq!(value, val);
@@ -377,15 +394,34 @@ mod trie {
             0x7, 0x2, 0x6, 0xc, 0x6, 0x4
         ];
         let val = "Welcome dude".to_string();
-        trie.update(&path, &val);
+        trie.update(&path, &val).unwrap();
 
         let new_val = "Welcome again dude".to_string();
-        trie.update(&path, &new_val);
+        trie.update(&path, &new_val).unwrap();
 
         let value = trie.get(&path).unwrap();
         assert_eq!(value, new_val);
     }
 
+    #[test]
+    fn test_trie_prefix() {
+        let long_path = vec![0x12, 0x34, 0x56];
+        let short_path = vec![0x12];
+
+        // the shorter path is inserted after the longer one and the other way round
+        let mut trie = Trie::<String>::new(&SHARED_MANAGER);
+        trie.update(&long_path, &"long".to_string()).unwrap();
+        trie.update(&short_path, &"short".to_string()).unwrap();
+        assert_eq!(trie.get(&long_path), Some("long".to_string()));
+        assert_eq!(trie.get(&short_path), Some("short".to_string()));
+
+        let mut trie = Trie::<String>::new(&SHARED_MANAGER);
+        trie.update(&short_path, &"short".to_string()).unwrap();
+        trie.update(&long_path, &"long".to_string()).unwrap();
+        assert_eq!(trie.get(&long_path), Some("long".to_string()));
+        assert_eq!(trie.get(&short_path), Some("short".to_string()));
+    }
+
     #[test]
     #[should_panic]
     fn test_trie_delete() {
@@ -396,8 +432,8 @@ mod trie {
             0x7, 0x2, 0x6, 0xc, 0x6, 0x4
         ];
         let val = "Welcome dude".to_string();
-        trie.update(&path, &val);
-        trie.delete(&path);
+        trie.update(&path, &val).unwrap();
+        trie.delete(&path).unwrap();
         let value = trie.get(&path).unwrap();
         assert_eq!(value, val);
     }
@@ -1,11 +1,12 @@
 use common::hash::*;
 use common::address::*;
+use common::bloom::Bloom;
 use rlp::RLPSerialize;
 use rlp::types::*;
 use log::Log;
 
 #[derive(Debug)]
-enum ReceiptStatus {
+pub enum ReceiptStatus {
     ReceiptStatusUnknown,
     ReceiptStatusFailedRLP,
     ReceiptStatusSuccessful,
@@ -13,18 +14,22 @@ enum ReceiptStatus {
 }
 
 /// Receipt represents the results of a transaction.
-#[derive(Debug)]
-struct Receipt {
+/// Only the consensus fields are encoded.
+#[derive(Debug, RLPSerialize)]
+pub struct Receipt {
     /// Consensus fields
     pub post_state: Vec<u8>,
     pub status: ReceiptStatus,
     pub cumulative_gas_used: u64,
-    //pub logs_bloom: Option<Bloom<Log>>,
+    pub logs_bloom: Bloom,
     pub logs: Option<Vec<Log>>,
 
     // Implementation fields (don't reorder!)
+    #[rlp(skip)]
     txhash: Option<Hash>,
+    #[rlp(skip)]
     contract_address: Option<Address>,
+    #[rlp(skip)]
     gas_used: u64
 }
 
@@ -33,9 +38,9 @@ impl Receipt {
     pub fn new(root: &Vec<u8>, failed: bool, cumulative_gas_used: u64) -> Self {
         let r = Receipt {
             post_state: root.to_vec(),
-            status: ReceiptStatus::ReceiptStatusUnknown,
+            status: if failed { ReceiptStatus::ReceiptStatusFailedRLP } else { ReceiptStatus::ReceiptStatusSuccessful },
             cumulative_gas_used: cumulative_gas_used,
-            //logs_bloom: None,
+            logs_bloom: Bloom::new(),
             logs: None,
 
             txhash: None,
@@ -44,20 +49,89 @@ impl Receipt {
         };
         r
     }
+
+    /// Attach the logs of the transaction, the bloom follows them
+    pub fn set_logs(&mut self, logs: Vec<Log>) {
+        self.logs_bloom = logs.iter().fold(Bloom::new(), |bloom, log| bloom | log.bloom());
+        self.logs = Some(logs);
+    }
 }
 
-impl RLPSerialize for Receipt {
+/// Failed is encoded as empty bytes and successful as `0x01`, any other bytes are
+/// a status returned by the contract. An unknown status has no encoding.
+impl RLPSerialize for ReceiptStatus {
     fn serialize(&self) -> Result<RLP, RLPError> {
-        Err(RLPError::RLPErrorUnknown(""))
+        match self {
+            &ReceiptStatus::ReceiptStatusUnknown => Err(RLPError::RLPErrorUnknown("receipt status unknown")),
+            &ReceiptStatus::ReceiptStatusFailedRLP => Ok(RLP::RLPItem(vec![])),
+            &ReceiptStatus::ReceiptStatusSuccessful => Ok(RLP::RLPItem(vec![0x01u8])),
+            &ReceiptStatus::ReceiptStatusSuccessfulRLP(ref value) => Ok(RLP::RLPItem(value.clone()))
+        }
     }
 
     fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        Err(RLPError::RLPErrorUnknown(""))
+        match rlp {
+            &RLP::RLPItem(ref value) => {
+                match value.as_slice() {
+                    &[] => Ok(ReceiptStatus::ReceiptStatusFailedRLP),
+                    &[0x01u8] => Ok(ReceiptStatus::ReceiptStatusSuccessful),
+                    _ => Ok(ReceiptStatus::ReceiptStatusSuccessfulRLP(value.clone()))
+                }
+            },
+            _ => Err(RLPError::RLPErrorType)
+        }
     }
 }
 
 # [cfg(test)]
 mod tests {
+    use super::*;
+
+    fn log(topic: u8) -> Log {
+        Log {
+            contract_address: Address::from([1u8; 32]),
+            topics: vec![[topic; 32]],
+            data: vec![],
+            block_number: 0,
+            tx_hash: zero_hash!(),
+            tx_index: 0,
+            block_hash: zero_hash!(),
+            index: 0
+        }
+    }
+
     # [test]
-    fn test_receipt() {}
+    fn test_receipt() {
+        let mut receipt = Receipt::new(&vec![7u8; 32], false, 42000);
+        receipt.set_logs(vec![log(2)]);
+        let decoded = Receipt::deserialize(&receipt.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.post_state, receipt.post_state);
+        assert_eq!(decoded.cumulative_gas_used, 42000);
+        assert_eq!(decoded.logs_bloom, receipt.logs_bloom);
+        assert_eq!(decoded.logs.unwrap().len(), 1);
+        match decoded.status {
+            ReceiptStatus::ReceiptStatusSuccessful => {},
+            other => panic!("unexpected status {:?}", other)
+        }
+
+        // a receipt without logs keeps none
+        let bare = Receipt::new(&vec![], true, 21000);
+        let decoded = Receipt::deserialize(&bare.serialize().unwrap()).unwrap();
+        assert!(decoded.logs.is_none());
+        assert!(decoded.logs_bloom.is_empty());
+
+        let mut unknown = Receipt::new(&vec![], true, 0);
+        unknown.status = ReceiptStatus::ReceiptStatusUnknown;
+        assert!(unknown.serialize().is_err());
+    }
+
+    # [test]
+    fn test_logs_bloom() {
+        let mut receipt = Receipt::new(&vec![], false, 0);
+        assert!(receipt.logs_bloom.is_empty());
+        receipt.set_logs(vec![log(2), log(3)]);
+        assert!(receipt.logs_bloom.contains_bloom(&log(2).bloom()));
+        assert!(receipt.logs_bloom.contains_bloom(&log(3).bloom()));
+        assert!(!receipt.logs_bloom.contains_input(&[4u8; 32]));
+    }
 }
@@ -2,6 +2,7 @@ use super::mpt::trie::Trie;
 
 use common::address::Address;
 use common::hash::*;
+use db::manager::SHARED_MANAGER;
 use rlp::RLPSerialize;
 use rlp::types::*;
 
@@ -34,24 +35,36 @@ pub enum StorageError {
 
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RLPSerialize)]
 pub struct Storage {
     trie: Trie<CHUNK>,
     account_addr: Address,
 }
 
 impl Storage {
+    /// Empty storage of the account at `account_addr`
+    pub fn new(account_addr: Address) -> Self {
+        Storage {
+            trie: Trie::new(&SHARED_MANAGER),
+            account_addr: account_addr
+        }
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.account_addr
+    }
+
     pub fn get(&self, key: Hash) -> Option<CHUNK> {
         let vec = (&key[..]).to_vec();
         self.trie.get(&vec)
     }
 
-    pub fn delete(&mut self, key: Hash) {
+    pub fn delete(&mut self, key: Hash) -> Result<(), RLPError> {
         let vec = (&key[..]).to_vec();
         self.trie.delete(&vec)
     }
 
-    pub fn update(&mut self, key: Hash, chunk: CHUNK) {
+    pub fn update(&mut self, key: Hash, chunk: CHUNK) -> Result<(), RLPError> {
         let vec = (&key[..]).to_vec();
         self.trie.update(&vec, &chunk)
     }
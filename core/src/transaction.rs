@@ -1,13 +1,14 @@
+use authority::{Authority, AuthorityError};
 use common::address::Address;
-use common::key::Signature;
+use common::key::TaggedSignature;
 use num::bigint::BigInt;
 use num::Zero;
 use rlp::RLPSerialize;
+use rlp::encoder::encode_to_vec;
 use rlp::types::*;
 
-///
-///
-///
+/// What every signer signs
+#[derive(RLPSerialize)]
 pub struct TransactionBody {
     account_nounce: u64,
     gas_price: BigInt,
@@ -16,14 +17,15 @@ pub struct TransactionBody {
     recipient: Address,
     amount: BigInt,
     payload: Vec<u8>,
-    sig: Option<Signature>
+    /// new authority of the sender, set only by an authority change
+    authority: Option<Authority>
 }
 
-///
-///
-///
+/// Encoded as the body followed by the signatures
+#[derive(RLPSerialize)]
 pub struct Transaction {
-    tx_body: TransactionBody
+    tx_body: TransactionBody,
+    sigs: Vec<TaggedSignature>
 }
 
 impl Transaction {
@@ -49,24 +51,109 @@ impl Transaction {
                     None => BigInt::zero()
                 },
                 payload: data.to_vec(),
-                sig: None
+                authority: None
             },
+            sigs: vec![]
         })
     }
-}
 
-impl RLPSerialize for Transaction {
-    fn serialize(&self) -> Result<RLP, RLPError> {
-        unimplemented!()
+    /// Replace the authority of `from` with `authority`, signed like any other transaction
+    pub fn change_authority(nonce: u64,
+                            from: Address,
+                            authority: Authority,
+                            gas_limit: u64,
+                            gas_price: Option<BigInt>) -> Box<Self> {
+        let mut tx = Transaction::new(nonce, from.clone(), from, None, gas_limit, gas_price, &vec![]);
+        tx.tx_body.authority = Some(authority);
+        tx
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.tx_body.account_nounce
+    }
+
+    pub fn sender(&self) -> &Address {
+        &self.tx_body.sender
+    }
+
+    /// Authority installed by this transaction, if it changes one
+    pub fn new_authority(&self) -> Option<&Authority> {
+        self.tx_body.authority.as_ref()
+    }
+
+    /// Bytes every signer signs, the body without its signatures
+    pub fn signing_payload(&self) -> Result<Vec<u8>, RLPError> {
+        Ok(encode_to_vec(&self.tx_body.serialize()?))
+    }
+
+    pub fn add_signature(&mut self, sig: TaggedSignature) {
+        self.sigs.push(sig);
+    }
+
+    pub fn signatures(&self) -> &[TaggedSignature] {
+        &self.sigs
     }
 
-    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        unimplemented!()
+    /// Check the signatures against the authority of the sender account
+    pub fn verify_authority(&self, authority: &Authority) -> Result<(), AuthorityError> {
+        let payload = self.signing_payload().map_err(AuthorityError::Encoding)?;
+        authority.check(&payload, self.signatures())
     }
 }
 
 # [cfg(test)]
 mod tests {
+    use super::*;
+    use authority::KeyWeight;
+    use common::key::{KeyPair, SignatureScheme};
+
+    # [test]
+    fn test_transaction() {
+        let (key, _) = <KeyPair as SignatureScheme>::generate().unwrap();
+        let mut tx = Transaction::new(3, SignatureScheme::address(&key), Address::from([2u8; 32]),
+                                      Some(BigInt::from(500)), 21000, Some(BigInt::from(7)), &vec![1u8, 2u8]);
+        let payload = tx.signing_payload().unwrap();
+        tx.add_signature(SignatureScheme::sign(&key, &payload));
+
+        let decoded = Transaction::deserialize(&tx.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.sender(), tx.sender());
+        assert_eq!(decoded.signing_payload().unwrap(), payload);
+        assert_eq!(decoded.signatures(), tx.signatures());
+        assert_eq!(decoded.verify_authority(&Authority::single(tx.sender().clone())), Ok(()));
+        assert_eq!(Transaction::deserialize(&RLP::RLPList(vec![])).err(), Some(RLPError::RLPErrorWrongNumParams));
+    }
+
+    # [test]
+    fn test_multisig() {
+        let keys: Vec<KeyPair> = (0..2).map(|_| <KeyPair as SignatureScheme>::generate().unwrap().0).collect();
+        let authority = Authority::new(2, keys.iter().map(|k| KeyWeight::new(SignatureScheme::address(k), 1)).collect()).unwrap();
+
+        let mut tx = Transaction::new(0, Address::from([1u8; 32]), Address::from([2u8; 32]), None, 21000, None, &vec![]);
+        let payload = tx.signing_payload().unwrap();
+        tx.add_signature(SignatureScheme::sign(&keys[0], &payload));
+        assert_eq!(tx.verify_authority(&authority), Err(AuthorityError::Unsatisfied { weight: 1, threshold: 2 }));
+        tx.add_signature(SignatureScheme::sign(&keys[1], &payload));
+        assert_eq!(tx.verify_authority(&authority), Ok(()));
+        // signatures are not part of what is signed
+        assert_eq!(tx.signing_payload().unwrap(), payload);
+    }
+
     # [test]
-    fn test_transaction() {}
+    fn test_change_authority() {
+        let keys: Vec<KeyPair> = (0..2).map(|_| <KeyPair as SignatureScheme>::generate().unwrap().0).collect();
+        let authority = Authority::new(2, keys.iter().map(|k| KeyWeight::new(SignatureScheme::address(k), 1)).collect()).unwrap();
+        let sender = SignatureScheme::address(&keys[0]);
+
+        let tx = Transaction::change_authority(5, sender.clone(), authority.clone(), 21000, None);
+        let plain = Transaction::new(5, sender.clone(), sender.clone(), None, 21000, None, &vec![]);
+        assert_eq!(tx.nonce(), 5);
+        assert_eq!(tx.new_authority(), Some(&authority));
+        assert_eq!(plain.new_authority(), None);
+        // the new authority is signed over
+        assert_ne!(tx.signing_payload().unwrap(), plain.signing_payload().unwrap());
+
+        let decoded = Transaction::deserialize(&tx.serialize().unwrap()).unwrap();
+        assert_eq!(decoded.new_authority(), Some(&authority));
+        assert_eq!(decoded.signing_payload().unwrap(), tx.signing_payload().unwrap());
+    }
 }
\ No newline at end of file
@@ -7,20 +7,24 @@ use action::Action;
 use storage::StorageCache;
 
 use common::address::Address;
+use common::node_config::node_config;
 
 use wasmi::*;
 
 pub struct GenVM{
     system_call: SystemCall,
     kernel: KernelRef,
+    time_limit: usize,
 }
 
 impl GenVM {
     pub fn new(action: &Action, contract: Address) -> Result<Self, Error> {
-        let kernel_ref = Kernel::new(contract);
+        let config = node_config().vm;
+        let kernel_ref = Kernel::new_with_stack_size(contract, config.max_stack_size);
         let mut vm = GenVM {
             system_call: SystemCall::new_with_kernel(kernel_ref.clone()),
-            kernel: kernel_ref.clone()
+            kernel: kernel_ref.clone(),
+            time_limit: config.time_limit
         };
         Ok(vm)
     }
@@ -40,7 +44,7 @@ impl GenVM {
             );
 
             // exececute
-            let result = self.execute(&mut runtime, selector, 1000);
+            let result = self.execute(&mut runtime, selector, self.time_limit);
 
             // pop stack
             self.kernel.borrow_mut().pop_runtime();
@@ -19,15 +19,22 @@ pub type KernelRef = Rc<RefCell<Kernel>>;
 pub struct Kernel {
     stack: Vec<(RuntimeContextRef, Option<MemoryRef>, ModuleRef, StorageCache)>,
     address: Address,
-    result: Option<Result<RuntimeResult, Error>>
+    result: Option<Result<RuntimeResult, Error>>,
+    max_stack_size: usize
 }
 
 impl Kernel {
     pub fn new(address: Address) -> KernelRef {
+        Kernel::new_with_stack_size(address, MAX_STACK_SIZE)
+    }
+
+    /// Kernel refusing more than `max_stack_size` nested calls
+    pub fn new_with_stack_size(address: Address, max_stack_size: usize) -> KernelRef {
         Rc::new(RefCell::new(Kernel {
             stack: vec![],
             address: address,
-            result: None
+            result: None,
+            max_stack_size: max_stack_size
         }))
     }
 
@@ -39,7 +46,7 @@ impl Kernel {
         module: ModuleRef,
         cache: StorageCache
     ) -> bool {
-        if self.stack.len() > MAX_STACK_SIZE {
+        if self.stack.len() > self.max_stack_size {
             false
         } else {
             self.stack.push(
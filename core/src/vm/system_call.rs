@@ -1,4 +1,5 @@
 use common::address::Address;
+use common::key::{PublicKey, PUBLIC_KEY_LEN};
 use common::hash::*;
 use std::collections::HashMap;
 use storage::*;
@@ -15,6 +16,16 @@ pub const TEST_INDEX: usize = 0x04;
 pub const MEM_STAT_INDEX: usize = 0x05;
 pub const CPU_STAT_INDEX: usize = 0x06;
 
+/// Contracts name accounts by their bare ed25519 key
+fn contract_address(vec: Vec<u8>) -> Result<Address, ()> {
+    if vec.len() != PUBLIC_KEY_LEN {
+        return Err(());
+    }
+    let mut key: PublicKey = [0u8; PUBLIC_KEY_LEN];
+    key.copy_from_slice(&vec);
+    Ok(Address::from(key))
+}
+
 macro_rules! hashmap {
     ($( $key: expr => $val: expr ),*) => {{
          let mut map = ::std::collections::HashMap::new();
@@ -121,7 +132,7 @@ impl Api for SystemCall {
     fn call(&mut self, addr: u32, abi: u32, abi_len: u32, input_balance: u32) -> RuntimeValue {
         let parent = self.kernel.borrow().top_context();
         let result = self.memory_load(addr, 32).and_then(|vec| {
-            match Address::try_from(vec) {
+            match contract_address(vec) {
                 Ok(addr) => {
                     self.init_runtime_with_parent
                     (
@@ -186,7 +197,7 @@ impl Api for SystemCall {
     // get account balance with provided addr
     fn get_account_balance(&mut self, addr: u32) -> RuntimeValue {
         let result = self.memory_load(addr, 32).and_then(|vec| {
-            match Address::try_from(vec) {
+            match contract_address(vec) {
                 Ok(addr) => {
                     Kernel::load_contract_account(addr).and_then(|account| {
                         Ok(RuntimeValue::I32(account.balance() as i32))
@@ -28,7 +28,7 @@ impl DBConfig {
 
 impl RocksDB {
     pub fn open(options: &DBConfig) -> Self {
-        let db = ::rocksdb::DB::open(&options.to_rocksdb(), &"rocksdb/dir").unwrap();
+        let db = ::rocksdb::DB::open(&options.to_rocksdb(), &options.path).unwrap();
         Self { db: Arc::new(db) }
     }
 }
@@ -1,10 +1,16 @@
 extern crate common;
 extern crate rlp;
 
-use self::common::hash::Hash;
+use self::common::hash::{Hash, chain_digest};
+use self::common::node_config::{node_config, DBSettings};
 use self::rlp::RLPSerialize;
+use self::rlp::decoder::Decoder;
+use self::rlp::encoder::encode_to_vec;
+use self::rlp::types::{EncodedRLP, RLPError};
 use gen_rocksdb::*;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 pub enum DBResult {
@@ -32,15 +38,34 @@ pub struct DBStatus {
 }
 
 pub struct DBConfig {
+    pub path: PathBuf,
     pub create_if_missing: bool,
     pub max_open_files: i32
 }
 
+impl<'a> From<&'a DBSettings> for DBConfig {
+    fn from(settings: &'a DBSettings) -> Self {
+        DBConfig {
+            path: settings.path.clone(),
+            create_if_missing: settings.create_if_missing,
+            max_open_files: settings.max_open_files
+        }
+    }
+}
+
 pub struct DBManager {
-    config: &'static mut DBConfig
+    config: DBConfig,
+    /// encoded values by their key with the number of times they were put,
+    /// held in memory until the RocksDB backend is wired in
+    nodes: HashMap<Vec<u8>, (EncodedRLP, usize)>
 }
 
 impl DBManager {
+    /// Settings the manager was created with
+    pub fn config(&self) -> &DBConfig {
+        &self.config
+    }
+
     pub fn connect(&self, config: & DBConfig) -> Result<(&'static DBContext, DBResult), DBError> {
         let db = RocksDB::open(config);
         Ok(( &DBContext{}, DBResult::DBConnectSuccess ))
@@ -58,38 +83,86 @@ impl DBManager {
 lazy_static! {
     //TODO:
     pub static ref SHARED_MANAGER: Mutex<DBManager> = {
-        static mut conf: DBConfig = DBConfig {
-            create_if_missing: false,
-            max_open_files: 32
-        };
-        unsafe { Mutex::new(DBManager{ config: &mut conf }) }
+        Mutex::new(DBManager{ config: DBConfig::from(&node_config().db), nodes: HashMap::new() })
     };
 }
 
-///
-///
-///
+/// Content addressed storage, a value is keyed by the chain hash of its encoding
+/// so trie roots follow the hash algorithm the chain is configured with.
+/// Equal values share a key, a value is dropped once it was deleted as often as it was put.
 pub trait DBManagerOP {
-    fn put<T: RLPSerialize>(&self, value: &T) -> Hash;
-    fn delete(&self, key: &Vec<u8>);
+    fn put<T: RLPSerialize>(&mut self, value: &T) -> Result<Hash, RLPError>;
+    fn delete(&mut self, key: &Vec<u8>);
     fn get<T: RLPSerialize>(&self, key: &Vec<u8>) -> Option<T>;
     fn get_node<T: RLPSerialize>(&self, value: &T) -> Option<T>;
 }
 
 impl DBManagerOP for DBManager {
-    fn put<T: RLPSerialize>(&self, value: &T) -> Hash {
-        unimplemented!()
+    fn put<T: RLPSerialize>(&mut self, value: &T) -> Result<Hash, RLPError> {
+        let encoded = encode_to_vec(&value.serialize()?);
+        let key = chain_digest(&encoded);
+        self.nodes.entry(key.to_vec()).or_insert((encoded, 0)).1 += 1;
+        Ok(key)
     }
 
-    fn delete(&self, key: &Vec<u8>) {
-        unimplemented!()
+    fn delete(&mut self, key: &Vec<u8>) {
+        let dropped = match self.nodes.get_mut(key) {
+            Some(&mut (_, ref mut count)) => {
+                *count -= 1;
+                *count == 0
+            },
+            None => false
+        };
+        if dropped {
+            self.nodes.remove(key);
+        }
     }
 
     fn get<T: RLPSerialize>(&self, key: &Vec<u8>) -> Option<T> {
-        unimplemented!()
+        let rlp = Decoder::decode(&self.nodes.get(key)?.0)?;
+        T::deserialize(&rlp).ok()
     }
 
     fn get_node<T: RLPSerialize>(&self, value: &T) -> Option<T> {
-        unimplemented!()
+        let key = chain_digest(&encode_to_vec(&value.serialize().ok()?));
+        self.get(&key.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> DBManager {
+        DBManager {
+            config: DBConfig::from(&node_config().db),
+            nodes: HashMap::new()
+        }
+    }
+
+    #[test]
+    fn test_put_get() {
+        let mut manager = manager();
+        let key = manager.put(&"dog".to_string()).unwrap();
+        assert_eq!(key, chain_digest(&encode_to_vec(&"dog".to_string().serialize().unwrap())));
+        assert_eq!(manager.get::<String>(&key.to_vec()), Some("dog".to_string()));
+        assert_eq!(manager.get_node(&"dog".to_string()), Some("dog".to_string()));
+        assert_eq!(manager.get::<String>(&vec![0u8; 32]), None);
+    }
+
+    #[test]
+    fn test_shared_delete() {
+        let mut manager = manager();
+        let key = manager.put(&"dog".to_string()).unwrap().to_vec();
+        assert_eq!(manager.put(&"dog".to_string()).unwrap().to_vec(), key);
+
+        // the value stays while one of its owners still holds it
+        manager.delete(&key);
+        assert_eq!(manager.get::<String>(&key), Some("dog".to_string()));
+        manager.delete(&key);
+        assert_eq!(manager.get::<String>(&key), None);
+        assert!(manager.nodes.is_empty());
+        // deleting a missing key is harmless
+        manager.delete(&key);
     }
 }
\ No newline at end of file
@@ -0,0 +1,586 @@
+//! Bridge exposing selected topics of the [MESSAGE_CENTER] to other processes over a Unix socket.
+//!
+//! **Protocol**
+//! - every frame is an 8-byte big-endian length followed by an RLP list `[kind, topic, body]`
+//! - `SUBSCRIBE` and `UNSUBSCRIBE` from the client are acknowledged by echoing the frame
+//! - `PUBLISH` from the client sends its body as the payload of the topic on the bus,
+//!   the node forwards the payloads of subscribed topics as `PUBLISH` frames
+//! - a frame the node refuses is answered by an `ERROR` frame whose body is the reason
+//!
+//! Payloads cross the socket as their [RLPSerialize] encoding, a topic is only bridged when
+//! [Bridge::expose] was given its payload type.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use rlp::RLPSerialize;
+use rlp::decoder::Decoder;
+use rlp::encoder::encode_to_vec;
+use rlp::limits::DecodeLimits;
+use rlp::types::*;
+
+use {MessageChannel, Message, Topic, MESSAGE_CENTER};
+
+/// Max size of a frame body, larger frames close the connection
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024 * 4;
+
+const FRAME_SUBSCRIBE: u8 = 0x01u8;
+const FRAME_UNSUBSCRIBE: u8 = 0x02u8;
+const FRAME_PUBLISH: u8 = 0x03u8;
+const FRAME_ERROR: u8 = 0x04u8;
+
+/// How long a connection waits on its channel before checking whether it was closed
+const WRITER_POLL: u64 = 100u64;
+
+/// Mode of the socket file, only the node's user may connect
+const SOCKET_MODE: u32 = 0o600u32;
+
+/// # Frame
+/// **Usage**
+/// - unit of the bridge protocol, the same frames go both ways
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Subscribe(String),
+    Unsubscribe(String),
+    /// topic and RLP of the payload
+    Publish(String, RLP),
+    /// topic and reason
+    Error(String, String)
+}
+
+impl Frame {
+    pub fn topic(&self) -> &str {
+        match *self {
+            Frame::Subscribe(ref topic) => topic,
+            Frame::Unsubscribe(ref topic) => topic,
+            Frame::Publish(ref topic, _) => topic,
+            Frame::Error(ref topic, _) => topic
+        }
+    }
+
+    /// Payload of a `Publish` frame of `topic`
+    pub fn read<T: RLPSerialize>(&self, topic: &Topic<T>) -> Option<T> {
+        match *self {
+            Frame::Publish(ref name, ref rlp) if name == topic.name() => T::deserialize(rlp).ok(),
+            _ => None
+        }
+    }
+}
+
+impl RLPSerialize for Frame {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        let (kind, body) = match *self {
+            Frame::Subscribe(_) => (FRAME_SUBSCRIBE, RLP::RLPItem(vec![])),
+            Frame::Unsubscribe(_) => (FRAME_UNSUBSCRIBE, RLP::RLPItem(vec![])),
+            Frame::Publish(_, ref rlp) => (FRAME_PUBLISH, rlp.clone()),
+            Frame::Error(_, ref reason) => (FRAME_ERROR, reason.serialize()?)
+        };
+        Ok(RLP::RLPList(vec![kind.serialize()?, self.topic().to_string().serialize()?, body]))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        match rlp {
+            &RLP::RLPList(ref list) if list.len() == 3 => {
+                let topic = String::deserialize(&list[1])?;
+                match u8::deserialize(&list[0])? {
+                    FRAME_SUBSCRIBE => Ok(Frame::Subscribe(topic)),
+                    FRAME_UNSUBSCRIBE => Ok(Frame::Unsubscribe(topic)),
+                    FRAME_PUBLISH => Ok(Frame::Publish(topic, list[2].clone())),
+                    FRAME_ERROR => Ok(Frame::Error(topic, String::deserialize(&list[2])?)),
+                    _ => Err(RLPError::RLPErrorTagType)
+                }
+            },
+            &RLP::RLPList(_) => Err(RLPError::RLPErrorWrongNumParams),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+}
+
+fn invalid_data<E: ::std::fmt::Debug>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e))
+}
+
+pub fn write_frame<W: Write>(out: &mut W, frame: &Frame) -> io::Result<()> {
+    let body = encode_to_vec(&frame.serialize().map_err(invalid_data)?);
+    let mut data: Vec<u8> = Vec::with_capacity(8 + body.len());
+    data.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    data.extend_from_slice(&body);
+    out.write_all(&data)
+}
+
+/// Read the next frame, the body is decoded strictly within [MAX_FRAME_SIZE]
+pub fn read_frame<R: Read>(input: &mut R) -> io::Result<Frame> {
+    let mut size_buf = [0u8; 8];
+    input.read_exact(&mut size_buf)?;
+    let size = u64::from_be_bytes(size_buf);
+    if size > MAX_FRAME_SIZE as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut body = vec![0u8; size as usize];
+    input.read_exact(&mut body)?;
+    let rlp = Decoder::decode_with_limits(&body, &DecodeLimits::new_with_size(MAX_FRAME_SIZE)).map_err(invalid_data)?;
+    Frame::deserialize(&rlp).map_err(invalid_data)
+}
+
+/// What clients may do with an exposed topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Subscribe,
+    Publish,
+    Both
+}
+
+impl Access {
+    fn can_subscribe(&self) -> bool {
+        *self != Access::Publish
+    }
+
+    fn can_publish(&self) -> bool {
+        *self != Access::Subscribe
+    }
+}
+
+/// Conversions between the payload of a topic and its RLP
+struct Exposed {
+    access: Access,
+    encode: Box<dyn Fn(&Message) -> Option<RLP> + Send + Sync>,
+    decode: Box<dyn Fn(&RLP) -> Result<Message, RLPError> + Send + Sync>
+}
+
+/// # Bridge
+/// **Usage**
+/// - expose topics, then [Bridge::start] listening on the socket path
+/// - each client gets its own channel, it shows up in the metrics under the topics it subscribed
+/// ## Examples
+/// ```
+/// use gen_message::{Topic, MESSAGE_CENTER};
+/// use gen_message::bridge::{Access, Bridge, BridgeClient};
+///
+/// const HEIGHT: Topic<u64> = Topic::new("doc-bridge-height");
+///
+/// let path = std::env::temp_dir().join("gen-doc-bridge.sock");
+/// let mut bridge = Bridge::new(&path);
+/// bridge.expose(&HEIGHT, Access::Subscribe);
+/// let handle = bridge.start().unwrap();
+///
+/// let mut client = BridgeClient::connect(&path).unwrap();
+/// client.subscribe(HEIGHT.name()).unwrap();
+/// MESSAGE_CENTER.lock().unwrap().publish(&HEIGHT, 42u64);
+/// assert_eq!(client.receive().unwrap().read(&HEIGHT), Some(42u64));
+/// handle.shutdown();
+/// ```
+pub struct Bridge {
+    path: PathBuf,
+    topics: HashMap<String, Exposed>
+}
+
+impl Bridge {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Bridge {
+            path: path.as_ref().to_path_buf(),
+            topics: HashMap::new()
+        }
+    }
+
+    /// Make `topic` reachable from the socket
+    pub fn expose<T>(&mut self, topic: &Topic<T>, access: Access)
+        where T: RLPSerialize + Clone + Send + Sync + 'static {
+        let topic = *topic;
+        self.topics.insert(topic.name().to_string(), Exposed {
+            access: access,
+            encode: Box::new(move |msg| topic.read(msg).and_then(|payload| payload.serialize().ok())),
+            decode: Box::new(move |rlp| T::deserialize(rlp).map(|payload| topic.message(payload)))
+        });
+    }
+
+    /// # start(0)
+    /// **Usage**
+    /// - bind the socket readable and writable by the owner only
+    /// - a stale socket left by a previous run is replaced, any other file
+    ///   or a socket still accepting connections is an error
+    /// **Return**: [[BridgeHandle]] which stops the bridge
+    pub fn start(self) -> io::Result<BridgeHandle> {
+        remove_stale(&self.path)?;
+        let listener = UnixListener::bind(&self.path)?;
+        fs::set_permissions(&self.path, fs::Permissions::from_mode(SOCKET_MODE))?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let connections: Arc<Mutex<Vec<Connection>>> = Arc::new(Mutex::new(vec![]));
+        let topics = Arc::new(self.topics);
+
+        let acceptor = {
+            let stopped = stopped.clone();
+            let connections = connections.clone();
+            thread::Builder::new().name("bridge-accept".to_string()).spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        if let Ok(conn) = Connection::open(stream, topics.clone()) {
+                            let mut connections = connections.lock().unwrap();
+                            connections.retain(|conn| !conn.is_closed());
+                            connections.push(conn);
+                        }
+                    }
+                }
+            })?
+        };
+
+        Ok(BridgeHandle {
+            path: self.path,
+            stopped: stopped,
+            acceptor: Some(acceptor),
+            connections: connections
+        })
+    }
+}
+
+/// Remove the socket at `path` when nobody listens on it anymore
+fn remove_stale(path: &Path) -> io::Result<()> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e)
+    };
+    if !meta.file_type().is_socket() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "path exists and is not a socket"));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "socket is in use"));
+    }
+    fs::remove_file(path)
+}
+
+/// One client, a reader thread serving its frames and a writer thread forwarding its channel
+struct Connection {
+    stream: UnixStream,
+    closed: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>
+}
+
+impl Connection {
+    fn open(stream: UnixStream, topics: Arc<HashMap<String, Exposed>>) -> io::Result<Self> {
+        let channel = Arc::new((Mutex::new(MessageChannel::new()), Condvar::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+
+        let reader = {
+            let mut input = stream.try_clone()?;
+            let writer = writer.clone();
+            let channel = channel.clone();
+            let closed = closed.clone();
+            let topics = topics.clone();
+            thread::Builder::new().name("bridge-read".to_string()).spawn(move || {
+                while let Ok(frame) = read_frame(&mut input) {
+                    let reply = serve(&frame, &channel, &topics);
+                    if let Some(reply) = reply {
+                        if write_frame(&mut *writer.lock().unwrap(), &reply).is_err() {
+                            break;
+                        }
+                    }
+                }
+                closed.store(true, Ordering::SeqCst);
+                let uid = channel.0.lock().unwrap().uid.clone();
+                MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+                channel.1.notify_all();
+            })?
+        };
+
+        let forwarder = {
+            let closed = closed.clone();
+            thread::Builder::new().name("bridge-write".to_string()).spawn(move || {
+                loop {
+                    // the channel is not locked while writing, a slow client must not block senders
+                    let msg = {
+                        let mut guard = channel.0.lock().unwrap();
+                        loop {
+                            if closed.load(Ordering::SeqCst) {
+                                return;
+                            }
+                            if let Some(msg) = guard.accept_msg_async() {
+                                break msg;
+                            }
+                            guard = channel.1.wait_timeout(guard, Duration::from_millis(WRITER_POLL)).unwrap().0;
+                        }
+                    };
+                    let frame = topics.get(&msg.msg)
+                        .and_then(|exposed| (exposed.encode)(&msg))
+                        .map(|rlp| Frame::Publish(msg.msg.clone(), rlp));
+                    if let Some(frame) = frame {
+                        if write_frame(&mut *writer.lock().unwrap(), &frame).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })?
+        };
+
+        Ok(Connection {
+            stream: stream,
+            closed: closed,
+            threads: vec![reader, forwarder]
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn close(self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Handle a frame of the client, returns the answer if any
+fn serve(frame: &Frame, channel: &Arc<(Mutex<MessageChannel>, Condvar)>, topics: &HashMap<String, Exposed>) -> Option<Frame> {
+    let name = frame.topic().to_string();
+    let exposed = match topics.get(&name) {
+        Some(exposed) => exposed,
+        None => return Some(Frame::Error(name, "topic is not exposed".to_string()))
+    };
+    match *frame {
+        Frame::Subscribe(_) if exposed.access.can_subscribe() => {
+            MESSAGE_CENTER.lock().unwrap().subscribe_channel(name, channel.clone());
+            Some(frame.clone())
+        },
+        Frame::Unsubscribe(_) => {
+            let uid = channel.0.lock().unwrap().uid.clone();
+            let mut center = MESSAGE_CENTER.lock().unwrap();
+            if center.channels_exist_by_name(name.to_owned()) {
+                center.unsubscribe(name, uid);
+            }
+            Some(frame.clone())
+        },
+        Frame::Publish(_, ref rlp) if exposed.access.can_publish() => {
+            match (exposed.decode)(rlp) {
+                Ok(msg) => {
+                    MESSAGE_CENTER.lock().unwrap().send(name, msg);
+                    None
+                },
+                Err(e) => Some(Frame::Error(name, format!("bad payload: {:?}", e)))
+            }
+        },
+        Frame::Error(_, _) => None,
+        _ => Some(Frame::Error(name, "access denied".to_string()))
+    }
+}
+
+/// # BridgeHandle
+/// **Usage**
+/// - returned by [Bridge::start], dropping it stops the bridge as well
+pub struct BridgeHandle {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+    connections: Arc<Mutex<Vec<Connection>>>
+}
+
+impl BridgeHandle {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Clients currently connected
+    pub fn clients(&self) -> usize {
+        self.connections.lock().unwrap().iter().filter(|conn| !conn.is_closed()).count()
+    }
+
+    /// Disconnect every client, stop listening and remove the socket file
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(acceptor) = self.acceptor.take() {
+            self.stopped.store(true, Ordering::SeqCst);
+            // wake the acceptor blocked in accept
+            let _ = UnixStream::connect(&self.path);
+            let _ = acceptor.join();
+            let connections: Vec<Connection> = self.connections.lock().unwrap().drain(..).collect();
+            for conn in connections {
+                conn.close();
+            }
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl Drop for BridgeHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// # BridgeClient
+/// **Usage**
+/// - the process side of the bridge, see [Bridge] for an example
+/// - frames arriving while waiting for an acknowledgement are kept for [BridgeClient::receive]
+pub struct BridgeClient {
+    stream: UnixStream,
+    pending: VecDeque<Frame>
+}
+
+impl BridgeClient {
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(BridgeClient {
+            stream: UnixStream::connect(path)?,
+            pending: VecDeque::new()
+        })
+    }
+
+    /// Bound the wait of [BridgeClient::receive], `None` waits forever
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    pub fn subscribe(&mut self, topic: &str) -> io::Result<()> {
+        self.request(Frame::Subscribe(topic.to_string()))
+    }
+
+    pub fn unsubscribe(&mut self, topic: &str) -> io::Result<()> {
+        self.request(Frame::Unsubscribe(topic.to_string()))
+    }
+
+    /// Publish `payload` on the bus, a refusal arrives later as an `Error` frame
+    pub fn publish<T: RLPSerialize>(&mut self, topic: &Topic<T>, payload: &T) -> io::Result<()> {
+        let rlp = payload.serialize().map_err(invalid_data)?;
+        write_frame(&mut self.stream, &Frame::Publish(topic.name().to_string(), rlp))
+    }
+
+    /// Next frame from the node
+    pub fn receive(&mut self) -> io::Result<Frame> {
+        match self.pending.pop_front() {
+            Some(frame) => Ok(frame),
+            None => read_frame(&mut self.stream)
+        }
+    }
+
+    /// Send `frame` and wait for its acknowledgement
+    fn request(&mut self, frame: Frame) -> io::Result<()> {
+        write_frame(&mut self.stream, &frame)?;
+        loop {
+            let reply = read_frame(&mut self.stream)?;
+            if reply == frame {
+                return Ok(());
+            }
+            match reply {
+                Frame::Error(ref topic, ref reason) if topic == frame.topic() => {
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason.to_owned()));
+                },
+                _ => self.pending.push_back(reply)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use random_string;
+
+    const PRICE: Topic<u64> = Topic::new("bridge-test-price");
+    const ORDERS: Topic<String> = Topic::new("bridge-test-orders");
+    const HIDDEN: Topic<u64> = Topic::new("bridge-test-hidden");
+
+    fn socket_path() -> PathBuf {
+        ::std::env::temp_dir().join(format!("gen-bridge-{}.sock", random_string(8)))
+    }
+
+    #[test]
+    fn test_frames() {
+        let frames = vec![
+            Frame::Subscribe("a".to_string()),
+            Frame::Unsubscribe("a".to_string()),
+            Frame::Publish("a".to_string(), 7u64.serialize().unwrap()),
+            Frame::Error("a".to_string(), "no".to_string())
+        ];
+        let mut data: Vec<u8> = vec![];
+        for frame in &frames {
+            write_frame(&mut data, frame).unwrap();
+        }
+        let mut input = &data[..];
+        for frame in &frames {
+            assert_eq!(&read_frame(&mut input).unwrap(), frame);
+        }
+        assert_eq!(read_frame(&mut input).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut huge = &((MAX_FRAME_SIZE + 1) as u64).to_be_bytes()[..];
+        assert_eq!(read_frame(&mut huge).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_bridge() {
+        let path = socket_path();
+        let mut bridge = Bridge::new(&path);
+        bridge.expose(&PRICE, Access::Subscribe);
+        bridge.expose(&ORDERS, Access::Both);
+        let handle = bridge.start().unwrap();
+
+        let mut client = BridgeClient::connect(&path).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(client.subscribe(HIDDEN.name()).is_err());
+        client.subscribe(PRICE.name()).unwrap();
+        client.subscribe(ORDERS.name()).unwrap();
+
+        // node to client
+        MESSAGE_CENTER.lock().unwrap().publish(&PRICE, 100u64);
+        assert_eq!(client.receive().unwrap().read(&PRICE), Some(100u64));
+
+        // client to node, the client hears its own publication as well
+        let ch = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&ORDERS);
+        client.publish(&ORDERS, &"buy".to_string()).unwrap();
+        assert_eq!(client.receive().unwrap().read(&ORDERS), Some("buy".to_string()));
+        assert_eq!(ch.0.lock().unwrap().accept_typed(&ORDERS), Some("buy".to_string()));
+        assert_eq!(handle.clients(), 1);
+
+        client.publish(&PRICE, &1u64).unwrap();
+        assert_eq!(client.receive().unwrap(), Frame::Error(PRICE.name().to_string(), "access denied".to_string()));
+
+        client.unsubscribe(PRICE.name()).unwrap();
+        MESSAGE_CENTER.lock().unwrap().publish(&PRICE, 101u64);
+        client.publish(&ORDERS, &"sell".to_string()).unwrap();
+        assert_eq!(client.receive().unwrap().read(&ORDERS), Some("sell".to_string()));
+
+        handle.shutdown();
+        assert!(!path.exists());
+        assert!(client.receive().is_err());
+        let uid = ch.0.lock().unwrap().uid.clone();
+        MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+    }
+
+    #[test]
+    fn test_socket_path() {
+        // a regular file is left alone
+        let path = socket_path();
+        fs::write(&path, b"data").unwrap();
+        assert_eq!(Bridge::new(&path).start().err().unwrap().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&path).unwrap(), b"data".to_vec());
+        fs::remove_file(&path).unwrap();
+
+        // a live socket is not taken over
+        let path = socket_path();
+        let handle = Bridge::new(&path).start().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, SOCKET_MODE);
+        assert_eq!(Bridge::new(&path).start().err().unwrap().kind(), io::ErrorKind::AddrInUse);
+        handle.shutdown();
+
+        // a socket nobody listens on is replaced
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        let handle = Bridge::new(&path).start().unwrap();
+        assert!(BridgeClient::connect(&path).is_ok());
+        handle.shutdown();
+    }
+}
@@ -1,10 +1,20 @@
 #[macro_use]
 extern crate lazy_static;
 extern crate rand;
+extern crate rlp;
 
+#[cfg(unix)]
+pub mod bridge;
+pub mod metrics;
+pub mod rpc;
+
+use std::any::Any;
 use std::sync::{Arc, Mutex, Condvar};
 use std::cell::RefCell;
 use std::collections::{LinkedList, HashMap};
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
 const DEFAULT_MSG_QUEUE_SIZE: usize = 1024;
 
@@ -23,79 +33,309 @@ fn random_string(length: usize) -> String {
     result
 }
 
+/// Typed content of a message, shared by every channel it is sent to
+#[derive(Clone)]
+pub struct Payload(Arc<dyn Any + Send + Sync>);
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Payload")
+    }
+}
+
+/// Lane of a message in a queue, a lane is only served once the ones above it are empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// commands like stop or pause, never held back by the queue limit
+    Control,
+    Normal,
+    /// traffic which may wait, like transaction gossip
+    Bulk
+}
+
+const PRIORITY_LANES: usize = 3usize;
+
+impl Priority {
+    fn lane(&self) -> usize {
+        match *self {
+            Priority::Control => 0,
+            Priority::Normal => 1,
+            Priority::Bulk => 2
+        }
+    }
+}
+
 ///
 #[derive(Debug, Clone)]
 pub struct Message {
     pub op: u16,
-    pub msg: String
+    pub msg: String,
+    pub payload: Option<Payload>,
+    pub priority: Priority
 }
 
 impl Message {
     pub fn new(op: u16, msg: String) -> Self {
         Message {
             op: op,
-            msg: msg
+            msg: msg,
+            payload: None,
+            priority: Priority::Normal
+        }
+    }
+}
+
+/// # Topic
+/// **Usage**
+/// - a named kind of message whose payload is a `T`
+/// - declare topics as constants, a misspelled topic is then a compile error
+///   and a payload of the wrong type can not be sent
+/// ## Examples
+/// ```
+/// use gen_message::{Topic, MessageCenter};
+///
+/// const HEIGHT: Topic<u64> = Topic::new("height");
+///
+/// let mut center = MessageCenter::new();
+/// let ch = center.subscribe_topic(&HEIGHT);
+/// center.publish(&HEIGHT, 42u64);
+/// let msg = ch.0.lock().unwrap().accept_msg_async().unwrap();
+/// assert_eq!(HEIGHT.read(&msg), Some(42u64));
+/// ```
+pub struct Topic<T> {
+    name: &'static str,
+    priority: Priority,
+    marker: PhantomData<fn(T)>
+}
+
+impl<T> Clone for Topic<T> {
+    fn clone(&self) -> Self {
+        Topic::with_priority(self.name, self.priority)
+    }
+}
+
+impl<T> Copy for Topic<T> {}
+
+impl<T> Topic<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Topic::with_priority(name, Priority::Normal)
+    }
+
+    /// Topic whose messages go to the `priority` lane
+    pub const fn with_priority(name: &'static str, priority: Priority) -> Self {
+        Topic {
+            name: name,
+            priority: priority,
+            marker: PhantomData
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Topic<T> {
+    /// Wrap `payload` into a message of this topic
+    pub fn message(&self, payload: T) -> Message {
+        Message {
+            op: 0u16,
+            msg: self.name.to_string(),
+            payload: Some(Payload(Arc::new(payload))),
+            priority: self.priority
+        }
+    }
+
+    pub fn matches(&self, msg: &Message) -> bool {
+        msg.msg == self.name && msg.payload.as_ref().map_or(false, |p| p.0.is::<T>())
+    }
+
+    /// Payload of `msg` if it belongs to this topic
+    pub fn read(&self, msg: &Message) -> Option<T> {
+        if msg.msg != self.name {
+            return None;
         }
+        msg.payload.as_ref().and_then(|p| p.0.downcast_ref::<T>()).cloned()
     }
 }
 
+/// What a full queue does with one more message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// the sender waits for room, see [send_blocking]
+    Block,
+    /// the oldest message of the same or a lower lane makes room
+    DropOldest,
+    /// the new message is dropped
+    DropNewest,
+    /// the queue grows past its limit up to the given hard cap, then drops the newest
+    Grow(usize)
+}
+
 ///
 pub struct MessageQueue {
-    queue: LinkedList<Message>,
-    limit: usize
+    lanes: Vec<LinkedList<Message>>,
+    limit: usize,
+    policy: OverflowPolicy,
+    enqueued: usize,
+    dropped: usize,
+    delivered: usize
 }
 
 impl MessageQueue {
     pub fn new(limit: usize) -> Self {
+        MessageQueue::new_with_policy(limit, OverflowPolicy::DropNewest)
+    }
+
+    pub fn new_with_policy(limit: usize, policy: OverflowPolicy) -> Self {
         MessageQueue {
-            queue: LinkedList::<Message>::new(),
-            limit: limit
+            lanes: (0..PRIORITY_LANES).map(|_| LinkedList::<Message>::new()).collect(),
+            limit: limit,
+            policy: policy,
+            enqueued: 0usize,
+            dropped: 0usize,
+            delivered: 0usize
         }
     }
 
     pub fn size(&self) -> usize {
-        self.queue.len()
+        self.lanes.iter().map(|lane| lane.len()).sum()
     }
 
     pub fn limit(&self) -> usize {
         self.limit
     }
 
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Messages queued so far
+    pub fn enqueued(&self) -> usize {
+        self.enqueued
+    }
+
+    /// Messages lost to the overflow policy or discarded unread so far
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Messages taken by the receiver so far
+    pub fn delivered(&self) -> usize {
+        self.delivered
+    }
+
+    /// Count a message the sender gave up on
+    pub fn record_dropped(&mut self) {
+        self.dropped = self.dropped + 1;
+    }
+
+    /// Queue `msg` following the overflow policy. On `Err` the message is not queued,
+    /// it is counted as dropped unless the policy is to block.
     pub fn enqueue_msg(&mut self, msg: Message) -> Result<usize, &'static str> {
-        let cur_size = self.queue.len();
-        if cur_size >= self.limit {
-            Err("The message queue is full.")
-        } else {
-            self.queue.push_back(msg);
-            Ok(cur_size + 1)
+        let result = self.push_msg(msg);
+        if result.is_ok() {
+            self.enqueued = self.enqueued + 1;
+        }
+        result
+    }
+
+    fn push_msg(&mut self, msg: Message) -> Result<usize, &'static str> {
+        let cur_size = self.size();
+        let lane = msg.priority.lane();
+        if cur_size < self.limit || msg.priority == Priority::Control {
+            self.lanes[lane].push_back(msg);
+            return Ok(cur_size + 1);
+        }
+        match self.policy {
+            OverflowPolicy::Block => Err("The message queue is full."),
+            OverflowPolicy::DropOldest => {
+                self.dropped = self.dropped + 1;
+                match (lane..PRIORITY_LANES).rev().find(|l| !self.lanes[*l].is_empty()) {
+                    Some(victim) => {
+                        self.lanes[victim].pop_front();
+                        self.lanes[lane].push_back(msg);
+                        Ok(cur_size)
+                    },
+                    None => Err("The message queue is full of higher priority messages.")
+                }
+            },
+            OverflowPolicy::DropNewest => {
+                self.dropped = self.dropped + 1;
+                Err("The message queue is full.")
+            },
+            OverflowPolicy::Grow(cap) => {
+                if cur_size < cap {
+                    self.lanes[lane].push_back(msg);
+                    Ok(cur_size + 1)
+                } else {
+                    self.dropped = self.dropped + 1;
+                    Err("The message queue reached its hard cap.")
+                }
+            }
         }
     }
 
     pub fn dequeue_msg(&mut self) -> Option<Message> {
-        self.queue.pop_front()
+        let msg = self.lanes.iter_mut().filter_map(|lane| lane.pop_front()).next();
+        if msg.is_some() {
+            self.delivered = self.delivered + 1;
+        }
+        msg
+    }
+
+    /// Remove the first message accepted by `filter`, the others keep their order
+    pub fn dequeue_msg_where<F>(&mut self, filter: F) -> Option<Message> where F: Fn(&Message) -> bool {
+        for lane in self.lanes.iter_mut() {
+            if let Some(pos) = lane.iter().position(|msg| filter(msg)) {
+                let mut tail = lane.split_off(pos);
+                let result = tail.pop_front();
+                lane.append(&mut tail);
+                self.delivered = self.delivered + 1;
+                return result;
+            }
+        }
+        None
     }
 
+    /// Take every message, highest lane first
     pub fn flush(&mut self) -> LinkedList<Message> {
         let mut result = LinkedList::<Message>::new();
-        loop {
-            if self.queue.is_empty() { break; }
-            result.push_front(self.queue.pop_back().unwrap());
+        for lane in self.lanes.iter_mut() {
+            result.append(lane);
         }
+        self.delivered = self.delivered + result.len();
         result
     }
 
+    /// Drop every message unread, returns how many were dropped
+    pub fn discard(&mut self) -> usize {
+        let count = self.size();
+        for lane in self.lanes.iter_mut() {
+            lane.clear();
+        }
+        self.dropped = self.dropped + count;
+        count
+    }
+
     pub fn get_size(&self) -> usize {
-        self.queue.len()
+        self.size()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+        self.lanes.iter().all(|lane| lane.is_empty())
     }
 }
 
 pub struct MessageChannel {
     pub uid: String,
-    pub queue: RefCell<MessageQueue>
+    pub queue: RefCell<MessageQueue>,
+    /// signaled when messages are taken, blocked senders wait on it
+    room: Arc<Condvar>
 }
 
 impl PartialEq for MessageChannel {
@@ -109,11 +349,7 @@ unsafe impl std::marker::Sync for MessageChannel {}
 
 impl MessageChannel {
     pub fn new() -> Self {
-        let msg_queue = MessageQueue::new(DEFAULT_MSG_QUEUE_SIZE);
-        MessageChannel {
-            uid: random_string(32),
-            queue: RefCell::new(msg_queue)
-        }
+        MessageChannel::new_with_size(DEFAULT_MSG_QUEUE_SIZE)
     }
 
     pub fn queue_size(&self) -> usize {
@@ -121,10 +357,15 @@ impl MessageChannel {
     }
 
     pub fn new_with_size(size: usize) -> Self {
-        let msg_queue = MessageQueue::new(size);
+        MessageChannel::new_with_policy(size, OverflowPolicy::DropNewest)
+    }
+
+    pub fn new_with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        let msg_queue = MessageQueue::new_with_policy(size, policy);
         MessageChannel {
             uid: random_string(32),
-            queue: RefCell::new(msg_queue)
+            queue: RefCell::new(msg_queue),
+            room: Arc::new(Condvar::new())
         }
     }
 
@@ -134,18 +375,112 @@ impl MessageChannel {
     }
 
     pub fn send_msg_without_cache(&mut self, msg: Message) -> Result<usize, &'static str> {
-        self.flush();
+        let discarded = self.queue.get_mut().discard();
+        if discarded > 0 {
+            self.room.notify_all();
+        }
         self.send_msg(msg)
     }
 
     pub fn accept_msg_async(&mut self) -> Option<Message> {
-        self.queue.get_mut().dequeue_msg().to_owned()
+        let msg = self.queue.get_mut().dequeue_msg();
+        if msg.is_some() {
+            self.room.notify_all();
+        }
+        msg
+    }
+
+    /// Take the next message of `topic`, messages of other topics stay queued
+    pub fn accept_typed<T: Clone + Send + Sync + 'static>(&mut self, topic: &Topic<T>) -> Option<T> {
+        let msg = self.queue.get_mut().dequeue_msg_where(|msg| topic.matches(msg));
+        if msg.is_some() {
+            self.room.notify_all();
+        }
+        msg.and_then(|msg| topic.read(&msg))
     }
 
     pub fn flush(&mut self) -> LinkedList<Message> {
         let result = self.queue.get_mut().flush();
+        self.room.notify_all();
         result
     }
+
+    pub fn stats(&self) -> ChannelStats {
+        let queue = self.queue.borrow();
+        ChannelStats {
+            uid: self.uid.clone(),
+            depth: queue.size(),
+            limit: queue.limit(),
+            policy: queue.policy(),
+            enqueued: queue.enqueued(),
+            dropped: queue.dropped(),
+            delivered: queue.delivered()
+        }
+    }
+}
+
+/// Depth, limit and counters of a channel when it was inspected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub uid: String,
+    pub depth: usize,
+    pub limit: usize,
+    pub policy: OverflowPolicy,
+    pub enqueued: usize,
+    pub dropped: usize,
+    pub delivered: usize
+}
+
+/// Subscribers of a topic or channel name, see [MessageCenter::stats]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicStats {
+    pub name: String,
+    pub channels: Vec<ChannelStats>
+}
+
+impl TopicStats {
+    /// Messages waiting in all subscribers
+    pub fn depth(&self) -> usize {
+        self.channels.iter().map(|ch| ch.depth).sum()
+    }
+}
+
+/// Queue `msg` into `ch` and wake its receiver. With the blocking policy wait for room
+/// until `deadline`, without one a full queue counts the message as dropped.
+fn deliver(ch: &Arc<(Mutex<MessageChannel>, Condvar)>, msg: &Message, deadline: Option<Instant>) -> bool {
+    let mut guard = ch.0.lock().unwrap();
+    let room = guard.room.clone();
+    loop {
+        if guard.send_msg(msg.to_owned()).is_ok() {
+            ch.1.notify_all();
+            return true;
+        }
+        if guard.queue.get_mut().policy() != OverflowPolicy::Block {
+            return false;
+        }
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if now < deadline => {
+                guard = room.wait_timeout(guard, deadline - now).unwrap().0;
+            },
+            _ => {
+                guard.queue.get_mut().record_dropped();
+                return false;
+            }
+        }
+    }
+}
+
+/// # send_blocking(3)
+/// **Usage**
+/// - send through [MESSAGE_CENTER] like [MessageCenter::send], but wait up to `timeout`
+///   for room in full channels whose policy is [OverflowPolicy::Block]
+/// - the center is not locked while waiting
+/// **Return**: number of channels which queued the message
+pub fn send_blocking(name: String, msg: Message, timeout: Duration) -> usize {
+    let deadline = Instant::now() + timeout;
+    let channels = MESSAGE_CENTER.lock().unwrap().channels_by_name(name);
+    channels.iter().filter(|ch| deliver(ch, &msg, Some(deadline))).count()
 }
 
 pub struct MessageCenter {
@@ -160,14 +495,32 @@ impl MessageCenter {
         }
     }
 
+    /// Messages waiting in all channels named `name`
     pub fn queue_size(&mut self, name: String) -> usize {
-        let existed = self.channels_exist_by_name(name.to_owned());
-        if existed {
-            self.channels_by_name(name.to_owned()).iter().map(|ch| {
-                ch.0.lock().unwrap().queue_size()
-            });
-            1
-        } else { 0 }
+        self.topic_stats(name).map_or(0, |stats| stats.depth())
+    }
+
+    /// Names and topics with at least one subscriber, sorted
+    pub fn topics(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.channel_map.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn topic_stats(&self, name: String) -> Option<TopicStats> {
+        self.channel_map.get(&name).map(|chs| TopicStats {
+            name: name.to_owned(),
+            channels: chs.iter().map(|ch| ch.0.lock().unwrap().stats()).collect()
+        })
+    }
+
+    /// # stats(0)
+    /// **Usage**
+    /// - snapshot of every topic with the depth, limit and counters of its subscribers
+    /// - a channel subscribed to several topics shows up under each of them
+    /// **Return**: topics sorted by name
+    pub fn stats(&self) -> Vec<TopicStats> {
+        self.topics().into_iter().filter_map(|name| self.topic_stats(name)).collect()
     }
 
     pub fn subscribe(&mut self, name: String) -> Arc<(Mutex<MessageChannel>, Condvar)> {
@@ -199,17 +552,50 @@ impl MessageCenter {
         }
     }
 
-    pub fn send(&mut self, name: String, msg: Message) {
-        let existed = self.channels_exist_by_name(name.to_owned());
-        if !existed {
-            return;
-        } else {
-            let channels = self.channel_map.get_mut(&name).unwrap();
-            for i in 0 .. channels.len() {
-                let mut ch = channels[i].clone();
-                ch.0.lock().unwrap().send_msg(msg.to_owned());
-                ch.1.notify_all();
-            }
+    /// Also deliver the messages sent to `name` into an existing channel
+    pub fn subscribe_channel(&mut self, name: String, channel: Arc<(Mutex<MessageChannel>, Condvar)>) {
+        let uid = channel.0.lock().unwrap().uid.clone();
+        let chs = self.channel_map.entry(name).or_insert(vec![]);
+        if !chs.iter().any(|ch| ch.0.lock().unwrap().uid == uid) {
+            chs.push(channel);
+        }
+    }
+
+    /// Subscribe a channel with its own limit and overflow policy
+    pub fn subscribe_with_policy(&mut self, name: String, limit: usize, policy: OverflowPolicy) -> Arc<(Mutex<MessageChannel>, Condvar)> {
+        let channel = Arc::new((Mutex::new(MessageChannel::new_with_policy(limit, policy)), Condvar::new()));
+        self.channel_map.entry(name).or_insert(vec![]).push(channel.clone());
+        channel
+    }
+
+    pub fn subscribe_topic<T>(&mut self, topic: &Topic<T>) -> Arc<(Mutex<MessageChannel>, Condvar)> {
+        self.subscribe(topic.name().to_string())
+    }
+
+    /// Remove the channel `uid` from every name and topic it is subscribed to
+    pub fn unsubscribe_channel(&mut self, uid: String) {
+        for chs in self.channel_map.values_mut() {
+            chs.retain(|ch| ch.0.lock().unwrap().uid != uid);
+        }
+        self.channel_map.retain(|_, chs| !chs.is_empty());
+    }
+
+    /// Send `payload` to every subscriber of `topic`
+    pub fn publish<T: Clone + Send + Sync + 'static>(&mut self, topic: &Topic<T>, payload: T) -> usize {
+        self.send(topic.name().to_string(), topic.message(payload))
+    }
+
+    /// Send `payload` as a message of `topic` to the channels named `name`
+    pub fn send_typed<T: Clone + Send + Sync + 'static>(&mut self, name: String, topic: &Topic<T>, payload: T) -> usize {
+        self.send(name, topic.message(payload))
+    }
+
+    /// Send `msg` to every channel named `name` without waiting,
+    /// returns the number of channels which queued it
+    pub fn send(&mut self, name: String, msg: Message) -> usize {
+        match self.channel_map.get(&name) {
+            Some(channels) => channels.iter().filter(|ch| deliver(ch, &msg, None)).count(),
+            None => 0
         }
     }
 
@@ -229,4 +615,177 @@ impl MessageCenter {
             None => false
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Joined(usize)
+    }
+
+    const EVENTS: Topic<Event> = Topic::new("events");
+    const HEIGHT: Topic<u64> = Topic::new("height");
+
+    #[test]
+    fn test_typed_receive() {
+        let mut center = MessageCenter::new();
+        let ch = center.subscribe("controller".to_string());
+        center.subscribe_channel(EVENTS.name().to_string(), ch.clone());
+
+        center.send("controller".to_string(), Message::new(1, "plain".to_string()));
+        center.publish(&EVENTS, Event::Joined(3));
+        center.send_typed("controller".to_string(), &HEIGHT, 9u64);
+
+        let mut channel = ch.0.lock().unwrap();
+        assert_eq!(channel.accept_typed(&HEIGHT), Some(9u64));
+        assert_eq!(channel.accept_typed(&EVENTS), Some(Event::Joined(3)));
+        assert_eq!(channel.accept_typed(&EVENTS), None);
+        assert_eq!(channel.accept_msg_async().map(|msg| msg.msg), Some("plain".to_string()));
+    }
+
+    #[test]
+    fn test_wrong_payload_type() {
+        // same name, different payload type
+        const FAKE: Topic<u32> = Topic::new("height");
+        let msg = HEIGHT.message(1u64);
+        assert!(!FAKE.matches(&msg));
+        assert_eq!(FAKE.read(&msg), None);
+        assert_eq!(HEIGHT.read(&Message::new(0, "height".to_string())), None);
+    }
+
+    #[test]
+    fn test_unsubscribe_channel() {
+        let mut center = MessageCenter::new();
+        let ch = center.subscribe("controller".to_string());
+        center.subscribe_channel(EVENTS.name().to_string(), ch.clone());
+        center.subscribe_channel(EVENTS.name().to_string(), ch.clone());
+        assert_eq!(center.channels_by_name(EVENTS.name().to_string()).len(), 1);
+
+        let uid = ch.0.lock().unwrap().uid.clone();
+        center.unsubscribe_channel(uid);
+        assert!(!center.channels_exist_by_name("controller".to_string()));
+        assert!(!center.channels_exist_by_name(EVENTS.name().to_string()));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut center = MessageCenter::new();
+        let ch = center.subscribe_with_policy("slow".to_string(), 2, OverflowPolicy::DropNewest);
+        center.subscribe_channel(EVENTS.name().to_string(), ch.clone());
+        center.subscribe("fast".to_string());
+        for op in 0..3 {
+            center.send("slow".to_string(), Message::new(op, "slow".to_string()));
+        }
+        ch.0.lock().unwrap().accept_msg_async();
+        center.publish(&EVENTS, Event::Joined(1));
+
+        assert_eq!(center.topics(), vec!["events".to_string(), "fast".to_string(), "slow".to_string()]);
+        assert_eq!(center.queue_size("slow".to_string()), 2);
+        assert_eq!(center.queue_size("fast".to_string()), 0);
+        assert_eq!(center.queue_size("nobody".to_string()), 0);
+
+        let stats = center.topic_stats("slow".to_string()).unwrap();
+        let uid = ch.0.lock().unwrap().uid.clone();
+        assert_eq!(stats.channels, vec![ChannelStats {
+            uid: uid,
+            depth: 2,
+            limit: 2,
+            policy: OverflowPolicy::DropNewest,
+            enqueued: 3,
+            dropped: 1,
+            delivered: 1
+        }]);
+        // the channel is listed under both of its subscriptions
+        assert_eq!(center.stats().iter().map(|topic| topic.depth()).collect::<Vec<usize>>(), vec![2, 0, 2]);
+
+        ch.0.lock().unwrap().send_msg_without_cache(Message::new(9, "slow".to_string())).unwrap();
+        assert_eq!(ch.0.lock().unwrap().stats().dropped, 3);
+    }
+
+    fn bulk(op: u16) -> Message {
+        let mut msg = Message::new(op, "bulk".to_string());
+        msg.priority = Priority::Bulk;
+        msg
+    }
+
+    fn control(op: u16) -> Message {
+        let mut msg = Message::new(op, "control".to_string());
+        msg.priority = Priority::Control;
+        msg
+    }
+
+    fn ops(queue: &mut MessageQueue) -> Vec<u16> {
+        queue.flush().into_iter().map(|msg| msg.op).collect()
+    }
+
+    #[test]
+    fn test_priority_lanes() {
+        let mut queue = MessageQueue::new(3);
+        queue.enqueue_msg(bulk(1)).unwrap();
+        queue.enqueue_msg(Message::new(2, "normal".to_string())).unwrap();
+        queue.enqueue_msg(bulk(3)).unwrap();
+        // control commands are taken even when the queue is full
+        assert_eq!(queue.enqueue_msg(control(4)), Ok(4));
+        assert!(queue.enqueue_msg(bulk(5)).is_err());
+        assert_eq!(queue.dropped(), 1);
+
+        assert_eq!(queue.dequeue_msg().map(|msg| msg.op), Some(4));
+        assert_eq!(ops(&mut queue), vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let mut oldest = MessageQueue::new_with_policy(2, OverflowPolicy::DropOldest);
+        oldest.enqueue_msg(Message::new(1, "normal".to_string())).unwrap();
+        oldest.enqueue_msg(bulk(2)).unwrap();
+        // a normal message evicts the bulk one first
+        oldest.enqueue_msg(Message::new(3, "normal".to_string())).unwrap();
+        oldest.enqueue_msg(Message::new(4, "normal".to_string())).unwrap();
+        // a bulk message can not evict normal ones
+        assert!(oldest.enqueue_msg(bulk(5)).is_err());
+        assert_eq!(oldest.dropped(), 3);
+        assert_eq!(ops(&mut oldest), vec![3, 4]);
+
+        let mut newest = MessageQueue::new_with_policy(1, OverflowPolicy::DropNewest);
+        newest.enqueue_msg(bulk(1)).unwrap();
+        assert!(newest.enqueue_msg(bulk(2)).is_err());
+        assert_eq!(ops(&mut newest), vec![1]);
+
+        let mut grow = MessageQueue::new_with_policy(1, OverflowPolicy::Grow(3));
+        for op in 0..4 {
+            let _ = grow.enqueue_msg(bulk(op));
+        }
+        assert_eq!(grow.dropped(), 1);
+        assert_eq!(ops(&mut grow), vec![0, 1, 2]);
+
+        let mut block = MessageQueue::new_with_policy(1, OverflowPolicy::Block);
+        block.enqueue_msg(bulk(1)).unwrap();
+        assert!(block.enqueue_msg(bulk(2)).is_err());
+        // a blocked sender decides whether the message is lost
+        assert_eq!(block.dropped(), 0);
+    }
+
+    #[test]
+    fn test_send_blocking() {
+        let ch = MESSAGE_CENTER.lock().unwrap().subscribe_with_policy("test-blocking".to_string(), 1, OverflowPolicy::Block);
+        assert_eq!(MESSAGE_CENTER.lock().unwrap().send("test-blocking".to_string(), bulk(1)), 1);
+        // without waiting the message is lost, and counted
+        assert_eq!(MESSAGE_CENTER.lock().unwrap().send("test-blocking".to_string(), bulk(2)), 0);
+        assert_eq!(ch.0.lock().unwrap().queue.borrow().dropped(), 1);
+
+        let consumer = ch.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            consumer.0.lock().unwrap().accept_msg_async().map(|msg| msg.op)
+        });
+        assert_eq!(send_blocking("test-blocking".to_string(), bulk(3), Duration::from_secs(5)), 1);
+        assert_eq!(handle.join().unwrap(), Some(1));
+        assert_eq!(send_blocking("test-blocking".to_string(), bulk(4), Duration::from_millis(10)), 0);
+        assert_eq!(ch.0.lock().unwrap().accept_msg_async().map(|msg| msg.op), Some(3));
+        let uid = ch.0.lock().unwrap().uid.clone();
+        MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+    }
+}
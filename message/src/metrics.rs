@@ -0,0 +1,147 @@
+//! Export of the [MessageCenter] counters in the Prometheus text format.
+//!
+//! Every series is labelled with the topic and the channel uid. A controller subscribes under
+//! its own name, so a growing `gen_message_queue_depth` of that name shows it falling behind.
+//! The reply channels of [rpc](::rpc) calls are left out, each lives for one call only and
+//! would be a new series every time.
+
+use std::fmt::Write;
+
+use rpc::is_reply_channel;
+use {TopicStats, ChannelStats, MESSAGE_CENTER};
+
+struct Series {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    value: fn(&ChannelStats) -> usize
+}
+
+const SERIES: [Series; 5] = [
+    Series {
+        name: "gen_message_queue_depth",
+        kind: "gauge",
+        help: "Messages waiting in the channel",
+        value: depth
+    },
+    Series {
+        name: "gen_message_queue_limit",
+        kind: "gauge",
+        help: "Soft limit of the channel queue",
+        value: limit
+    },
+    Series {
+        name: "gen_message_enqueued_total",
+        kind: "counter",
+        help: "Messages queued into the channel",
+        value: enqueued
+    },
+    Series {
+        name: "gen_message_dropped_total",
+        kind: "counter",
+        help: "Messages lost to the overflow policy or discarded unread",
+        value: dropped
+    },
+    Series {
+        name: "gen_message_delivered_total",
+        kind: "counter",
+        help: "Messages taken by the receiver",
+        value: delivered
+    }
+];
+
+fn depth(ch: &ChannelStats) -> usize { ch.depth }
+fn limit(ch: &ChannelStats) -> usize { ch.limit }
+fn enqueued(ch: &ChannelStats) -> usize { ch.enqueued }
+fn dropped(ch: &ChannelStats) -> usize { ch.dropped }
+fn delivered(ch: &ChannelStats) -> usize { ch.delivered }
+
+/// Escape a label value, topic names are free text
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// # render(1)
+/// **Usage**
+/// - format a snapshot taken with `MessageCenter::stats`
+/// **Return**: the exposition text, one sample per line
+/// ## Examples
+/// ```
+/// use gen_message::MessageCenter;
+/// use gen_message::metrics::render;
+///
+/// let mut center = MessageCenter::new();
+/// let ch = center.subscribe("p2p".to_string());
+/// let uid = ch.0.lock().unwrap().uid.clone();
+/// center.send("p2p".to_string(), gen_message::Message::new(0, "ping".to_string()));
+///
+/// let text = render(&center.stats());
+/// assert!(text.contains(&format!("gen_message_queue_depth{{topic=\"p2p\",channel=\"{}\"}} 1", uid)));
+/// ```
+pub fn render(stats: &[TopicStats]) -> String {
+    let mut out = String::new();
+    for series in SERIES.iter() {
+        let _ = writeln!(out, "# HELP {} {}", series.name, series.help);
+        let _ = writeln!(out, "# TYPE {} {}", series.name, series.kind);
+        for topic in stats.iter().filter(|topic| !is_reply_channel(&topic.name)) {
+            for ch in &topic.channels {
+                let _ = writeln!(out, "{}{{topic=\"{}\",channel=\"{}\"}} {}",
+                                 series.name, escape(&topic.name), escape(&ch.uid), (series.value)(ch));
+            }
+        }
+    }
+    out
+}
+
+/// Metrics of [MESSAGE_CENTER], for the node's metrics endpoint
+pub fn export() -> String {
+    let stats = MESSAGE_CENTER.lock().unwrap().stats();
+    render(&stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OverflowPolicy;
+
+    #[test]
+    fn test_render() {
+        let stats = vec![TopicStats {
+            name: "a\"b".to_string(),
+            channels: vec![ChannelStats {
+                uid: "uid".to_string(),
+                depth: 2,
+                limit: 8,
+                policy: OverflowPolicy::DropNewest,
+                enqueued: 5,
+                dropped: 1,
+                delivered: 3
+            }]
+        }];
+        let text = render(&stats);
+        assert!(text.contains("# TYPE gen_message_dropped_total counter\n"));
+        assert!(text.contains("gen_message_queue_depth{topic=\"a\\\"b\",channel=\"uid\"} 2\n"));
+        assert!(text.contains("gen_message_enqueued_total{topic=\"a\\\"b\",channel=\"uid\"} 5\n"));
+        assert!(text.contains("gen_message_delivered_total{topic=\"a\\\"b\",channel=\"uid\"} 3\n"));
+        assert_eq!(text.lines().count(), 3 * SERIES.len());
+    }
+
+    #[test]
+    fn test_render_skips_replies() {
+        let stats = vec![TopicStats {
+            name: "rpc-reply-7".to_string(),
+            channels: vec![ChannelStats {
+                uid: "uid".to_string(),
+                depth: 0,
+                limit: 8,
+                policy: OverflowPolicy::DropNewest,
+                enqueued: 1,
+                dropped: 0,
+                delivered: 1
+            }]
+        }];
+        let text = render(&stats);
+        assert!(!text.contains("rpc-reply"));
+        assert_eq!(text.lines().count(), 2 * SERIES.len());
+    }
+}
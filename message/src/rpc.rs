@@ -0,0 +1,318 @@
+//! Request/response calls between controllers on top of the [MessageCenter].
+//!
+//! A caller subscribes a private reply channel, publishes a [Request] on the topic of a
+//! [Method] and waits on the reply channel for the [Response] carrying the same correlation id.
+//! Cancelling a call drops its reply channel, a late response then goes nowhere.
+//!
+//! A controller must not call a method it answers itself, it would wait for its own reply.
+//!
+//! Locks are taken in the order [MESSAGE_CENTER] then channel. [Method::reply] and
+//! [Request::is_cancelled] lock the [MESSAGE_CENTER], so a responder drops the guard of its
+//! channel before calling them.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use {MessageChannel, Message, Topic, MESSAGE_CENTER};
+
+/// Topic of every response, the reply channel is private to one call anyway
+const RPC_REPLY: &'static str = "rpc-reply";
+
+static NEXT_CALL_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcError {
+    /// nobody subscribed to the method
+    NoResponder,
+    /// no response within the timeout
+    Timeout
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RpcError::NoResponder => write!(f, "no controller answers this method"),
+            RpcError::Timeout => write!(f, "call timed out")
+        }
+    }
+}
+
+/// A request as the responder receives it
+#[derive(Debug, Clone)]
+pub struct Request<Req> {
+    pub id: u64,
+    pub body: Req,
+    reply_to: String
+}
+
+impl<Req> Request<Req> {
+    /// The caller gave up, answering is pointless
+    pub fn is_cancelled(&self) -> bool {
+        !MESSAGE_CENTER.lock().unwrap().channels_exist_by_name(self.reply_to.to_owned())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Response<Resp> {
+    id: u64,
+    body: Resp
+}
+
+/// True for the name of the private reply channel of one call
+pub fn is_reply_channel(name: &str) -> bool {
+    name.starts_with(RPC_REPLY) && name[RPC_REPLY.len()..].starts_with('-')
+}
+
+fn reply_topic<Resp>() -> Topic<Response<Resp>> {
+    Topic::new(RPC_REPLY)
+}
+
+/// # Method
+/// **Usage**
+/// - a named call taking a `Req` and answered with a `Resp`
+/// - responders subscribe to [Method::topic] and answer with [Method::reply]
+/// - the channel guard is dropped before [Method::reply], which locks the [MESSAGE_CENTER]
+/// ## Examples
+/// ```
+/// use gen_message::MESSAGE_CENTER;
+/// use gen_message::rpc::{call, Method};
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// const DOUBLE: Method<u32, u32> = Method::new("double");
+///
+/// let ch = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&DOUBLE.topic());
+/// let responder = thread::spawn(move || {
+///     let req = {
+///         let mut guard = ch.0.lock().unwrap();
+///         loop {
+///             if let Some(req) = guard.accept_typed(&DOUBLE.topic()) {
+///                 break req;
+///             }
+///             guard = ch.1.wait(guard).unwrap();
+///         }
+///     };
+///     DOUBLE.reply(&req, req.body * 2);
+/// });
+/// assert_eq!(call(&DOUBLE, 21, Duration::from_secs(5)), Ok(42));
+/// responder.join().unwrap();
+/// ```
+pub struct Method<Req, Resp> {
+    name: &'static str,
+    marker: PhantomData<fn(Req) -> Resp>
+}
+
+impl<Req, Resp> Clone for Method<Req, Resp> {
+    fn clone(&self) -> Self {
+        Method::new(self.name)
+    }
+}
+
+impl<Req, Resp> Copy for Method<Req, Resp> {}
+
+impl<Req, Resp> Method<Req, Resp> {
+    pub const fn new(name: &'static str) -> Self {
+        Method {
+            name: name,
+            marker: PhantomData
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Topic the requests are published on
+    pub fn topic(&self) -> Topic<Request<Req>> {
+        Topic::new(self.name)
+    }
+}
+
+impl<Req, Resp> Method<Req, Resp>
+    where Req: Clone + Send + Sync + 'static, Resp: Clone + Send + Sync + 'static {
+    /// Request carried by `msg` if it is a call of this method
+    pub fn read_request(&self, msg: &Message) -> Option<Request<Req>> {
+        self.topic().read(msg)
+    }
+
+    /// Answer `req`, dropped if the caller already gave up
+    pub fn reply(&self, req: &Request<Req>, resp: Resp) {
+        MESSAGE_CENTER.lock().unwrap().send_typed(
+            req.reply_to.to_owned(),
+            &reply_topic::<Resp>(),
+            Response { id: req.id, body: resp }
+        );
+    }
+}
+
+/// # PendingCall
+/// **Usage**
+/// - a call sent with [call_async] whose response is not taken yet
+/// - dropping it cancels the call
+pub struct PendingCall<Resp> {
+    id: u64,
+    channel: Arc<(Mutex<MessageChannel>, Condvar)>,
+    marker: PhantomData<fn() -> Resp>
+}
+
+impl<Resp: Clone + Send + Sync + 'static> PendingCall<Resp> {
+    /// Correlation id of the call
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Wait at most `timeout` for the response, the call stays pending on timeout
+    pub fn wait(&self, timeout: Duration) -> Result<Resp, RpcError> {
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.channel.0.lock().unwrap();
+        loop {
+            while let Some(resp) = guard.accept_typed(&reply_topic::<Resp>()) {
+                if resp.id == self.id {
+                    return Ok(resp.body);
+                }
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RpcError::Timeout);
+            }
+            guard = self.channel.1.wait_timeout(guard, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Take the response if it already arrived, never blocks
+    pub fn try_wait(&self) -> Option<Resp> {
+        let mut guard = self.channel.0.lock().unwrap();
+        while let Some(resp) = guard.accept_typed(&reply_topic::<Resp>()) {
+            if resp.id == self.id {
+                return Some(resp.body);
+            }
+        }
+        None
+    }
+
+    pub fn cancel(self) {}
+}
+
+impl<Resp> Drop for PendingCall<Resp> {
+    fn drop(&mut self) {
+        let uid = self.channel.0.lock().unwrap().uid.clone();
+        MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+    }
+}
+
+/// Send a request without waiting for the response
+pub fn call_async<Req, Resp>(method: &Method<Req, Resp>, req: Req) -> Result<PendingCall<Resp>, RpcError>
+    where Req: Clone + Send + Sync + 'static, Resp: Clone + Send + Sync + 'static {
+    let id = NEXT_CALL_ID.fetch_add(1, Ordering::SeqCst) as u64;
+    let reply_to = format!("{}-{}", RPC_REPLY, id);
+
+    let mut center = MESSAGE_CENTER.lock().unwrap();
+    if !center.channels_exist_by_name(method.name().to_string()) {
+        return Err(RpcError::NoResponder);
+    }
+    let channel = center.subscribe(reply_to.to_owned());
+    center.publish(&method.topic(), Request {
+        id: id,
+        body: req,
+        reply_to: reply_to
+    });
+
+    Ok(PendingCall {
+        id: id,
+        channel: channel,
+        marker: PhantomData
+    })
+}
+
+/// Call `method` and wait at most `timeout` for the response
+pub fn call<Req, Resp>(method: &Method<Req, Resp>, req: Req, timeout: Duration) -> Result<Resp, RpcError>
+    where Req: Clone + Send + Sync + 'static, Resp: Clone + Send + Sync + 'static {
+    call_async(method, req)?.wait(timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const SQUARE: Method<u64, u64> = Method::new("rpc-test-square");
+    const HALF: Method<u64, u64> = Method::new("rpc-test-half");
+    const SILENT: Method<(), ()> = Method::new("rpc-test-silent");
+    const NOBODY: Method<(), ()> = Method::new("rpc-test-nobody");
+
+    fn next_request<Req: Clone + Send + Sync + 'static, Resp>(
+        ch: &Arc<(Mutex<MessageChannel>, Condvar)>, method: &Method<Req, Resp>) -> Request<Req> {
+        let mut guard = ch.0.lock().unwrap();
+        loop {
+            if let Some(req) = guard.accept_typed(&method.topic()) {
+                return req;
+            }
+            guard = ch.1.wait(guard).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_call() {
+        let ch = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&SQUARE.topic());
+        let responder = thread::spawn(move || {
+            for _ in 0..2 {
+                let req = next_request(&ch, &SQUARE);
+                SQUARE.reply(&req, req.body * req.body);
+            }
+        });
+        assert_eq!(call(&SQUARE, 3, Duration::from_secs(5)), Ok(9));
+
+        let pending = call_async(&SQUARE, 4).unwrap();
+        assert_eq!(pending.wait(Duration::from_secs(5)), Ok(16));
+        responder.join().unwrap();
+    }
+
+    #[test]
+    fn test_reply_channel_name() {
+        assert!(is_reply_channel(&format!("{}-{}", RPC_REPLY, 7)));
+        assert!(!is_reply_channel(RPC_REPLY));
+        assert!(!is_reply_channel("rpc-replyer"));
+        assert!(!is_reply_channel("p2p"));
+    }
+
+    #[test]
+    fn test_try_wait() {
+        let ch = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&HALF.topic());
+        let pending = call_async(&HALF, 8).unwrap();
+        assert_eq!(pending.try_wait(), None);
+
+        let req = next_request(&ch, &HALF);
+        HALF.reply(&req, req.body / 2);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(resp) = pending.try_wait() {
+                assert_eq!(resp, 4);
+                break;
+            }
+            assert!(Instant::now() < deadline, "response never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+        // the response is taken once
+        assert_eq!(pending.try_wait(), None);
+    }
+
+    #[test]
+    fn test_timeout_and_cancel() {
+        assert_eq!(call(&NOBODY, (), Duration::from_secs(5)).err(), Some(RpcError::NoResponder));
+
+        let ch = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&SILENT.topic());
+        let pending = call_async(&SILENT, ()).unwrap();
+        assert_eq!(pending.wait(Duration::from_millis(10)), Err(RpcError::Timeout));
+
+        let req = next_request(&ch, &SILENT);
+        assert_eq!(req.id, pending.id());
+        assert!(!req.is_cancelled());
+        pending.cancel();
+        assert!(req.is_cancelled());
+        // answering a cancelled call is harmless
+        SILENT.reply(&req, ());
+    }
+}
@@ -1,6 +1,5 @@
 use chrono::*;
 use nat::*;
-use net_config::*;
 use network_eventloop::*;
 use peer::*;
 use message::protocol::*;
@@ -8,7 +7,8 @@ use session::*;
 
 use common::address::Address as Account;
 use common::gen_message::*;
-use common::thread::{Thread, ThreadStatus};
+use common::node_config::node_config;
+use common::thread::{Thread, ThreadHandle, ThreadStatus};
 use common::observe::Observe;
 
 use mio::*;
@@ -21,7 +21,6 @@ use std::rc::Rc;
 use std::sync::{Mutex, Arc, Condvar};
 use std::net::*;
 use std::str::FromStr;
-use std::time::Duration;
 use std::thread;
 
 pub struct ConsensusController {
@@ -46,11 +45,12 @@ impl ConsensusController {
     /// **Usage**
     /// - launch the controller with a new thread
     /// - subscribe a interthread channel
+    /// **Return**: [[ThreadHandle]]
     /// ## Examples
     /// ```
     /// ```
-    pub fn launch_controller() {
-        ConsensusController::launch::<ConsensusController>("ConsensusController".to_string());
+    pub fn launch_controller() -> ThreadHandle {
+        ConsensusController::launch::<ConsensusController>("ConsensusController".to_string())
     }
 
     /// # launch_controller_with_channel(1)
@@ -59,11 +59,12 @@ impl ConsensusController {
     /// - subscribe a interthread channel
     /// **Parameters**
     /// - 1. ***&'static str(ch)***: the interthread channel name
+    /// **Return**: [[ThreadHandle]]
     /// ## Examples
     /// ```
     /// ```
-    pub fn launch_controller_with_channel(ch: &'static str) {
-        ConsensusController::launch::<ConsensusController>(ch.to_string());
+    pub fn launch_controller_with_channel(ch: &'static str) -> ThreadHandle {
+        ConsensusController::launch::<ConsensusController>(ch.to_string())
     }
 
     /// # connect(&mut self, 1)
@@ -137,14 +138,13 @@ impl Observe for ConsensusController {
     }
 
     fn unsubscribe(&mut self) {
-        let name = self.name.to_owned();
         if let Some(ch_pair) = self.ch_pair.clone() {
             let uid = (*ch_pair).0.lock().unwrap().uid.clone();
             self.ch_pair = None;
             MESSAGE_CENTER
                 .lock()
                 .unwrap()
-                .unsubscribe(name, uid);
+                .unsubscribe_channel(uid);
         }
 
     }
@@ -180,18 +180,22 @@ impl Observe for ConsensusController {
             panic!("No channel subscribed")
         }
     }
+
+    fn channel(&self) -> Option<Arc<(Mutex<MessageChannel>, Condvar)>> {
+        self.ch_pair.clone()
+    }
 }
 
 impl Thread for ConsensusController {
     fn new(name: String) -> Result<Self> {
-        let config = NetConfig::load();
+        let config = node_config().consensus;
 
         //TODO: make socket resuseable
-        let server = TcpListener::bind(&config.server_addr());
+        let server = TcpListener::bind(&config.server_addr);
         let account = Account::load();
 
         match (server, account) {
-            (Ok(server), Some(account)) => {
+            (Ok(server), Ok(account)) => {
                 let mut peer_list = HashMap::<Token, PeerRef>::new();
                 Ok(ConsensusController {
                     account: account.clone(),
@@ -204,11 +208,11 @@ impl Thread for ConsensusController {
                     blocks: HashMap::new(),
                     prevotes: HashMap::new(),
                     precommits: HashMap::new(),
-                    eventloop: NetworkEventLoop::new(config.events_size()),
+                    eventloop: NetworkEventLoop::new(config.events_size),
                 })
             },
-            (Ok(_), None) => {
-                Err(Error::from(ErrorKind::ConnectionRefused))
+            (Ok(_), Err(e)) => {
+                Err(Error::new(ErrorKind::NotFound, format!("can not load the node identity: {}", e)))
             },
             (Err(e), _) => {
                 Err(e)
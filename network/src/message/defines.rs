@@ -1,7 +1,7 @@
 use std::ops::*;
 
 use common::address::Address as Account;
-use common::hash::{Hash, HASH_LEN};
+use common::hash::Hash;
 use chrono::*;
 use rust_base58::{ToBase58, FromBase58};
 
@@ -24,7 +24,7 @@ pub enum SocketMessageArg {
 impl From<Account> for SocketMessageArg {
     fn from(account: Account) -> Self {
         SocketMessageArg::Account {
-            value: account.text
+            value: account.to_string()
         }
     }
 }
@@ -79,13 +79,7 @@ impl SocketMessage {
 
     pub fn account_at(&self, index: usize) -> Option<Account> {
         match &self.arg[index] {
-            &SocketMessageArg::Account { ref value } => {
-                if value.len() == HASH_LEN {
-                    Some(Account{ text: value.clone() })
-                } else {
-                    None
-                }
-            },
+            &SocketMessageArg::Account { ref value } => value.parse::<Account>().ok(),
             _ => None
         }
     }
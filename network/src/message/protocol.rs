@@ -243,11 +243,7 @@ impl P2PProtocol {
     }
 
     fn verify_account(index: usize, msg: &SocketMessage) -> bool {
-        if let Some(v) = msg.account_at(index) {
-            v.text.len() == 32
-        } else {
-            false
-        }
+        msg.account_at(index).is_some()
     }
 
     fn verify_timestamp(index: usize, msg: &SocketMessage) -> bool {
@@ -1,6 +1,7 @@
 use std::net::SocketAddr;
 use std::str::FromStr;
 use common::address::Address as Account;
+use common::node_config::{node_config, NetworkSettings};
 
 pub trait MockConfig {
     fn mock() -> Self;
@@ -25,8 +26,9 @@ pub struct NetConfig {
 }
 
 impl NetConfig {
+    /// Network settings of the installed [NodeConfig](common::node_config::NodeConfig)
     pub fn load() -> Self {
-        unimplemented!()
+        NetConfig::from(&node_config().network)
     }
 
     pub fn server_addr(&self) -> SocketAddr {
@@ -58,7 +60,7 @@ impl NetConfig {
     }
 
     pub fn connect_timeout(&self) -> i64 {
-        self.update_timebase
+        self.connect_timeout
     }
 
     pub fn peer_expire(&self) -> i64 {
@@ -70,6 +72,27 @@ impl NetConfig {
     }
 }
 
+impl<'a> From<&'a NetworkSettings> for NetConfig {
+    fn from(settings: &'a NetworkSettings) -> Self {
+        NetConfig {
+            server_addr: settings.server_addr,
+
+            events_size: settings.event_queue_size,
+
+            max_allowed_peers: settings.max_allowed_peers,
+            max_blocklist_size: settings.max_blocklist_size,
+            max_waitinglist_size: settings.max_waitinglist_size,
+            min_required_peer: settings.min_required_peer,
+
+            update_timebase: settings.update_timebase,
+            connect_timeout: settings.connect_timeout,
+            peer_expire: settings.peer_expire,
+
+            bootstrap_peers: settings.bootstrap_peers.clone()
+        }
+    }
+}
+
 impl MockConfig for NetConfig {
     fn mock() -> Self {
         NetConfig {
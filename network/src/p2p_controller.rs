@@ -8,7 +8,8 @@ use session::*;
 
 use common::address::Address as Account;
 use common::gen_message::*;
-use common::thread::{Thread, ThreadStatus};
+use common::gen_message::rpc::{call_async, Method, PendingCall};
+use common::thread::{Thread, ThreadHandle, ThreadStatus};
 use common::observe::Observe;
 
 use mio::*;
@@ -21,9 +22,48 @@ use std::rc::Rc;
 use std::sync::{Mutex, Arc, Condvar};
 use std::net::*;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::thread;
 
+/// Events the sessions report to their [[P2PController]]
+#[derive(Debug, Clone)]
+pub enum PeerEvent {
+    /// the peer of the token is bootstraped, send it our peer table
+    Gossip(Token)
+}
+
+pub const PEER_EVENTS: Topic<PeerEvent> = Topic::new("peer-events");
+
+/// Height of the local chain, answered by the controller owning the chain
+pub const CHAIN_HEIGHT: Method<(), u64> = Method::new("chain-height");
+
+/// Asks the chain for its height at most once per interval, without blocking the event loop
+struct HeightPoll {
+    call: Option<PendingCall<u64>>,
+    sent: Option<Instant>
+}
+
+impl HeightPoll {
+    fn new() -> Self {
+        HeightPoll { call: None, sent: None }
+    }
+
+    /// Height the chain answered since the last poll.
+    /// A new call is sent once `interval` has passed since the last one,
+    /// an unanswered call is given up for it.
+    fn poll(&mut self, interval: Duration) -> Option<u64> {
+        let height = self.call.as_ref().and_then(|call| call.try_wait());
+        if height.is_some() {
+            self.call = None;
+        }
+        if self.sent.map_or(true, |sent| sent.elapsed() >= interval) {
+            self.call = call_async(&CHAIN_HEIGHT, ()).ok();
+            self.sent = Some(Instant::now());
+        }
+        height
+    }
+}
+
 /// # P2PController
 /// **Usage**
 /// - p2p network controller
@@ -43,6 +83,7 @@ pub struct P2PController {
     name: String,
     account: Account,
     height: usize,
+    height_poll: HeightPoll,
     listener: TcpListener,
 
     peer_list: HashMap<Token, PeerRef>,
@@ -67,11 +108,12 @@ impl P2PController {
     /// **Usage**
     /// - launch the controller with a new thread
     /// - subscribe a interthread channel
+    /// **Return**: [[ThreadHandle]]
     /// ## Examples
     /// ```
     /// ```
-    pub fn launch_controller() {
-        P2PController::launch::<P2PController>("P2PController".to_string());
+    pub fn launch_controller() -> ThreadHandle {
+        P2PController::launch::<P2PController>("P2PController".to_string())
     }
 
     /// # launch_controller_with_channel(1)
@@ -80,11 +122,12 @@ impl P2PController {
     /// - subscribe a interthread channel
     /// **Parameters**
     /// - 1. ***&'static str(ch)***: the interthread channel name
+    /// **Return**: [[ThreadHandle]]
     /// ## Examples
     /// ```
     /// ```
-    pub fn launch_controller_with_channel(ch: &'static str) {
-        P2PController::launch::<P2PController>(ch.to_string());
+    pub fn launch_controller_with_channel(ch: &'static str) -> ThreadHandle {
+        P2PController::launch::<P2PController>(ch.to_string())
     }
 
     /// # connect(&mut self, 1)
@@ -277,6 +320,11 @@ impl P2PController {
    /// ```
     fn update(&mut self) {
         let update_timebase = self.config.update_timebase();
+        // the chain owns the height, keep the last known one until it answers
+        if let Some(height) = self.height_poll.poll(Duration::from_millis(update_timebase as u64)) {
+            self.height = height as usize;
+        }
+
         if (Utc::now() - self.last_updated).num_milliseconds() < update_timebase {
             return;
         }
@@ -473,14 +521,13 @@ impl Observe for P2PController {
     }
 
     fn unsubscribe(&mut self) {
-        let name = self.name.to_owned();
         if let Some(ch_pair) = self.ch_pair.clone() {
             let uid = (*ch_pair).0.lock().unwrap().uid.clone();
             self.ch_pair = None;
             MESSAGE_CENTER
                 .lock()
                 .unwrap()
-                .unsubscribe(name, uid);
+                .unsubscribe_channel(uid);
         }
 
     }
@@ -516,6 +563,10 @@ impl Observe for P2PController {
             panic!("No channel subscribed")
         }
     }
+
+    fn channel(&self) -> Option<Arc<(Mutex<MessageChannel>, Condvar)>> {
+        self.ch_pair.clone()
+    }
 }
 
 impl Thread for P2PController {
@@ -527,12 +578,13 @@ impl Thread for P2PController {
         let account = Account::load();
 
         match (server, account) {
-            (Ok(server), Some(account)) => {
+            (Ok(server), Ok(account)) => {
                 let mut peer_list = HashMap::<Token, PeerRef>::new();
                 Ok(P2PController {
                     name: name,
                     account: account.clone(),
                     height: 0usize,
+                    height_poll: HeightPoll::new(),
                     peer_list: peer_list,
                     min_required_peers: config.min_required_peer(),
                     max_allowed_peers: config.max_allowed_peers(),
@@ -548,8 +600,8 @@ impl Thread for P2PController {
                     config: config
                 })
             },
-            (Ok(_), None) => {
-                Err(Error::from(ErrorKind::ConnectionRefused))
+            (Ok(_), Err(e)) => {
+                Err(Error::new(ErrorKind::NotFound, format!("can not load the node identity: {}", e)))
             },
             (Err(e), _) => {
                 Err(e)
@@ -588,9 +640,8 @@ impl Thread for P2PController {
     /// ```
     /// ```
     fn msg_handler(&mut self, msg: Message) {
-        match msg.msg.as_ref() {
-            "gossip" => {
-                let token = Token(msg.op as usize);
+        match PEER_EVENTS.read(&msg) {
+            Some(PeerEvent::Gossip(token)) => {
                 let mut peer_ref = self.peer_list.get(&token);
                 if let None = peer_ref {
                     return;
@@ -611,7 +662,7 @@ impl Thread for P2PController {
                     self.height
                 );
             },
-            _ => {}
+            None => {}
         }
     }
 
@@ -641,6 +692,35 @@ mod p2p {
             .channels_exist_by_name("server".to_string()));
     }
 
+    #[test]
+    fn test_poll_height() {
+        let mut poll = HeightPoll::new();
+        // no chain answers yet
+        assert_eq!(poll.poll(Duration::from_millis(0)), None);
+        assert!(poll.call.is_none());
+
+        let chain = MESSAGE_CENTER.lock().unwrap().subscribe_topic(&CHAIN_HEIGHT.topic());
+        assert_eq!(poll.poll(Duration::from_millis(0)), None);
+        assert!(poll.call.is_some());
+
+        // answer as the chain would, the guard is dropped before replying
+        loop {
+            let req = chain.0.lock().unwrap().accept_typed(&CHAIN_HEIGHT.topic());
+            match req {
+                Some(req) => CHAIN_HEIGHT.reply(&req, 42),
+                None => break
+            }
+        }
+        assert_eq!(poll.poll(Duration::from_secs(60)), Some(42));
+        // the interval has not passed, no new call goes out
+        assert!(poll.call.is_none());
+        assert!(chain.0.lock().unwrap().accept_typed(&CHAIN_HEIGHT.topic()).is_none());
+        assert_eq!(poll.poll(Duration::from_secs(60)), None);
+
+        let uid = chain.0.lock().unwrap().uid.clone();
+        MESSAGE_CENTER.lock().unwrap().unsubscribe_channel(uid);
+    }
+
     #[test]
     fn test_start() {
 
@@ -1,7 +1,8 @@
-use gen_message::{Message, MESSAGE_CENTER};
+use gen_message::MESSAGE_CENTER;
 use message::defines::*;
 use message::message_handler::SocketMessageHandler;
 use message::protocol::*;
+use p2p_controller::{PeerEvent, PEER_EVENTS};
 use session::*;
 
 pub trait EventRegister {
@@ -41,9 +42,10 @@ fn bootstrap_handler(session: &mut Session, msg: &SocketMessage, name: String) -
                 session.set_status(SessionStatus::WaitGosship);
                 // notify controller send gossip
                 if let Some(token) = session.token() {
-                    MESSAGE_CENTER.lock().unwrap().send(
+                    MESSAGE_CENTER.lock().unwrap().send_typed(
                         name,
-                        Message::new(token.0 as u16, "gossip".to_string())
+                        &PEER_EVENTS,
+                        PeerEvent::Gossip(token)
                     );
                 }
                 true
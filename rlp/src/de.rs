@@ -0,0 +1,428 @@
+//! Serde `Deserializer` reading [[RLP]], the inverse of [ser::Serializer].
+//!
+//! RLP carries no type information, so the target type drives decoding.
+//! `deserialize_any` only tells [[RLPItem]] (as bytes) from [[RLPList]] (as a sequence).
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use std::slice::Iter;
+use std::str;
+
+use decoder::Decoder;
+use types::{RLP, RLPError};
+use RLPSerialize;
+
+/// Deserialize a `T` out of [[RLP]], strings and bytes are borrowed from `rlp`
+pub fn from_rlp<'de, T: Deserialize<'de>>(rlp: &'de RLP) -> Result<T, RLPError> {
+    T::deserialize(Deserializer::new(rlp))
+}
+
+/// Strictly decode `input`, see [Decoder::decode_strict], and deserialize a `T` out of it
+pub fn from_slice<T: DeserializeOwned>(input: &[u8]) -> Result<T, RLPError> {
+    let rlp = Decoder::decode_strict(input)?;
+    from_rlp(&rlp)
+}
+
+pub struct Deserializer<'de> {
+    rlp: &'de RLP
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(rlp: &'de RLP) -> Self {
+        Deserializer { rlp: rlp }
+    }
+
+    fn item(&self) -> Result<&'de [u8], RLPError> {
+        match self.rlp {
+            &RLP::RLPItem(ref value) => Ok(&value[..]),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+
+    fn list(&self) -> Result<&'de [RLP], RLPError> {
+        match self.rlp {
+            &RLP::RLPList(ref list) => Ok(&list[..]),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+
+    fn str(&self) -> Result<&'de str, RLPError> {
+        str::from_utf8(self.item()?).map_err(|_| RLPError::RLPErrorUTF8)
+    }
+
+    /// Visit `list` as a sequence which must be consumed entirely
+    fn visit_list<V: Visitor<'de>>(list: &'de [RLP], visitor: V) -> Result<V::Value, RLPError> {
+        let mut access = ListAccess { iter: list.iter() };
+        let value = visitor.visit_seq(&mut access)?;
+        if access.iter.len() != 0 {
+            Err(RLPError::RLPErrorWrongNumParams)
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+macro_rules! deserialize_int {
+    ($( $method: ident => $visit: ident : $t: ty ),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+                visitor.$visit(<$t as RLPSerialize>::deserialize(self.rlp)?)
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = RLPError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        match self.rlp {
+            &RLP::RLPItem(ref value) => visitor.visit_borrowed_bytes(&value[..]),
+            &RLP::RLPList(ref list) => Deserializer::visit_list(&list[..], visitor),
+            &RLP::RLPEmpty => Err(RLPError::RLPErrorType)
+        }
+    }
+
+    deserialize_int!(
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128
+    );
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        visitor.visit_f32(f32::from_bits(<u32 as RLPSerialize>::deserialize(self.rlp)?))
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        visitor.visit_f64(f64::from_bits(<u64 as RLPSerialize>::deserialize(self.rlp)?))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        let mut chars = self.str()?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        visitor.visit_borrowed_str(self.str()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        visitor.visit_borrowed_bytes(self.item()?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        let list = self.list()?;
+        match list.len() {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(Deserializer::new(&list[0])),
+            _ => Err(RLPError::RLPErrorWrongNumParams)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        if self.list()?.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(RLPError::RLPErrorWrongNumParams)
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, RLPError> {
+        let list = self.list()?;
+        if list.len() != 1 {
+            return Err(RLPError::RLPErrorWrongNumParams);
+        }
+        visitor.visit_newtype_struct(Deserializer::new(&list[0]))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        Deserializer::visit_list(self.list()?, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        let mut access = MapAccess { iter: self.list()?.iter(), value: None };
+        visitor.visit_map(&mut access)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, RLPError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, RLPError> {
+        let list = self.list()?;
+        let tag = match list.first() {
+            Some(&RLP::RLPItem(ref tag)) if tag.len() == 1 => tag[0],
+            Some(_) => return Err(RLPError::RLPErrorTagType),
+            None => return Err(RLPError::RLPErrorTagMissing)
+        };
+        visitor.visit_enum(EnumAccess { tag: tag, fields: &list[1..] })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RLPError> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct ListAccess<'de> {
+    iter: Iter<'de, RLP>
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for &'a mut ListAccess<'de> {
+    type Error = RLPError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, RLPError> {
+        match self.iter.next() {
+            Some(rlp) => Ok(Some(seed.deserialize(Deserializer::new(rlp))?)),
+            None => Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Walks a list of `[key, value]` pairs
+struct MapAccess<'de> {
+    iter: Iter<'de, RLP>,
+    value: Option<&'de RLP>
+}
+
+impl<'de, 'a> de::MapAccess<'de> for &'a mut MapAccess<'de> {
+    type Error = RLPError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, RLPError> {
+        match self.iter.next() {
+            Some(&RLP::RLPList(ref pair)) if pair.len() == 2 => {
+                self.value = Some(&pair[1]);
+                Ok(Some(seed.deserialize(Deserializer::new(&pair[0]))?))
+            },
+            Some(&RLP::RLPList(_)) => Err(RLPError::RLPErrorWrongNumParams),
+            Some(_) => Err(RLPError::RLPErrorType),
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RLPError> {
+        match self.value.take() {
+            Some(rlp) => seed.deserialize(Deserializer::new(rlp)),
+            None => Err(RLPError::RLPErrorUnknown("map value deserialized before its key"))
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// A tagged enum, `fields` are the list elements after the tag
+struct EnumAccess<'de> {
+    tag: u8,
+    fields: &'de [RLP]
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = RLPError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), RLPError> {
+        let tag: de::value::U32Deserializer<RLPError> = (self.tag as u32).into_deserializer();
+        let variant = seed.deserialize(tag).map_err(|_| RLPError::RLPErrorTagType)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'de> {
+    type Error = RLPError;
+
+    fn unit_variant(self) -> Result<(), RLPError> {
+        if self.fields.is_empty() {
+            Ok(())
+        } else {
+            Err(RLPError::RLPErrorWrongNumParams)
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RLPError> {
+        if self.fields.len() != 1 {
+            return Err(RLPError::RLPErrorWrongNumParams);
+        }
+        seed.deserialize(Deserializer::new(&self.fields[0]))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, RLPError> {
+        Deserializer::visit_list(self.fields, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, RLPError> {
+        Deserializer::visit_list(self.fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod deserializer {
+    use super::*;
+    use ser::{to_rlp, to_vec};
+    use encoder::Encoder;
+
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Header {
+        parent: String,
+        height: u64,
+        extra: Option<Vec<u32>>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wrapper(u16);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Marker;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Failed,
+        Successful(String),
+        Moved(u8, u8),
+        Named { code: i32 },
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(v: T) -> RLP {
+        let encoded = to_vec(&v).unwrap();
+        assert_eq!(from_slice::<T>(&encoded).unwrap(), v);
+        to_rlp(&v).unwrap()
+    }
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(round_trip(0x1234u16), 0x1234u16.into());
+        assert_eq!(round_trip(-7i64), (-7i64).into());
+        assert_eq!(round_trip(true), RLP::RLPItem(vec![1]));
+        assert_eq!(round_trip('é'), RLP::RLPItem("é".to_string().into_bytes()));
+        assert_eq!(round_trip("dog".to_string()), "dog".to_string().into());
+        round_trip(1.5f32);
+        round_trip(-0.25f64);
+        round_trip(u128::max_value());
+    }
+
+    #[test]
+    fn test_struct() {
+        let header = Header { parent: "genesis".to_string(), height: 1, extra: Some(vec![7]) };
+        assert_eq!(round_trip(header), RLP::RLPList(vec![
+            "genesis".to_string().into(),
+            1u64.into(),
+            RLP::RLPList(vec![RLP::RLPList(vec![7u32.into()])])
+        ]));
+        assert_eq!(round_trip(Wrapper(3)), RLP::RLPList(vec![3u16.into()]));
+        assert_eq!(round_trip(Marker), RLP::RLPList(vec![]));
+        round_trip(());
+    }
+
+    #[test]
+    fn test_enum() {
+        assert_eq!(round_trip(Status::Failed), RLP::RLPList(vec![RLP::RLPItem(vec![0])]));
+        assert_eq!(round_trip(Status::Successful("ok".to_string())), RLP::RLPList(vec![
+            RLP::RLPItem(vec![1]),
+            "ok".to_string().into()
+        ]));
+        round_trip(Status::Moved(1, 2));
+        round_trip(Status::Named { code: -1 });
+
+        let unknown = RLP::RLPList(vec![RLP::RLPItem(vec![9])]);
+        assert_eq!(from_rlp::<Status>(&unknown), Err(RLPError::RLPErrorTagType));
+        assert_eq!(from_rlp::<Status>(&RLP::RLPList(vec![])), Err(RLPError::RLPErrorTagMissing));
+    }
+
+    #[test]
+    fn test_collections() {
+        round_trip(vec!["cat".to_string(), "dog".to_string()]);
+        round_trip((1u8, "cat".to_string(), None::<u8>));
+        let mut map: BTreeMap<String, u32> = BTreeMap::new();
+        map.insert("a".to_string(), 1);
+        map.insert("b".to_string(), 2);
+        assert_eq!(round_trip(map), RLP::RLPList(vec![
+            RLP::RLPList(vec!["a".to_string().into(), 1u32.into()]),
+            RLP::RLPList(vec!["b".to_string().into(), 2u32.into()])
+        ]));
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let rlp: RLP = "dog".to_string().into();
+        let s: &str = from_rlp(&rlp).unwrap();
+        assert_eq!(s, "dog");
+    }
+
+    #[test]
+    fn test_errors() {
+        let header = RLP::RLPList(vec!["genesis".to_string().into(), 1u64.into()]);
+        assert!(from_rlp::<Header>(&header).is_err());
+        let too_long = RLP::RLPList(vec![1u8.into(), 2u8.into(), 3u8.into()]);
+        assert_eq!(from_rlp::<(u8, u8)>(&too_long), Err(RLPError::RLPErrorWrongNumParams));
+        assert_eq!(from_rlp::<u32>(&RLP::RLPItem(vec![1])), Err(RLPError::RLPErrorType));
+        assert_eq!(from_rlp::<String>(&RLP::RLPItem(vec![0xff])), Err(RLPError::RLPErrorUTF8));
+        // non canonical input is rejected before deserializing
+        let encoded = Encoder::new().encode(&"dog".to_string().into());
+        let mut trailing = encoded.clone();
+        trailing.push(0x00);
+        assert!(from_slice::<String>(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_matches_rlp_serialize() {
+        let v = (7u32, "cat".to_string(), Some(true));
+        assert_eq!(to_rlp(&v).unwrap(), RLPSerialize::serialize(&v).unwrap());
+    }
+}
@@ -2,6 +2,8 @@
 
 use defines::*;
 use types::*;
+use limits::DecodeLimits;
+use view::Header;
 use std::io::Result;
 use std::mem::*;
 use std::io::{Error, ErrorKind};
@@ -9,11 +11,23 @@ use std::io::{Error, ErrorKind};
 #[inline]
 fn malformed_err() -> (Result<RLP>, usize) { (Err(Error::new(ErrorKind::Other, "Malformed input")), 0) }
 
+type StrictResult<T> = ::std::result::Result<T, RLPError>;
+
+#[inline]
+fn strict_err<T>(offset: usize, reason: &'static str) -> StrictResult<T> {
+    Err(RLPError::RLPDecodingErrorAt { offset: offset, reason: reason })
+}
+
+#[inline]
+fn limit_err<T>(offset: usize, limit: RLPLimit) -> StrictResult<T> {
+    Err(RLPError::RLPDecodingErrorLimitExceeded { offset: offset, limit: limit })
+}
+
 pub struct Decoder;
 
 impl Decoder {
     #[inline]
-    fn detect_len(input: Vec<u8>) -> usize {
+    fn detect_len(input: &[u8]) -> usize {
         let prefix = input[0];
         match input[0] {
             // single byte
@@ -40,7 +54,7 @@ impl Decoder {
             },
             // long list
             0xf8u8...0xffu8 => {
-                let l_total_byte = prefix - 0xf8u8;
+                let l_total_byte = prefix - 0xf7u8;
                 let mut buffer = [0u8; 8];
                 for i in 1usize..(1 + l_total_byte) as usize {
                     buffer[i - 1] = input[i];
@@ -110,7 +124,7 @@ impl Decoder {
                         if cur_pos > end { break; }
 
                         let seg_estimated_end = if cur_pos + 8usize > end { end } else { cur_pos + 8usize };
-                        let seg_len = Decoder::detect_len(input[cur_pos .. seg_estimated_end + 1].to_vec());
+                        let seg_len = Decoder::detect_len(&input[cur_pos .. seg_estimated_end + 1]);
                         let (r_rlp, _) = Decoder::decode_helper(input, cur_pos, cur_pos + seg_len - 1usize);
                         let rlp: Option<RLP> = match r_rlp {
                             Ok(rlp) => Some(rlp),
@@ -131,7 +145,7 @@ impl Decoder {
                 let l_total_byte = prefix - LONG_LIST_PREFIX_BASE;
                 let mut buffer = [0u8; 8];
                 for i in 1usize..(1 + l_total_byte) as usize {
-                    buffer[i - 1] = input[i];
+                    buffer[i - 1] = input[start + i];
                 }
                 let l = unsafe { transmute::<[u8; 8], u64>(buffer) as usize };
                 let all_seg_len = 1usize + l_total_byte as usize + l as usize;
@@ -143,7 +157,7 @@ impl Decoder {
                     loop {
                         if cur_pos > end { break; }
                         let seg_estimated_end = if cur_pos + 8usize > end { end } else { cur_pos + 8usize };
-                        let seg_len = Decoder::detect_len(input[cur_pos .. seg_estimated_end + 1].to_vec());
+                        let seg_len = Decoder::detect_len(&input[cur_pos .. seg_estimated_end + 1]);
                         let (r_rlp, _) = Decoder::decode_helper(input, cur_pos, cur_pos + seg_len - 1usize);
                         let rlp: Option<RLP> = match r_rlp {
                             Ok(rlp) => Some(rlp),
@@ -171,12 +185,108 @@ impl Decoder {
             Err(_) => None
         }
     }
+
+    /// Parse a header and reject every form that [Encoder] would never produce.
+    /// `offset` is the position of `input` inside the whole payload.
+    fn strict_header(input: &[u8], offset: usize) -> StrictResult<Header> {
+        if input.is_empty() {
+            return strict_err(offset, "unexpected end of input");
+        }
+        let header = match Header::decode(input) {
+            Ok(header) => header,
+            Err(_) => return strict_err(offset, "truncated length prefix")
+        };
+
+        if header.header_len == 1 && !header.is_list && header.payload_len == 1 && input.len() > 1
+            && input[1] <= SINGLE_BYTE_MAX_VALUE {
+            return strict_err(offset, "single byte encoded as a short string");
+        }
+        if header.header_len > 1 {
+            if header.payload_len <= SHORT_STRING_MAX_LEN {
+                return strict_err(offset, "long form used for a short payload");
+            }
+            // length bytes are little endian, the last one is the most significant
+            if input[header.header_len - 1] == 0 {
+                return strict_err(offset + 1, "length prefix has leading zero bytes");
+            }
+        }
+        Ok(header)
+    }
+
+    fn decode_strict_helper(
+        input: &[u8],
+        offset: usize,
+        depth: usize,
+        limits: &DecodeLimits
+    ) -> StrictResult<(RLP, usize)> {
+        let header = Decoder::strict_header(input, offset)?;
+        if !header.is_list && header.payload_len > limits.max_item_size {
+            return limit_err(offset, RLPLimit::ItemSize);
+        }
+        let total_len = match header.total_len() {
+            Ok(len) if len <= input.len() => len,
+            _ => return strict_err(offset, "item exceeds the enclosing buffer")
+        };
+
+        if header.header_len == 0 {
+            return Ok((RLP::RLPItem(vec![input[0]]), 1usize));
+        }
+
+        let payload = &input[header.header_len .. total_len];
+        if !header.is_list {
+            return Ok((RLP::RLPItem(payload.to_vec()), total_len));
+        }
+
+        if depth + 1 > limits.max_depth {
+            return limit_err(offset, RLPLimit::Depth);
+        }
+        let mut list: Vec<RLP> = vec![];
+        let mut pos = 0usize;
+        while pos < payload.len() {
+            if list.len() == limits.max_list_len {
+                return limit_err(offset + header.header_len + pos, RLPLimit::ListLength);
+            }
+            let (item, len) = Decoder::decode_strict_helper(
+                &payload[pos..],
+                offset + header.header_len + pos,
+                depth + 1,
+                limits
+            )?;
+            list.push(item);
+            pos = pos + len;
+        }
+        Ok((RLP::RLPList(list), total_len))
+    }
+
+    /// Decode a single canonical item within [DecodeLimits::default].
+    /// Non-minimal length prefixes, long forms for short payloads, single bytes
+    /// wrapped as short strings, truncated input and trailing bytes are all rejected
+    /// with [RLPError::RLPDecodingErrorAt] pointing at the offending byte.
+    pub fn decode_strict(input: &[u8]) -> StrictResult<RLP> {
+        Decoder::decode_with_limits(input, &DecodeLimits::default())
+    }
+
+    /// Strictly decode untrusted input, e.g. a payload received from a peer.
+    /// Any bound of `limits` being hit is reported as [RLPError::RLPDecodingErrorLimitExceeded].
+    pub fn decode_with_limits(input: &[u8], limits: &DecodeLimits) -> StrictResult<RLP> {
+        if input.len() > limits.max_total_size {
+            return limit_err(0usize, RLPLimit::TotalSize);
+        }
+        let (rlp, len) = Decoder::decode_strict_helper(input, 0usize, 0usize, limits)?;
+        if len != input.len() {
+            strict_err(len, "trailing bytes after the item")
+        } else {
+            Ok(rlp)
+        }
+    }
 }
 
 #[cfg(test)]
 mod decoder {
     use super::Decoder;
-    use super::{RLP, RLPError};
+    use super::{RLP, RLPError, RLPLimit};
+    use encoder::Encoder;
+    use limits::DecodeLimits;
 
     #[test]
     fn test_item_string() {
@@ -241,4 +351,165 @@ mod decoder {
         ]);
         assert_eq!(rlp, target);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_list_nested_long() {
+        let inner = RLP::RLPList(vec![RLP::RLPItem(vec![0xaa; 60])]);
+        let target = RLP::RLPList(vec![inner.clone(), inner]);
+        let encoded = Encoder::new().encode(&target);
+        assert_eq!(Decoder::decode(&encoded).unwrap(), target);
+    }
+
+    #[test]
+    fn test_strict_canonical() {
+        let rlp = RLP::RLPList(vec![
+            RLP::RLPItem(vec![0x0f]),
+            "dog".to_string().into(),
+            RLP::RLPItem(vec![0xaa; 60]),
+            RLP::RLPList(vec![RLP::RLPItem(vec![0x80])])
+        ]);
+        let encoded = Encoder::new().encode(&rlp);
+        assert_eq!(Decoder::decode_strict(&encoded).unwrap(), rlp);
+    }
+
+    #[test]
+    fn test_strict_single_byte_as_string() {
+        assert_eq!(
+            Decoder::decode_strict(&[0xc2, 0x81, 0x0f]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 1, reason: "single byte encoded as a short string" })
+        );
+        assert_eq!(Decoder::decode_strict(&[0x81, 0x80]).unwrap(), RLP::RLPItem(vec![0x80]));
+    }
+
+    #[test]
+    fn test_strict_long_form_for_short_payload() {
+        let mut input = vec![0xb8, 0x03];
+        input.extend_from_slice(b"dog");
+        assert_eq!(
+            Decoder::decode_strict(&input),
+            Err(RLPError::RLPDecodingErrorAt { offset: 0, reason: "long form used for a short payload" })
+        );
+        assert_eq!(
+            Decoder::decode_strict(&[0xf8, 0x00]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 0, reason: "long form used for a short payload" })
+        );
+    }
+
+    #[test]
+    fn test_strict_leading_zero_length() {
+        let mut input = vec![0xb9, 0x3c, 0x00];
+        input.extend_from_slice(&[0xaa; 60]);
+        assert_eq!(
+            Decoder::decode_strict(&input),
+            Err(RLPError::RLPDecodingErrorAt { offset: 1, reason: "length prefix has leading zero bytes" })
+        );
+    }
+
+    #[test]
+    fn test_strict_truncated() {
+        assert_eq!(
+            Decoder::decode_strict(&[]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 0, reason: "unexpected end of input" })
+        );
+        assert_eq!(
+            Decoder::decode_strict(&[0xc5, 0x83, 'd' as u8, 'o' as u8, 'g' as u8]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 0, reason: "item exceeds the enclosing buffer" })
+        );
+        assert_eq!(
+            Decoder::decode_strict(&[0xc3, 0x0f, 0x83, 'd' as u8]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 2, reason: "item exceeds the enclosing buffer" })
+        );
+        assert_eq!(
+            Decoder::decode_strict(&[0xba, 0x01]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 0, reason: "truncated length prefix" })
+        );
+    }
+
+    #[test]
+    fn test_strict_trailing_bytes() {
+        assert_eq!(
+            Decoder::decode_strict(&[0x0f, 0x0f]),
+            Err(RLPError::RLPDecodingErrorAt { offset: 1, reason: "trailing bytes after the item" })
+        );
+    }
+
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut rlp = RLP::RLPList(vec![]);
+        for _ in 1..depth {
+            rlp = RLP::RLPList(vec![rlp]);
+        }
+        Encoder::new().encode(&rlp)
+    }
+
+    #[test]
+    fn test_limit_total_size() {
+        let limits = DecodeLimits::new_with_size(3);
+        assert_eq!(Decoder::decode_with_limits(&[0x82, 0x04, 0x00], &limits).unwrap(), 1024u16.into());
+        assert_eq!(
+            Decoder::decode_with_limits(&[0x83, 'd' as u8, 'o' as u8, 'g' as u8], &limits),
+            Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 0, limit: RLPLimit::TotalSize })
+        );
+    }
+
+    #[test]
+    fn test_limit_depth() {
+        let limits = DecodeLimits { max_depth: 4, ..DecodeLimits::default() };
+        assert!(Decoder::decode_with_limits(&nested_lists(4), &limits).is_ok());
+        assert_eq!(
+            Decoder::decode_with_limits(&nested_lists(5), &limits),
+            Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 4, limit: RLPLimit::Depth })
+        );
+
+        // far deeper than the stack would allow without a limit
+        let mut headers: Vec<Vec<u8>> = vec![];
+        let mut len = 1usize;
+        for _ in 0..100000 {
+            let header = if len <= 55 {
+                vec![0xc0 + len as u8]
+            } else {
+                let len_bytes: Vec<u8> = (0..4).map(|i| (len >> (8 * i)) as u8).take_while(|b| *b != 0).collect();
+                let mut header = vec![0xf7 + len_bytes.len() as u8];
+                header.extend(len_bytes);
+                header
+            };
+            len = len + header.len();
+            headers.push(header);
+        }
+        let mut input: Vec<u8> = headers.into_iter().rev().flat_map(|header| header.into_iter()).collect();
+        input.push(0xc0);
+        match Decoder::decode_strict(&input) {
+            Err(RLPError::RLPDecodingErrorLimitExceeded { limit: RLPLimit::Depth, .. }) => {},
+            other => panic!("unexpected result {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_limit_list_len() {
+        let limits = DecodeLimits { max_list_len: 2, ..DecodeLimits::default() };
+        assert!(Decoder::decode_with_limits(&[0xc2, 0x01, 0x02], &limits).is_ok());
+        assert_eq!(
+            Decoder::decode_with_limits(&[0xc3, 0x01, 0x02, 0x03], &limits),
+            Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 3, limit: RLPLimit::ListLength })
+        );
+    }
+
+    #[test]
+    fn test_limit_item_size() {
+        let limits = DecodeLimits { max_item_size: 2, ..DecodeLimits::default() };
+        assert!(Decoder::decode_with_limits(&[0xc3, 0x82, 0x04, 0x00], &limits).is_ok());
+        assert_eq!(
+            Decoder::decode_with_limits(&[0xc4, 0x83, 'd' as u8, 'o' as u8, 'g' as u8], &limits),
+            Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 1, limit: RLPLimit::ItemSize })
+        );
+    }
+
+    #[test]
+    fn test_limit_declared_len() {
+        // declares a 2^56 byte item in a 9 byte buffer
+        let input = vec![0xbf, 0, 0, 0, 0, 0, 0, 0, 0x01];
+        assert_eq!(
+            Decoder::decode_strict(&input),
+            Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 0, limit: RLPLimit::ItemSize })
+        );
+    }
+}
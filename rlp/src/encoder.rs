@@ -4,6 +4,7 @@ use self::bytebuffer::*;
 use defines::*;
 use types::*;
 
+use std::io;
 use std::io::Write;
 use std::mem::*;
 use std::iter::FromIterator;
@@ -205,10 +206,130 @@ impl Encoder {
     }
 }
 
+/// # StreamEncoder<W: Write>
+/// **Usage**
+/// - encode [[RLP]] straight into any [[Write]], e.g. a file, a socket or a `Vec<u8>`
+/// - owns no shared buffer, so every thread can use its own instance without locking
+/// - produces exactly the same bytes as [[Encoder]]
+/// **Member**
+/// - 1. ***out***:      the output sink
+/// - 2. ***lens***:     payload sizes of the current object in pre-order, reused between calls
+/// ## Examples
+/// ```
+/// use rlp::encoder::StreamEncoder;
+/// use rlp::types::RLP;
+///
+/// let mut encoder = StreamEncoder::new(vec![]);
+/// encoder.encode(&RLP::RLPList(vec![])).unwrap();
+/// assert_eq!(encoder.into_inner(), vec![0xc0]);
+/// ```
+pub struct StreamEncoder<W: Write> {
+    out: W,
+    lens: Vec<usize>
+}
+
+impl<W: Write> StreamEncoder<W> {
+    pub fn new(out: W) -> Self {
+        StreamEncoder { out: out, lens: vec![] }
+    }
+
+    /// Get back the output sink
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Encode `obj` and return the number of bytes written
+    pub fn encode(&mut self, obj: &RLP) -> io::Result<usize> {
+        self.lens.clear();
+        let len = self.measure(obj);
+        let mut cursor = 0usize;
+        self.write(obj, &mut cursor)?;
+        Ok(len)
+    }
+
+    #[inline]
+    fn header_len(payload_len: usize) -> usize {
+        if payload_len <= SHORT_LIST_MAX_LEN { 1 } else { 1 + total_bytes!(payload_len as u64) as usize }
+    }
+
+    /// Single post-order pass: record the payload size of every node in pre-order slots
+    /// and return the encoded size of `obj`.
+    fn measure(&mut self, obj: &RLP) -> usize {
+        match obj {
+            &RLP::RLPEmpty => {
+                self.lens.push(0);
+                0
+            },
+            &RLP::RLPItem(ref value) => {
+                self.lens.push(value.len());
+                if value.len() == 1 && value[0] <= SINGLE_BYTE_MAX_VALUE {
+                    1
+                } else {
+                    StreamEncoder::<W>::header_len(value.len()) + value.len()
+                }
+            },
+            &RLP::RLPList(ref list) => {
+                let slot = self.lens.len();
+                self.lens.push(0);
+                let mut payload_len = 0usize;
+                for elem in list {
+                    payload_len = payload_len + self.measure(elem);
+                }
+                self.lens[slot] = payload_len;
+                StreamEncoder::<W>::header_len(payload_len) + payload_len
+            }
+        }
+    }
+
+    fn write_header(&mut self, payload_len: usize, short_base: u8, long_base: u8) -> io::Result<()> {
+        if payload_len <= SHORT_STRING_MAX_LEN {
+            self.out.write_all(&[short_base + payload_len as u8])
+        } else {
+            let l = payload_len as u64;
+            let l_total_byte = total_bytes!(l);
+            let len_bytes: [u8; 8] = unsafe { transmute(l.to_le()) };
+            self.out.write_all(&[long_base + l_total_byte])?;
+            self.out.write_all(&len_bytes[0..l_total_byte as usize])
+        }
+    }
+
+    fn write(&mut self, obj: &RLP, cursor: &mut usize) -> io::Result<()> {
+        let payload_len = self.lens[*cursor];
+        *cursor = *cursor + 1;
+        match obj {
+            &RLP::RLPEmpty => Ok(()),
+            &RLP::RLPItem(ref value) => {
+                if value.len() == 1 && value[0] <= SINGLE_BYTE_MAX_VALUE {
+                    self.out.write_all(&value[..])
+                } else {
+                    self.write_header(payload_len, SHORT_STRING_PREFIX_BASE, LONG_STRING_PREFIX_BASE)?;
+                    self.out.write_all(&value[..])
+                }
+            },
+            &RLP::RLPList(ref list) => {
+                self.write_header(payload_len, SHORT_LIST_PREFIX_BASE, LONG_LIST_PREFIX_BASE)?;
+                for elem in list {
+                    self.write(elem, cursor)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Encode RLP into a new u8 array without touching [[SHARED_ENCODER]]
+pub fn encode_to_vec(obj: &RLP) -> EncodedRLP {
+    let mut encoder = StreamEncoder::new(Vec::<u8>::new());
+    // writing into a Vec never fails
+    let _ = encoder.encode(obj);
+    encoder.into_inner()
+}
+
 #[cfg(test)]
 mod encoder {
-    use super::Encoder;
+    use super::{encode_to_vec, Encoder, StreamEncoder};
     use super::{RLP, RLPError};
+    use std::thread;
 
     #[test]
     fn test_item_string() {
@@ -278,4 +399,51 @@ mod encoder {
         let result = encoder.encode(&rlp);
         assert_eq!(result, vec![ 0xc7, 0xc0, 0xc1, 0xc0, 0xc3, 0xc0, 0xc1, 0xc0 ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stream_matches_encoder() {
+        let cases = vec![
+            RLP::RLPItem(vec![]),
+            RLP::RLPItem(vec![0x7f]),
+            RLP::RLPItem(vec![0x80]),
+            RLP::RLPItem(vec![0xaa; 55]),
+            RLP::RLPItem(vec![0xaa; 56]),
+            RLP::RLPItem(vec![0xaa; 1024]),
+            RLP::RLPList(vec![]),
+            RLP::RLPList(vec![RLP::RLPItem(vec![0xaa; 54])]),
+            RLP::RLPList(vec![RLP::RLPItem(vec![0xaa; 55]), RLP::RLPEmpty]),
+            RLP::RLPList(vec![
+                RLP::RLPList(vec![RLP::RLPItem(vec![0xbb; 300]); 3]),
+                "dog".to_string().into(),
+                RLP::RLPList(vec![RLP::RLPList(vec![])])
+            ])
+        ];
+        for rlp in cases {
+            assert_eq!(encode_to_vec(&rlp), Encoder::new().encode(&rlp));
+        }
+    }
+
+    #[test]
+    fn test_stream_reuse() {
+        let mut encoder = StreamEncoder::new(Vec::<u8>::new());
+        let first: RLP = "dog".to_string().into();
+        let second = RLP::RLPList(vec![RLP::RLPList(vec![])]);
+        assert_eq!(encoder.encode(&first).unwrap(), 4);
+        assert_eq!(encoder.encode(&second).unwrap(), 2);
+        assert_eq!(encoder.into_inner(), vec![0x83u8, 'd' as u8, 'o' as u8, 'g' as u8, 0xc1, 0xc0]);
+    }
+
+    #[test]
+    fn test_stream_threads() {
+        let handles: Vec<_> = (0..4u8).map(|i| {
+            thread::spawn(move || {
+                let rlp = RLP::RLPList(vec![RLP::RLPItem(vec![i; 100]); 100]);
+                encode_to_vec(&rlp)
+            })
+        }).collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let rlp = RLP::RLPList(vec![RLP::RLPItem(vec![i as u8; 100]); 100]);
+            assert_eq!(handle.join().unwrap(), Encoder::new().encode(&rlp));
+        }
+    }
+}
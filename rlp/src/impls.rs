@@ -0,0 +1,359 @@
+//! Fallible [RLPSerialize] implementations for primitive and compound types.
+//!
+//! **Layout**
+//! - integers are fixed width big endian [[RLPItem]], the same as `From<uN> for RLP`.
+//! - `bool` is a single byte item, `0` or `1`.
+//! - `BigUint` is minimal big endian, `BigInt` is minimal two's complement, zero is the empty item.
+//! - `[u8; N]` and `Vec<u8>` are a single [[RLPItem]], any other `Vec<T>` is an [[RLPList]].
+//! - `Option<T>` is an empty [[RLPList]] for `None` and `[v]` for `Some(v)`.
+//! - tuples are an [[RLPList]] of their elements.
+//!
+//! Every decoder returns an [RLPError] on unexpected input, nothing here panics.
+
+use num::{BigInt, BigUint, One, Zero};
+use num::bigint::Sign;
+
+use types::{RLP, RLPError};
+use RLPSerialize;
+
+/// Payload of an [[RLPItem]], or [RLPErrorType] for anything else
+fn item_bytes(rlp: &RLP) -> Result<&Vec<u8>, RLPError> {
+    match rlp {
+        &RLP::RLPItem(ref value) => Ok(value),
+        _ => Err(RLPError::RLPErrorType)
+    }
+}
+
+impl RLPSerialize for u8 {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(vec![*self]))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        let value = item_bytes(rlp)?;
+        if value.len() != 1 {
+            return Err(RLPError::RLPErrorType);
+        }
+        Ok(value[0])
+    }
+
+    fn serialize_list(list: &[Self]) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(list.to_vec()))
+    }
+
+    fn deserialize_list(rlp: &RLP) -> Result<Vec<Self>, RLPError> {
+        Ok(item_bytes(rlp)?.clone())
+    }
+}
+
+macro_rules! impl_int {
+    ($t: ty, $width: expr) => {
+        impl RLPSerialize for $t {
+            fn serialize(&self) -> Result<RLP, RLPError> {
+                let v = *self as u128;
+                let mut bytes: Vec<u8> = Vec::with_capacity($width);
+                for i in (0..$width).rev() {
+                    bytes.push((v >> (8 * i)) as u8);
+                }
+                Ok(RLP::RLPItem(bytes))
+            }
+
+            fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+                let value = item_bytes(rlp)?;
+                if value.len() != $width {
+                    return Err(RLPError::RLPErrorType);
+                }
+                let mut v = 0u128;
+                for byte in value {
+                    v = (v << 8) | (*byte as u128);
+                }
+                Ok(v as $t)
+            }
+        }
+    }
+}
+
+impl_int!(u16, 2);
+impl_int!(u32, 4);
+impl_int!(u64, 8);
+impl_int!(u128, 16);
+impl_int!(i8, 1);
+impl_int!(i16, 2);
+impl_int!(i32, 4);
+impl_int!(i64, 8);
+impl_int!(i128, 16);
+
+/// `usize` is encoded as `u64` so the encoding does not depend on the platform
+impl RLPSerialize for usize {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        (*self as u64).serialize()
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        let v = u64::deserialize(rlp)?;
+        if v > usize::max_value() as u64 {
+            Err(RLPError::RLPErrorType)
+        } else {
+            Ok(v as usize)
+        }
+    }
+}
+
+impl RLPSerialize for bool {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(vec![*self as u8]))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        match u8::deserialize(rlp)? {
+            0u8 => Ok(false),
+            1u8 => Ok(true),
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+}
+
+impl RLPSerialize for BigUint {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        if self.is_zero() {
+            Ok(RLP::RLPItem(vec![]))
+        } else {
+            Ok(RLP::RLPItem(self.to_bytes_be()))
+        }
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        let value = item_bytes(rlp)?;
+        if value.first() == Some(&0u8) {
+            // not minimal
+            return Err(RLPError::RLPErrorType);
+        }
+        Ok(BigUint::from_bytes_be(value))
+    }
+}
+
+impl RLPSerialize for BigInt {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        let mut bytes = match self.sign() {
+            Sign::NoSign => return Ok(RLP::RLPItem(vec![])),
+            Sign::Plus => self.to_biguint().unwrap().to_bytes_be(),
+            // two's complement of -n is !(n - 1)
+            Sign::Minus => {
+                let magnitude: BigUint = (-self).to_biguint().unwrap() - BigUint::one();
+                magnitude.to_bytes_be().iter().map(|byte| !byte).collect()
+            }
+        };
+        // keep the sign bit of the first byte consistent with the sign
+        let negative = self.sign() == Sign::Minus;
+        if (bytes[0] & 0x80u8 != 0) != negative {
+            bytes.insert(0, if negative { 0xffu8 } else { 0x00u8 });
+        }
+        Ok(RLP::RLPItem(bytes))
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        let value = item_bytes(rlp)?;
+        let minimal = match value.len() {
+            0 => true,
+            1 => value[0] != 0x00u8,
+            _ => !(value[0] == 0x00u8 && value[1] < 0x80u8) && !(value[0] == 0xffu8 && value[1] >= 0x80u8)
+        };
+        if !minimal {
+            return Err(RLPError::RLPErrorType);
+        }
+        if value.is_empty() || value[0] & 0x80u8 == 0 {
+            Ok(BigInt::from_biguint(Sign::Plus, BigUint::from_bytes_be(value)))
+        } else {
+            let inverted: Vec<u8> = value.iter().map(|byte| !byte).collect();
+            let magnitude = BigUint::from_bytes_be(&inverted) + BigUint::one();
+            Ok(BigInt::from_biguint(Sign::Minus, magnitude))
+        }
+    }
+}
+
+macro_rules! impl_byte_array {
+    ($( $len: expr ),*) => {
+        $(
+            impl RLPSerialize for [u8; $len] {
+                fn serialize(&self) -> Result<RLP, RLPError> {
+                    Ok(RLP::RLPItem(self.to_vec()))
+                }
+
+                fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+                    let value = item_bytes(rlp)?;
+                    if value.len() != $len {
+                        return Err(RLPError::RLPErrorType);
+                    }
+                    let mut result = [0u8; $len];
+                    result.copy_from_slice(value);
+                    Ok(result)
+                }
+            }
+        )*
+    }
+}
+
+impl_byte_array!(
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+    17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,
+    48, 64, 65, 85, 96, 128, 256
+);
+
+impl<T: RLPSerialize> RLPSerialize for Vec<T> {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        T::serialize_list(self)
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        T::deserialize_list(rlp)
+    }
+}
+
+impl<T: RLPSerialize> RLPSerialize for Option<T> {
+    fn serialize(&self) -> Result<RLP, RLPError> {
+        match self {
+            &Some(ref v) => Ok(RLP::RLPList(vec![v.serialize()?])),
+            &None => Ok(RLP::RLPList(vec![]))
+        }
+    }
+
+    fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+        match rlp {
+            &RLP::RLPList(ref list) => {
+                match list.len() {
+                    0 => Ok(None),
+                    1 => Ok(Some(T::deserialize(&list[0])?)),
+                    _ => Err(RLPError::RLPErrorWrongNumParams)
+                }
+            },
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
+}
+
+macro_rules! impl_tuple {
+    ($len: expr, $( $name: ident : $index: tt ),+) => {
+        impl<$( $name: RLPSerialize ),+> RLPSerialize for ($( $name, )+) {
+            fn serialize(&self) -> Result<RLP, RLPError> {
+                Ok(RLP::RLPList(vec![$( self.$index.serialize()? ),+]))
+            }
+
+            fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
+                match rlp {
+                    &RLP::RLPList(ref list) => {
+                        if list.len() != $len {
+                            return Err(RLPError::RLPErrorWrongNumParams);
+                        }
+                        Ok(($( $name::deserialize(&list[$index])?, )+))
+                    },
+                    _ => Err(RLPError::RLPErrorType)
+                }
+            }
+        }
+    }
+}
+
+impl_tuple!(1, A: 0);
+impl_tuple!(2, A: 0, B: 1);
+impl_tuple!(3, A: 0, B: 1, C: 2);
+impl_tuple!(4, A: 0, B: 1, C: 2, D: 3);
+impl_tuple!(5, A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_tuple!(6, A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+#[cfg(test)]
+mod impls {
+    use super::*;
+    use num::{BigInt, BigUint};
+
+    fn round_trip<T: RLPSerialize + PartialEq + ::std::fmt::Debug>(v: T) -> RLP {
+        let rlp = v.serialize().unwrap();
+        assert_eq!(T::deserialize(&rlp).unwrap(), v);
+        rlp
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(round_trip(0x12u8), RLP::RLPItem(vec![0x12]));
+        assert_eq!(round_trip(0x1234u16), RLP::RLPItem(vec![0x12, 0x34]));
+        assert_eq!(round_trip(0x12345678u32), 0x12345678u32.into());
+        assert_eq!(round_trip(-2i64), (-2i64).into());
+        round_trip(u128::max_value());
+        round_trip(i128::min_value());
+        round_trip(usize::max_value());
+        assert_eq!(u32::deserialize(&RLP::RLPItem(vec![0x01, 0x02])), Err(RLPError::RLPErrorType));
+        assert_eq!(u8::deserialize(&RLP::RLPList(vec![])), Err(RLPError::RLPErrorType));
+        assert_eq!(u64::deserialize(&RLP::RLPEmpty), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_bool() {
+        assert_eq!(round_trip(true), RLP::RLPItem(vec![1]));
+        assert_eq!(round_trip(false), RLP::RLPItem(vec![0]));
+        assert_eq!(bool::deserialize(&RLP::RLPItem(vec![2])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_big_uint() {
+        assert_eq!(round_trip(BigUint::from(0u32)), RLP::RLPItem(vec![]));
+        assert_eq!(round_trip(BigUint::from(0x0100u32)), RLP::RLPItem(vec![0x01, 0x00]));
+        assert_eq!(BigUint::deserialize(&RLP::RLPItem(vec![0x00, 0x01])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_big_int() {
+        assert_eq!(round_trip(BigInt::from(0)), RLP::RLPItem(vec![]));
+        assert_eq!(round_trip(BigInt::from(127)), RLP::RLPItem(vec![0x7f]));
+        assert_eq!(round_trip(BigInt::from(128)), RLP::RLPItem(vec![0x00, 0x80]));
+        assert_eq!(round_trip(BigInt::from(-1)), RLP::RLPItem(vec![0xff]));
+        assert_eq!(round_trip(BigInt::from(-128)), RLP::RLPItem(vec![0x80]));
+        assert_eq!(round_trip(BigInt::from(-129)), RLP::RLPItem(vec![0xff, 0x7f]));
+        assert_eq!(round_trip(BigInt::from(-256)), RLP::RLPItem(vec![0xff, 0x00]));
+        round_trip(BigInt::from(i64::min_value()));
+        assert_eq!(BigInt::deserialize(&RLP::RLPItem(vec![0x00])), Err(RLPError::RLPErrorType));
+        assert_eq!(BigInt::deserialize(&RLP::RLPItem(vec![0x00, 0x7f])), Err(RLPError::RLPErrorType));
+        assert_eq!(BigInt::deserialize(&RLP::RLPItem(vec![0xff, 0xff])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_byte_array() {
+        let hash = [0xabu8; 32];
+        assert_eq!(round_trip(hash), RLP::RLPItem(vec![0xab; 32]));
+        round_trip([0x01u8; 256]);
+        assert_eq!(<[u8; 32]>::deserialize(&RLP::RLPItem(vec![0xab; 31])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_vec() {
+        assert_eq!(round_trip(vec![1u8, 2u8]), RLP::RLPItem(vec![1, 2]));
+        assert_eq!(round_trip(vec![1u16, 2u16]), RLP::RLPList(vec![
+            RLP::RLPItem(vec![0, 1]),
+            RLP::RLPItem(vec![0, 2])
+        ]));
+        round_trip(vec![vec![0u8; 3], vec![]]);
+        round_trip(vec!["cat".to_string(), "dog".to_string()]);
+        assert_eq!(Vec::<u16>::deserialize(&RLP::RLPItem(vec![])), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_option() {
+        assert_eq!(round_trip(None::<u8>), RLP::RLPList(vec![]));
+        assert_eq!(round_trip(Some(7u8)), RLP::RLPList(vec![RLP::RLPItem(vec![7])]));
+        round_trip(Some(None::<u8>));
+        let two = RLP::RLPList(vec![RLP::RLPItem(vec![7]), RLP::RLPItem(vec![8])]);
+        assert_eq!(Option::<u8>::deserialize(&two), Err(RLPError::RLPErrorWrongNumParams));
+    }
+
+    #[test]
+    fn test_tuple() {
+        round_trip((1u8,));
+        round_trip((1u8, "cat".to_string(), true, vec![1u32], Some(2u64), BigUint::from(3u32)));
+        let one = RLP::RLPList(vec![RLP::RLPItem(vec![7])]);
+        assert_eq!(<(u8, u8)>::deserialize(&one), Err(RLPError::RLPErrorWrongNumParams));
+    }
+
+    #[test]
+    fn test_string() {
+        assert_eq!(String::deserialize(&RLP::RLPItem(vec![0xff, 0xfe])), Err(RLPError::RLPErrorUTF8));
+        assert_eq!(String::deserialize(&RLP::RLPList(vec![])), Err(RLPError::RLPErrorType));
+    }
+}
@@ -0,0 +1,226 @@
+//! Human readable views of [[RLP]] for debugging stored nodes and peer payloads.
+//!
+//! **JSON layout**
+//! - an [[RLPList]] is a JSON array.
+//! - an [[RLPItem]] is `{"hex": "0x..", "utf8": ".."}`, `utf8` is only present when the bytes are valid utf-8.
+//! - [from_json] also accepts a bare string, `"0x.."` as hex and anything else as utf-8 text.
+
+use serde_json::{Map, Value};
+
+use std::fmt::Write;
+use std::str;
+
+use limits::DEFAULT_MAX_DEPTH;
+use types::{RLP, RLPError};
+use view::Header;
+
+const HEX_PREFIX: &'static str = "0x";
+const HEX_DIGITS: &'static [u8] = b"0123456789abcdef";
+const KEY_HEX: &'static str = "hex";
+const KEY_UTF8: &'static str = "utf8";
+
+/// Lowercase hex without prefix
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        result.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        result.push(HEX_DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    result
+}
+
+/// Parse hex, an optional `0x` prefix and whitespace are allowed
+pub fn from_hex(text: &str) -> Result<Vec<u8>, RLPError> {
+    let text = text.trim();
+    let text = if text.starts_with(HEX_PREFIX) { &text[HEX_PREFIX.len()..] } else { text };
+    let digits: Vec<u8> = text.bytes().filter(|c| !(*c as char).is_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        return Err(RLPError::RLPErrorUnknown("odd number of hex digits"));
+    }
+
+    let mut result: Vec<u8> = Vec::with_capacity(digits.len() / 2);
+    for pair in digits.chunks(2) {
+        let high = hex_value(pair[0])?;
+        let low = hex_value(pair[1])?;
+        result.push((high << 4) | low);
+    }
+    Ok(result)
+}
+
+fn hex_value(c: u8) -> Result<u8, RLPError> {
+    match c {
+        b'0' ... b'9' => Ok(c - b'0'),
+        b'a' ... b'f' => Ok(c - b'a' + 10),
+        b'A' ... b'F' => Ok(c - b'A' + 10),
+        _ => Err(RLPError::RLPErrorUnknown("invalid hex digit"))
+    }
+}
+
+/// Annotated JSON of [[RLP]], see the module documentation for the layout
+pub fn to_json(rlp: &RLP) -> Value {
+    match rlp {
+        &RLP::RLPList(ref list) => Value::Array(list.iter().map(to_json).collect()),
+        &RLP::RLPItem(ref value) => {
+            let mut item = Map::new();
+            item.insert(KEY_HEX.to_string(), Value::String(format!("{}{}", HEX_PREFIX, to_hex(value))));
+            if let Ok(text) = str::from_utf8(value) {
+                item.insert(KEY_UTF8.to_string(), Value::String(text.to_string()));
+            }
+            Value::Object(item)
+        },
+        &RLP::RLPEmpty => Value::Null
+    }
+}
+
+/// Rebuild [[RLP]] from the output of [to_json], `utf8` annotations are ignored
+pub fn from_json(json: &Value) -> Result<RLP, RLPError> {
+    match json {
+        &Value::Array(ref list) => {
+            let mut result: Vec<RLP> = Vec::with_capacity(list.len());
+            for elem in list {
+                result.push(from_json(elem)?);
+            }
+            Ok(RLP::RLPList(result))
+        },
+        &Value::Object(ref item) => {
+            match item.get(KEY_HEX) {
+                Some(&Value::String(ref hex)) => Ok(RLP::RLPItem(from_hex(hex)?)),
+                _ => Err(RLPError::RLPErrorType)
+            }
+        },
+        &Value::String(ref text) => {
+            if text.starts_with(HEX_PREFIX) {
+                Ok(RLP::RLPItem(from_hex(text)?))
+            } else {
+                Ok(RLP::RLPItem(text.clone().into_bytes()))
+            }
+        },
+        _ => Err(RLPError::RLPErrorType)
+    }
+}
+
+/// Render an [[EncodedRLP]] one element per line, prefixed with its byte offset.
+/// Non canonical encodings are shown as they are, structural errors are
+/// reported as [RLPError::RLPDecodingErrorAt].
+/// ## Examples
+/// ```
+/// use rlp::inspect::pretty;
+///
+/// let encoded = vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
+/// let text = pretty(&encoded).unwrap();
+/// let lines: Vec<&str> = text.lines().collect();
+/// assert_eq!(lines[0], "     0  list, 2 elements, 8 bytes");
+/// assert_eq!(lines[1], "     1    item, 3 bytes 0x636174 \"cat\"");
+/// assert_eq!(lines[2], "     5    item, 3 bytes 0x646f67 \"dog\"");
+/// ```
+pub fn pretty(input: &[u8]) -> Result<String, RLPError> {
+    let mut out = String::new();
+    let len = pretty_helper(input, 0usize, 0usize, &mut out)?;
+    if len != input.len() {
+        return Err(RLPError::RLPDecodingErrorAt { offset: len, reason: "trailing bytes after the item" });
+    }
+    Ok(out)
+}
+
+/// Render the item at the beginning of `input`, `offset` is its position in the whole buffer.
+/// Returns the number of bytes taken by the item.
+fn pretty_helper(input: &[u8], offset: usize, depth: usize, out: &mut String) -> Result<usize, RLPError> {
+    if depth > DEFAULT_MAX_DEPTH {
+        return Err(RLPError::RLPDecodingErrorAt { offset: offset, reason: "nested too deep" });
+    }
+    let header = Header::decode(input)
+        .map_err(|_| RLPError::RLPDecodingErrorAt { offset: offset, reason: "malformed header" })?;
+    let total_len = match header.total_len() {
+        Ok(len) if len <= input.len() => len,
+        _ => return Err(RLPError::RLPDecodingErrorAt { offset: offset, reason: "item exceeds the enclosing buffer" })
+    };
+    let payload = &input[header.header_len..total_len];
+
+    let indent = "  ".repeat(depth);
+    if header.is_list {
+        let mut count = 0usize;
+        let mut body = String::new();
+        let mut pos = 0usize;
+        while pos < payload.len() {
+            pos = pos + pretty_helper(&payload[pos..], offset + header.header_len + pos, depth + 1, &mut body)?;
+            count = count + 1;
+        }
+        let _ = writeln!(out, "{:>6}  {}list, {} elements, {} bytes", offset, indent, count, payload.len());
+        out.push_str(&body);
+    } else {
+        let _ = write!(out, "{:>6}  {}item, {} bytes 0x{}", offset, indent, payload.len(), to_hex(payload));
+        if let Ok(text) = str::from_utf8(payload) {
+            if !payload.is_empty() && !text.chars().any(|c| c.is_control()) {
+                let _ = write!(out, " {:?}", text);
+            }
+        }
+        out.push('\n');
+    }
+    Ok(total_len)
+}
+
+#[cfg(test)]
+mod inspect {
+    use super::*;
+    use encoder::Encoder;
+
+    fn sample() -> RLP {
+        RLP::RLPList(vec![
+            "cat".to_string().into(),
+            RLP::RLPItem(vec![0xff, 0x00]),
+            RLP::RLPList(vec![RLP::RLPItem(vec![])])
+        ])
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(to_hex(&[0x00, 0xab, 0x10]), "00ab10");
+        assert_eq!(from_hex("0x00AB10").unwrap(), vec![0x00, 0xab, 0x10]);
+        assert_eq!(from_hex(" 00 ab\n10 ").unwrap(), vec![0x00, 0xab, 0x10]);
+        assert!(from_hex("0x0").is_err());
+        assert!(from_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let rlp = sample();
+        let json = to_json(&rlp);
+        assert_eq!(json[0]["hex"], "0x636174");
+        assert_eq!(json[0]["utf8"], "cat");
+        assert!(json[1].get("utf8").is_none());
+        assert_eq!(json[2][0]["hex"], "0x");
+        assert_eq!(from_json(&json).unwrap(), rlp);
+    }
+
+    #[test]
+    fn test_json_strings() {
+        let json: Value = ::serde_json::from_str(r#"["cat", "0x0102", []]"#).unwrap();
+        assert_eq!(from_json(&json).unwrap(), RLP::RLPList(vec![
+            "cat".to_string().into(),
+            RLP::RLPItem(vec![0x01, 0x02]),
+            RLP::RLPList(vec![])
+        ]));
+        assert_eq!(from_json(&Value::Bool(true)), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let encoded = Encoder::new().encode(&sample());
+        let text = pretty(&encoded).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "     0  list, 3 elements, 9 bytes");
+        assert_eq!(lines[1], "     1    item, 3 bytes 0x636174 \"cat\"");
+        assert_eq!(lines[2], "     5    item, 2 bytes 0xff00");
+        assert_eq!(lines[3], "     8    list, 1 elements, 1 bytes");
+        assert_eq!(lines[4], "     9      item, 0 bytes 0x");
+    }
+
+    #[test]
+    fn test_pretty_malformed() {
+        assert_eq!(pretty(&[0xc2, 0x83, b'c']),
+                   Err(RLPError::RLPDecodingErrorAt { offset: 1, reason: "item exceeds the enclosing buffer" }));
+        assert_eq!(pretty(&[0x01, 0x02]),
+                   Err(RLPError::RLPDecodingErrorAt { offset: 1, reason: "trailing bytes after the item" }));
+    }
+}
@@ -4,24 +4,62 @@ pub const DOMAIN: &'static str = "rlp";
 pub extern crate lazy_static;
 pub extern crate bytebuffer;
 pub extern crate gen_utils;
+pub extern crate num;
+pub extern crate serde;
 
-extern crate serde_json;
+pub extern crate serde_json;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
 
+pub mod de;
 pub mod decoder;
 pub mod defines;
 pub mod encoder;
+pub mod impls;
+pub mod inspect;
+pub mod limits;
+pub mod ser;
+pub mod stream;
 pub mod types;
+pub mod view;
+
+pub use de::{from_rlp, from_slice};
+pub use ser::{to_rlp, to_vec};
 
 use self::gen_utils::log_writer::LOGGER;
 
 use std::convert::{Into, From};
-use std::string::FromUtf8Error;
 
 use types::{ RLPError, RLP };
 
 pub trait RLPSerialize: Sized {
     fn serialize(&self) -> Result<RLP, RLPError>;
     fn deserialize(rlp: &types::RLP) -> Result<Self, RLPError>;
+
+    /// Encoding of `Vec<Self>`, an [[RLPList]] of the elements by default.
+    /// `u8` overrides it so byte vectors are encoded as a single [[RLPItem]].
+    fn serialize_list(list: &[Self]) -> Result<RLP, RLPError> {
+        let mut result: Vec<RLP> = Vec::with_capacity(list.len());
+        for elem in list {
+            result.push(elem.serialize()?);
+        }
+        Ok(RLP::RLPList(result))
+    }
+
+    /// Decoding of `Vec<Self>`, see [serialize_list]
+    fn deserialize_list(rlp: &RLP) -> Result<Vec<Self>, RLPError> {
+        match rlp {
+            &RLP::RLPList(ref list) => {
+                let mut result: Vec<Self> = Vec::with_capacity(list.len());
+                for elem in list {
+                    result.push(Self::deserialize(elem)?);
+                }
+                Ok(result)
+            },
+            _ => Err(RLPError::RLPErrorType)
+        }
+    }
 }
 
 impl<T> RLPSerialize for T
@@ -43,7 +81,11 @@ impl RLPSerialize for String {
     }
 
     fn deserialize(rlp: &RLP) -> Result<Self, RLPError> {
-        let result: Result<String, FromUtf8Error> = rlp.clone().into();
-        Ok(result.unwrap())
+        match rlp {
+            &RLP::RLPItem(ref value) => {
+                String::from_utf8(value.clone()).map_err(|_| RLPError::RLPErrorUTF8)
+            },
+            _ => Err(RLPError::RLPErrorType)
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+use defines::*;
+
+/// Default max nesting depth of lists
+pub const DEFAULT_MAX_DEPTH: usize = 64usize;
+
+/// Default max number of elements in a single list
+pub const DEFAULT_MAX_LIST_LEN: usize = 1024 * 64;
+
+/// Bounds applied while decoding untrusted input.
+/// Exceeding any of them makes the decoder return
+/// [RLPError::RLPDecodingErrorLimitExceeded] instead of recursing or allocating further.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeLimits {
+    /// max size of the whole encoded input in bytes
+    pub max_total_size: usize,
+    /// max nesting depth, the top-level list is at depth 1
+    pub max_depth: usize,
+    /// max number of elements of a single list
+    pub max_list_len: usize,
+    /// max payload size of a single [[RLPItem]] in bytes
+    pub max_item_size: usize
+}
+
+impl DecodeLimits {
+    /// Limits for a single peer payload of at most `max_total_size` bytes
+    pub fn new_with_size(max_total_size: usize) -> Self {
+        DecodeLimits {
+            max_total_size: max_total_size,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_list_len: DEFAULT_MAX_LIST_LEN,
+            max_item_size: max_total_size
+        }
+    }
+}
+
+impl Default for DecodeLimits {
+    /// Sized after [DECODER_BUFFER_SIZE]
+    fn default() -> Self {
+        DecodeLimits::new_with_size(DECODER_BUFFER_SIZE)
+    }
+}
@@ -0,0 +1,321 @@
+//! Serde `Serializer` producing [[RLP]].
+//!
+//! **Layout**, shared with [RLPSerialize] and `#[derive(RLPSerialize)]`
+//! - integers, `bool`, strings and bytes are [[RLPItem]], integers fixed width big endian.
+//! - `f32`/`f64` are their IEEE 754 bits as `u32`/`u64`, `char` is its utf-8 string.
+//! - `()`, unit structs and `None` are an empty [[RLPList]], `Some(v)` is `[v]`.
+//! - structs, tuples, newtype structs and sequences are an [[RLPList]] in field order,
+//!   field names are not encoded.
+//! - maps are an [[RLPList]] of `[key, value]` pairs.
+//! - enum variants are an [[RLPList]] of a one byte tag (the variant index) followed by the fields.
+//!
+//! Serde passes `Vec<u8>` and `[u8; N]` as sequences, so they become a list of single bytes.
+//! Use `serde_bytes` for fields that should be a single [[RLPItem]].
+
+use serde::ser::{self, Serialize};
+
+use encoder::encode_to_vec;
+use types::{EncodedRLP, RLP, RLPError};
+use RLPSerialize;
+
+/// Serialize `value` into [[RLP]]
+pub fn to_rlp<T: ?Sized + Serialize>(value: &T) -> Result<RLP, RLPError> {
+    value.serialize(Serializer)
+}
+
+/// Serialize `value` into an [[EncodedRLP]]
+pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<EncodedRLP, RLPError> {
+    Ok(encode_to_vec(&to_rlp(value)?))
+}
+
+pub struct Serializer;
+
+/// Encoding tag of an enum variant, see [RLPErrorTagType]
+fn variant_tag(variant_index: u32) -> Result<RLP, RLPError> {
+    if variant_index > u8::max_value() as u32 {
+        Err(RLPError::RLPErrorTagType)
+    } else {
+        Ok(RLP::RLPItem(vec![variant_index as u8]))
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = ListSerializer;
+    type SerializeStructVariant = ListSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v.to_bits())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RLP, RLPError> {
+        RLPSerialize::serialize(&v.to_bits())
+    }
+
+    fn serialize_char(self, v: char) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(v.to_string().into_bytes()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(v.as_bytes().to_vec()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPItem(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![]))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![to_rlp(value)?]))
+    }
+
+    fn serialize_unit(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![]))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![]))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![variant_tag(variant_index)?]))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![to_rlp(value)?]))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T
+    ) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(vec![variant_tag(variant_index)?, to_rlp(value)?]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, RLPError> {
+        Ok(ListSerializer::new(len.unwrap_or(0usize)))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, RLPError> {
+        Ok(ListSerializer::new(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, RLPError> {
+        Ok(ListSerializer::new(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize
+    ) -> Result<ListSerializer, RLPError> {
+        let mut serializer = ListSerializer::new(1 + len);
+        serializer.list.push(variant_tag(variant_index)?);
+        Ok(serializer)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, RLPError> {
+        Ok(MapSerializer { list: Vec::with_capacity(len.unwrap_or(0usize)), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, RLPError> {
+        Ok(ListSerializer::new(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize
+    ) -> Result<ListSerializer, RLPError> {
+        self.serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Collects the elements of every list shaped value
+pub struct ListSerializer {
+    list: Vec<RLP>
+}
+
+impl ListSerializer {
+    fn new(len: usize) -> Self {
+        ListSerializer { list: Vec::with_capacity(len) }
+    }
+
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        self.list.push(to_rlp(value)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+impl ser::SerializeStruct for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+impl ser::SerializeStructVariant for ListSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), RLPError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
+
+/// Collects `[key, value]` pairs
+pub struct MapSerializer {
+    list: Vec<RLP>,
+    key: Option<RLP>
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = RLP;
+    type Error = RLPError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), RLPError> {
+        self.key = Some(to_rlp(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RLPError> {
+        match self.key.take() {
+            Some(key) => {
+                self.list.push(RLP::RLPList(vec![key, to_rlp(value)?]));
+                Ok(())
+            },
+            None => Err(RLPError::RLPErrorUnknown("map value serialized before its key"))
+        }
+    }
+
+    fn end(self) -> Result<RLP, RLPError> {
+        Ok(RLP::RLPList(self.list))
+    }
+}
@@ -0,0 +1,285 @@
+use decoder::Decoder;
+use defines::*;
+use limits::DecodeLimits;
+use types::*;
+use view::Header;
+
+use std::io::{ErrorKind, Read};
+
+/// # RlpStreamReader<R: Read>
+/// **Usage**
+/// - read a sequence of concatenated top-level items, e.g. a block export file or a TCP stream
+/// - reads exactly one item per call, nothing past its last byte is consumed
+/// - every item is strictly decoded within the given [DecodeLimits]
+/// ## Examples
+/// ```
+/// use rlp::encoder::encode_to_vec;
+/// use rlp::stream::RlpStreamReader;
+/// use rlp::types::RLP;
+///
+/// let mut input = encode_to_vec(&RLP::RLPItem(b"cat".to_vec()));
+/// input.extend(encode_to_vec(&RLP::RLPList(vec![])));
+/// let items: Vec<RLP> = RlpStreamReader::new(&input[..]).map(|item| item.unwrap()).collect();
+/// assert_eq!(items, vec![RLP::RLPItem(b"cat".to_vec()), RLP::RLPList(vec![])]);
+/// ```
+pub struct RlpStreamReader<R: Read> {
+    input: R,
+    limits: DecodeLimits,
+    /// bytes consumed from `input` so far
+    offset: usize,
+    failed: bool
+}
+
+impl<R: Read> RlpStreamReader<R> {
+    pub fn new(input: R) -> Self {
+        RlpStreamReader::new_with_limits(input, DecodeLimits::default())
+    }
+
+    /// `limits.max_total_size` bounds every single item, not the whole stream
+    pub fn new_with_limits(input: R, limits: DecodeLimits) -> Self {
+        RlpStreamReader {
+            input: input,
+            limits: limits,
+            offset: 0usize,
+            failed: false
+        }
+    }
+
+    /// Bytes consumed from the underlying reader
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+
+    /// Read the next encoded item without decoding its payload.
+    /// `Ok(None)` means the stream ended cleanly between two items.
+    pub fn next_raw(&mut self) -> Result<Option<EncodedRLP>, RLPError> {
+        let start = self.offset;
+        let prefix = match self.read_prefix()? {
+            Some(prefix) => prefix,
+            None => return Ok(None)
+        };
+
+        let len_of_len = match prefix {
+            0xb8u8 ... 0xbfu8 => (prefix - LONG_STRING_PREFIX_BASE) as usize,
+            0xf8u8 ... 0xffu8 => (prefix - LONG_LIST_PREFIX_BASE) as usize,
+            _ => 0usize
+        };
+        let mut encoded: Vec<u8> = vec![0u8; 1 + len_of_len];
+        encoded[0] = prefix;
+        self.read_exact(&mut encoded[1..])?;
+
+        let header = Header::decode(&encoded)
+            .map_err(|_| RLPError::RLPDecodingErrorAt { offset: start, reason: "truncated length prefix" })?;
+        let total_len = header.total_len()
+            .map_err(|_| RLPError::RLPDecodingErrorLimitExceeded { offset: start, limit: RLPLimit::TotalSize })?;
+        if total_len > self.limits.max_total_size {
+            return Err(RLPError::RLPDecodingErrorLimitExceeded { offset: start, limit: RLPLimit::TotalSize });
+        }
+
+        // single bytes carry no header, the prefix is the payload
+        if header.header_len > 0 {
+            let header_len = header.header_len;
+            encoded.resize(total_len, 0u8);
+            self.read_exact(&mut encoded[header_len..])?;
+        }
+        Ok(Some(encoded))
+    }
+
+    /// Read and strictly decode the next item, see [Decoder::decode_with_limits]
+    pub fn next_item(&mut self) -> Result<Option<RLP>, RLPError> {
+        let start = self.offset;
+        match self.next_raw()? {
+            Some(encoded) => {
+                Decoder::decode_with_limits(&encoded, &self.limits)
+                    .map(Some)
+                    .map_err(|e| shift_offset(e, start))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// The first byte of an item, `None` on a clean end of stream
+    fn read_prefix(&mut self) -> Result<Option<u8>, RLPError> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => {
+                    self.offset = self.offset + 1;
+                    return Ok(Some(byte[0]));
+                },
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(RLPError::RLPStreamErrorIO(e.kind()))
+            }
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), RLPError> {
+        let mut pos = 0usize;
+        while pos < buf.len() {
+            match self.input.read(&mut buf[pos..]) {
+                Ok(0) => {
+                    return Err(RLPError::RLPDecodingErrorAt { offset: self.offset, reason: "truncated stream" });
+                },
+                Ok(n) => {
+                    pos = pos + n;
+                    self.offset = self.offset + n;
+                },
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(RLPError::RLPStreamErrorIO(e.kind()))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Make the offset of an item level error relative to the whole stream
+fn shift_offset(e: RLPError, start: usize) -> RLPError {
+    match e {
+        RLPError::RLPDecodingErrorAt { offset, reason } => {
+            RLPError::RLPDecodingErrorAt { offset: start + offset, reason: reason }
+        },
+        RLPError::RLPDecodingErrorLimitExceeded { offset, limit } => {
+            RLPError::RLPDecodingErrorLimitExceeded { offset: start + offset, limit: limit }
+        },
+        other => other
+    }
+}
+
+/// Yields items until the stream ends, an error is yielded once and ends the iteration
+impl<R: Read> Iterator for RlpStreamReader<R> {
+    type Item = Result<RLP, RLPError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_item() {
+            Ok(Some(rlp)) => Some(Ok(rlp)),
+            Ok(None) => None,
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod stream {
+    use super::*;
+    use encoder::encode_to_vec;
+
+    use std::io::{self, Cursor};
+
+    fn sample() -> Vec<RLP> {
+        vec![
+            RLP::RLPItem(vec![0x0f]),
+            "dog".to_string().into(),
+            RLP::RLPItem(vec![0xaa; 300]),
+            RLP::RLPList(vec![RLP::RLPList(vec![]), RLP::RLPItem(vec![0xbb; 70])]),
+            RLP::RLPList(vec![])
+        ]
+    }
+
+    fn concat(items: &Vec<RLP>) -> EncodedRLP {
+        let mut result: EncodedRLP = vec![];
+        for item in items {
+            result.extend(encode_to_vec(item));
+        }
+        result
+    }
+
+    /// Hands out one byte per read call, like a slow socket
+    struct Trickle<'a> {
+        data: &'a [u8]
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.data.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.data[0];
+            self.data = &self.data[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_read_all() {
+        let items = sample();
+        let encoded = concat(&items);
+        let decoded: Vec<RLP> = RlpStreamReader::new(&encoded[..]).map(|item| item.unwrap()).collect();
+        assert_eq!(decoded, items);
+
+        let decoded: Vec<RLP> = RlpStreamReader::new(Trickle { data: &encoded }).map(|item| item.unwrap()).collect();
+        assert_eq!(decoded, items);
+    }
+
+    #[test]
+    fn test_reads_only_one_item() {
+        let items = sample();
+        let encoded = concat(&items);
+        let mut reader = RlpStreamReader::new(Cursor::new(encoded.clone()));
+        reader.next_item().unwrap();
+        reader.next_item().unwrap();
+        assert_eq!(reader.offset(), 1 + 4);
+        assert_eq!(reader.into_inner().position(), 5);
+    }
+
+    #[test]
+    fn test_next_raw() {
+        let items = sample();
+        let encoded = concat(&items);
+        let mut reader = RlpStreamReader::new(&encoded[..]);
+        for item in &items {
+            assert_eq!(reader.next_raw().unwrap().unwrap(), encode_to_vec(item));
+        }
+        assert_eq!(reader.next_raw(), Ok(None));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let encoded = concat(&sample());
+        let cut = encoded.len() - 3;
+        let mut reader = RlpStreamReader::new(&encoded[..cut]);
+        let results: Vec<Result<RLP, RLPError>> = reader.by_ref().collect();
+        // the last item is gone and the fourth one is cut
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[3], Err(RLPError::RLPDecodingErrorAt { offset: cut, reason: "truncated stream" }));
+        assert!(reader.next().is_none());
+
+        // cut inside a length prefix
+        let long = encode_to_vec(&RLP::RLPItem(vec![0xaa; 300]));
+        let mut reader = RlpStreamReader::new(&long[..2]);
+        assert_eq!(reader.next_item(), Err(RLPError::RLPDecodingErrorAt { offset: 2, reason: "truncated stream" }));
+    }
+
+    #[test]
+    fn test_errors_are_offset_in_stream() {
+        let mut encoded = encode_to_vec(&"dog".to_string().into());
+        // single byte wrapped as a short string
+        encoded.extend(vec![0x81, 0x05]);
+        let mut reader = RlpStreamReader::new(&encoded[..]);
+        reader.next_item().unwrap();
+        match reader.next_item() {
+            Err(RLPError::RLPDecodingErrorAt { offset, .. }) => assert_eq!(offset, 4),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let encoded = encode_to_vec(&RLP::RLPItem(vec![0xaa; 300]));
+        let mut reader = RlpStreamReader::new_with_limits(&encoded[..], DecodeLimits::new_with_size(100));
+        assert_eq!(reader.next_item(),
+                   Err(RLPError::RLPDecodingErrorLimitExceeded { offset: 0, limit: RLPLimit::TotalSize }));
+        // nothing past the header was read
+        assert_eq!(reader.offset(), 3);
+    }
+}
@@ -2,6 +2,11 @@ use std::ops::{Index, Shl};
 use std::convert::{From, Into};
 use std::string::FromUtf8Error;
 use std::mem::transmute;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use serde::{de, ser};
 
 #[macro_export]
 macro_rules! rlp_list {
@@ -14,7 +19,7 @@ macro_rules! rlp_list {
 
 pub type EncodedRLP = Vec<u8>;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum RLPError {
     RLPErrorUnknown(&'static str),
 
@@ -28,9 +33,55 @@ pub enum RLPError {
 
     RLPErrorUTF8,
 
+    RLPErrorIndexOutOfRange,
+
     RLPEncodingErrorUnencodable,
 
     RLPDecodingErrorMalformed,
+
+    /// Strict decoding failed at byte `offset` of the input
+    RLPDecodingErrorAt { offset: usize, reason: &'static str },
+
+    /// The item at byte `offset` exceeds one of the [DecodeLimits]
+    RLPDecodingErrorLimitExceeded { offset: usize, limit: RLPLimit },
+
+    /// Reading from the underlying stream failed
+    RLPStreamErrorIO(io::ErrorKind),
+
+    /// Raised by a serde `Serialize` or `Deserialize` implementation
+    RLPErrorCustom(String),
+}
+
+impl fmt::Display for RLPError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &RLPError::RLPErrorCustom(ref msg) => write!(f, "{}", msg),
+            other => write!(f, "{:?}", other)
+        }
+    }
+}
+
+impl Error for RLPError {}
+
+impl ser::Error for RLPError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RLPError::RLPErrorCustom(msg.to_string())
+    }
+}
+
+impl de::Error for RLPError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RLPError::RLPErrorCustom(msg.to_string())
+    }
+}
+
+/// The [DecodeLimits] bound that was hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RLPLimit {
+    TotalSize,
+    Depth,
+    ListLength,
+    ItemSize
 }
 
 #[derive(Clone, Debug, PartialEq)]
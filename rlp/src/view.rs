@@ -0,0 +1,293 @@
+use defines::*;
+use types::*;
+
+use std::mem::size_of;
+
+/// Prefix information of an encoded item, laid out the same way as [Encoder] writes it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    pub is_list: bool,
+    /// bytes taken by the prefix and the length bytes
+    pub header_len: usize,
+    /// bytes taken by the payload
+    pub payload_len: usize
+}
+
+impl Header {
+    /// Parse the header at the beginning of `input`.
+    /// The payload itself is not required to be present.
+    pub fn decode(input: &[u8]) -> Result<Header, RLPError> {
+        let prefix = match input.first() {
+            Some(prefix) => *prefix,
+            None => return Err(RLPError::RLPDecodingErrorMalformed)
+        };
+
+        match prefix {
+            // single byte
+            0x00u8 ... 0x7fu8 => Ok(Header { is_list: false, header_len: 0, payload_len: 1 }),
+            // short string
+            0x80u8 ... 0xb7u8 => Ok(Header {
+                is_list: false,
+                header_len: 1,
+                payload_len: (prefix - SHORT_STRING_PREFIX_BASE) as usize
+            }),
+            // long string
+            0xb8u8 ... 0xbfu8 => {
+                let len_of_len = (prefix - LONG_STRING_PREFIX_BASE) as usize;
+                let payload_len = Header::decode_len(&input[1..], len_of_len)?;
+                Ok(Header { is_list: false, header_len: 1 + len_of_len, payload_len: payload_len })
+            },
+            // short list
+            0xc0u8 ... 0xf7u8 => Ok(Header {
+                is_list: true,
+                header_len: 1,
+                payload_len: (prefix - SHORT_LIST_PREFIX_BASE) as usize
+            }),
+            // long list
+            _ => {
+                let len_of_len = (prefix - LONG_LIST_PREFIX_BASE) as usize;
+                let payload_len = Header::decode_len(&input[1..], len_of_len)?;
+                Ok(Header { is_list: true, header_len: 1 + len_of_len, payload_len: payload_len })
+            }
+        }
+    }
+
+    /// Length of the whole encoded item
+    pub fn total_len(&self) -> Result<usize, RLPError> {
+        self.header_len.checked_add(self.payload_len).ok_or(RLPError::RLPDecodingErrorMalformed)
+    }
+
+    /// Length bytes are stored little endian, see [Encoder::encode_long_str]
+    fn decode_len(input: &[u8], len_of_len: usize) -> Result<usize, RLPError> {
+        if len_of_len > size_of::<usize>() || input.len() < len_of_len {
+            return Err(RLPError::RLPDecodingErrorMalformed);
+        }
+        let mut len = 0usize;
+        for i in 0..len_of_len {
+            len = len | ((input[i] as usize) << (8 * i));
+        }
+        Ok(len)
+    }
+}
+
+/// # RlpView<'a>
+/// **Usage**
+/// - borrowed view over an [[EncodedRLP]], decoded lazily on access
+/// - nothing is copied, every accessor returns a slice of the original buffer
+/// ## Examples
+/// ```
+/// use rlp::view::RlpView;
+///
+/// // ["cat", "dog"]
+/// let encoded = vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
+/// let view = RlpView::new(&encoded);
+/// assert_eq!(view.item_count().unwrap(), 2);
+/// assert_eq!(view.at(1).unwrap().as_bytes().unwrap(), b"dog");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RlpView<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> RlpView<'a> {
+    /// Wrap an encoded buffer, no decoding happens here
+    pub fn new(bytes: &'a [u8]) -> Self {
+        RlpView { bytes: bytes }
+    }
+
+    /// The encoded bytes of this item, including its header
+    pub fn as_raw(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decode the header, the buffer must contain exactly one item
+    pub fn header(&self) -> Result<Header, RLPError> {
+        let header = Header::decode(self.bytes)?;
+        if header.total_len()? != self.bytes.len() {
+            Err(RLPError::RLPDecodingErrorMalformed)
+        } else {
+            Ok(header)
+        }
+    }
+
+    pub fn is_list(&self) -> Result<bool, RLPError> {
+        Ok(self.header()?.is_list)
+    }
+
+    pub fn is_item(&self) -> Result<bool, RLPError> {
+        Ok(!self.header()?.is_list)
+    }
+
+    /// Payload of an [[RLPItem]]
+    pub fn as_bytes(&self) -> Result<&'a [u8], RLPError> {
+        let header = self.header()?;
+        if header.is_list {
+            Err(RLPError::RLPErrorType)
+        } else {
+            Ok(&self.bytes[header.header_len..])
+        }
+    }
+
+    /// Number of elements of an [[RLPList]]
+    pub fn item_count(&self) -> Result<usize, RLPError> {
+        let mut count = 0usize;
+        for item in self.iter()? {
+            item?;
+            count = count + 1;
+        }
+        Ok(count)
+    }
+
+    /// The `index`-th element of an [[RLPList]]
+    pub fn at(&self, index: usize) -> Result<RlpView<'a>, RLPError> {
+        let mut iter = self.iter()?;
+        for _ in 0..index {
+            match iter.next() {
+                Some(Ok(_)) => {},
+                Some(Err(e)) => return Err(e),
+                None => return Err(RLPError::RLPErrorIndexOutOfRange)
+            }
+        }
+        match iter.next() {
+            Some(item) => item,
+            None => Err(RLPError::RLPErrorIndexOutOfRange)
+        }
+    }
+
+    /// Iterate over the elements of an [[RLPList]]
+    pub fn iter(&self) -> Result<RlpViewIter<'a>, RLPError> {
+        let header = self.header()?;
+        if !header.is_list {
+            Err(RLPError::RLPErrorType)
+        } else {
+            Ok(RlpViewIter { payload: &self.bytes[header.header_len..], pos: 0, failed: false })
+        }
+    }
+
+    /// Decode the whole view into an owned [[RLP]]
+    pub fn to_rlp(&self) -> Result<RLP, RLPError> {
+        if self.is_list()? {
+            let mut list: Vec<RLP> = vec![];
+            for item in self.iter()? {
+                list.push(item?.to_rlp()?);
+            }
+            Ok(RLP::RLPList(list))
+        } else {
+            Ok(RLP::RLPItem(self.as_bytes()?.to_vec()))
+        }
+    }
+}
+
+/// Iterator over the elements of a list view.
+/// Yields an error once and then stops if the payload is malformed.
+pub struct RlpViewIter<'a> {
+    payload: &'a [u8],
+    pos: usize,
+    failed: bool
+}
+
+impl<'a> Iterator for RlpViewIter<'a> {
+    type Item = Result<RlpView<'a>, RLPError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos >= self.payload.len() {
+            return None;
+        }
+
+        let remain = &self.payload[self.pos..];
+        let len = Header::decode(remain).and_then(|header| header.total_len());
+        match len {
+            Ok(len) if len <= remain.len() => {
+                self.pos = self.pos + len;
+                Some(Ok(RlpView::new(&remain[..len])))
+            },
+            Ok(_) => {
+                self.failed = true;
+                Some(Err(RLPError::RLPDecodingErrorMalformed))
+            },
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod view {
+    use super::*;
+    use encoder::Encoder;
+
+    fn encode(rlp: &RLP) -> EncodedRLP {
+        Encoder::new().encode(rlp)
+    }
+
+    #[test]
+    fn test_item_string() {
+        let encoded = vec![0x83u8, 'd' as u8, 'o' as u8, 'g' as u8];
+        let view = RlpView::new(&encoded);
+        assert_eq!(view.is_item().unwrap(), true);
+        assert_eq!(view.as_bytes().unwrap(), b"dog");
+        assert_eq!(view.item_count(), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_item_u8() {
+        let encoded = vec![0x0fu8];
+        let view = RlpView::new(&encoded);
+        assert_eq!(view.as_bytes().unwrap(), &[0x0fu8]);
+    }
+
+    #[test]
+    fn test_list_nested() {
+        let encoded = vec![0xc7, 0xc0, 0xc1, 0xc0, 0xc3, 0xc0, 0xc1, 0xc0];
+        let view = RlpView::new(&encoded);
+        assert_eq!(view.item_count().unwrap(), 3);
+        assert_eq!(view.at(0).unwrap().item_count().unwrap(), 0);
+        assert_eq!(view.at(1).unwrap().as_raw(), &[0xc1, 0xc0]);
+        assert_eq!(view.at(2).unwrap().at(1).unwrap().item_count().unwrap(), 1);
+        assert_eq!(view.at(3), Err(RLPError::RLPErrorIndexOutOfRange));
+        assert_eq!(view.at(0).unwrap().as_bytes(), Err(RLPError::RLPErrorType));
+    }
+
+    #[test]
+    fn test_long_item() {
+        let rlp = RLP::RLPList(vec![
+            RLP::RLPItem(vec![0xaa; 60]),
+            "cat".to_string().into()
+        ]);
+        let encoded = encode(&rlp);
+        let view = RlpView::new(&encoded);
+        assert_eq!(view.at(0).unwrap().as_bytes().unwrap(), &[0xaa; 60][..]);
+        assert_eq!(view.to_rlp().unwrap(), rlp);
+    }
+
+    #[test]
+    fn test_iter() {
+        let rlp = RLP::RLPList(vec![
+            "cat".to_string().into(),
+            "dog".to_string().into(),
+            RLP::RLPList(vec![])
+        ]);
+        let encoded = encode(&rlp);
+        let items: Vec<RlpView> = RlpView::new(&encoded).iter().unwrap().map(|item| item.unwrap()).collect();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].as_bytes().unwrap(), b"dog");
+        assert_eq!(items[2].is_list().unwrap(), true);
+    }
+
+    #[test]
+    fn test_malformed() {
+        // empty input
+        assert_eq!(RlpView::new(&[]).is_list(), Err(RLPError::RLPDecodingErrorMalformed));
+        // truncated item
+        assert_eq!(RlpView::new(&[0x83, 'd' as u8]).as_bytes(), Err(RLPError::RLPDecodingErrorMalformed));
+        // trailing bytes
+        assert_eq!(RlpView::new(&[0x0f, 0x0f]).as_bytes(), Err(RLPError::RLPDecodingErrorMalformed));
+        // list element overflows its parent
+        let encoded = vec![0xc2, 0x83, 'd' as u8];
+        assert_eq!(RlpView::new(&encoded).item_count(), Err(RLPError::RLPDecodingErrorMalformed));
+        // length of length larger than the buffer
+        assert_eq!(RlpView::new(&[0xbf, 0x01]).as_bytes(), Err(RLPError::RLPDecodingErrorMalformed));
+    }
+}
@@ -0,0 +1,380 @@
+//! `#[derive(RLPSerialize)]` for structs and enums.
+//!
+//! **Layout**
+//! - named and tuple structs are encoded as an [[RLPList]], one element per field in declaration order.
+//! - unit structs are encoded as an empty [[RLPList]].
+//! - enums are encoded as an [[RLPList]] whose first element is a one byte tag,
+//!   followed by the fields of the variant in declaration order.
+//!
+//! **Attributes**
+//! - `#[rlp(skip)]` on a field: the field is not encoded, and is restored with `Default::default()`.
+//! - `#[rlp(tag = N)]` on a variant: use `N` (0-255) as the tag. Without it the explicit
+//!   discriminant `Variant = N` is used if present, otherwise the variant index.
+//! - `#[rlp(check = "path")]` on the type: `path(&value) -> Result<(), RLPError>` is called on
+//!   every decoded value, and its error is returned instead of a value it refuses.
+//!
+//! ## Examples
+//! ```ignore
+//! #[macro_use]
+//! extern crate rlp_derive;
+//! extern crate rlp;
+//!
+//! #[derive(RLPSerialize)]
+//! struct Header {
+//!     parent: String,
+//!     #[rlp(skip)]
+//!     cached_size: Option<usize>,
+//! }
+//!
+//! #[derive(RLPSerialize)]
+//! #[rlp(check = "Range::check")]
+//! struct Range {
+//!     low: u32,
+//!     high: u32,
+//! }
+//!
+//! #[derive(RLPSerialize)]
+//! enum Status {
+//!     #[rlp(tag = 1)]
+//!     Failed,
+//!     #[rlp(tag = 2)]
+//!     Successful(String),
+//! }
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DataEnum, DataStruct, DeriveInput, Error, Expr, ExprPath, Fields, Ident, Index, Lit, Meta, NestedMeta, Result};
+
+use std::collections::HashMap;
+
+const ATTR_NAME: &'static str = "rlp";
+const ATTR_SKIP: &'static str = "skip";
+const ATTR_TAG: &'static str = "tag";
+const ATTR_CHECK: &'static str = "check";
+
+#[proc_macro_derive(RLPSerialize, attributes(rlp))]
+pub fn derive_rlp_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+/// A field taking part in the encoding.
+struct FieldInfo {
+    /// `self.member` accessor, either an ident or a tuple index
+    member: TokenStream2,
+    /// local binding used in match patterns
+    binding: Ident,
+    skip: bool
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+
+    let mut generics = input.generics.clone();
+    {
+        let type_params: Vec<Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+        let where_clause = generics.make_where_clause();
+        for param in type_params {
+            where_clause.predicates.push(parse_quote!(#param: ::rlp::RLPSerialize));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (serialize_body, decode_body) = match input.data {
+        Data::Struct(ref data) => expand_struct(name, data)?,
+        Data::Enum(ref data) => expand_enum(name, data)?,
+        Data::Union(_) => {
+            return Err(Error::new(input.span(), "RLPSerialize can not be derived for unions"));
+        }
+    };
+
+    let deserialize_body = match container_check(&input.attrs)? {
+        Some(check) => quote! {
+            let __value: Self = { #decode_body }?;
+            #check(&__value)?;
+            Ok(__value)
+        },
+        None => decode_body
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rlp::RLPSerialize for #name #ty_generics #where_clause {
+            fn serialize(&self) -> ::std::result::Result<::rlp::types::RLP, ::rlp::types::RLPError> {
+                #serialize_body
+            }
+
+            fn deserialize(__rlp: &::rlp::types::RLP) -> ::std::result::Result<Self, ::rlp::types::RLPError> {
+                #deserialize_body
+            }
+        }
+    })
+}
+
+fn expand_struct(name: &Ident, data: &DataStruct) -> Result<(TokenStream2, TokenStream2)> {
+    let fields = collect_fields(&data.fields)?;
+    let encoded: Vec<&FieldInfo> = fields.iter().filter(|f| !f.skip).collect();
+    let encoded_len = encoded.len();
+
+    let members = encoded.iter().map(|f| &f.member);
+    let serialize_body = quote! {
+        let mut __list: Vec<::rlp::types::RLP> = Vec::with_capacity(#encoded_len);
+        #( __list.push(::rlp::RLPSerialize::serialize(&self.#members)?); )*
+        Ok(::rlp::types::RLP::RLPList(__list))
+    };
+
+    let constructor = construct(quote!(#name), &data.fields, &fields, 0usize);
+    let deserialize_body = quote! {
+        match __rlp {
+            &::rlp::types::RLP::RLPList(ref __list) => {
+                if __list.len() != #encoded_len {
+                    return Err(::rlp::types::RLPError::RLPErrorWrongNumParams);
+                }
+                Ok(#constructor)
+            },
+            _ => Err(::rlp::types::RLPError::RLPErrorType)
+        }
+    };
+
+    Ok((serialize_body, deserialize_body))
+}
+
+fn expand_enum(name: &Ident, data: &DataEnum) -> Result<(TokenStream2, TokenStream2)> {
+    let mut used_tags: HashMap<u8, Ident> = HashMap::new();
+    let mut serialize_arms: Vec<TokenStream2> = vec![];
+    let mut deserialize_arms: Vec<TokenStream2> = vec![];
+
+    for (index, variant) in data.variants.iter().enumerate() {
+        let variant_name = &variant.ident;
+        let tag = variant_tag(index, variant.discriminant.as_ref().map(|d| &d.1), &variant.attrs)?;
+        if let Some(other) = used_tags.insert(tag, variant_name.clone()) {
+            return Err(Error::new(
+                variant.span(),
+                format!("tag {} is already used by variant `{}`", tag, other)
+            ));
+        }
+
+        let fields = collect_fields(&variant.fields)?;
+        let encoded: Vec<&FieldInfo> = fields.iter().filter(|f| !f.skip).collect();
+        let encoded_len = encoded.len();
+        let bindings = encoded.iter().map(|f| &f.binding);
+
+        let pattern = match variant.fields {
+            Fields::Named(_) => {
+                let names = encoded.iter().map(|f| &f.member);
+                let bindings = encoded.iter().map(|f| &f.binding);
+                quote!(&#name::#variant_name { #( #names: ref #bindings, )* .. })
+            },
+            Fields::Unnamed(_) => {
+                let slots = fields.iter().map(|f| {
+                    let binding = &f.binding;
+                    if f.skip { quote!(_) } else { quote!(ref #binding) }
+                });
+                quote!(&#name::#variant_name( #( #slots ),* ))
+            },
+            Fields::Unit => quote!(&#name::#variant_name)
+        };
+        serialize_arms.push(quote! {
+            #pattern => {
+                let mut __list: Vec<::rlp::types::RLP> = Vec::with_capacity(1 + #encoded_len);
+                __list.push(::rlp::types::RLP::RLPItem(vec![#tag]));
+                #( __list.push(::rlp::RLPSerialize::serialize(#bindings)?); )*
+                Ok(::rlp::types::RLP::RLPList(__list))
+            }
+        });
+
+        let constructor = construct(quote!(#name::#variant_name), &variant.fields, &fields, 1usize);
+        deserialize_arms.push(quote! {
+            #tag => {
+                if __list.len() != 1 + #encoded_len {
+                    return Err(::rlp::types::RLPError::RLPErrorWrongNumParams);
+                }
+                Ok(#constructor)
+            }
+        });
+    }
+
+    let serialize_body = if serialize_arms.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match self {
+                #( #serialize_arms, )*
+            }
+        }
+    };
+
+    let deserialize_body = quote! {
+        match __rlp {
+            &::rlp::types::RLP::RLPList(ref __list) => {
+                let __tag = match __list.first() {
+                    Some(&::rlp::types::RLP::RLPItem(ref tag)) if tag.len() == 1 => tag[0],
+                    Some(_) => { return Err(::rlp::types::RLPError::RLPErrorTagType); },
+                    None => { return Err(::rlp::types::RLPError::RLPErrorTagMissing); }
+                };
+                match __tag {
+                    #( #deserialize_arms, )*
+                    _ => Err(::rlp::types::RLPError::RLPErrorTagType)
+                }
+            },
+            _ => Err(::rlp::types::RLPError::RLPErrorType)
+        }
+    };
+
+    Ok((serialize_body, deserialize_body))
+}
+
+/// Build the value from `__list`, reading encoded fields starting at `offset`.
+fn construct(path: TokenStream2, shape: &Fields, fields: &Vec<FieldInfo>, offset: usize) -> TokenStream2 {
+    let mut position = offset;
+    let values: Vec<TokenStream2> = fields.iter().map(|f| {
+        if f.skip {
+            quote!(::std::default::Default::default())
+        } else {
+            let index = position;
+            position += 1;
+            quote!(::rlp::RLPSerialize::deserialize(&__list[#index])?)
+        }
+    }).collect();
+
+    match *shape {
+        Fields::Named(_) => {
+            let members = fields.iter().map(|f| &f.member);
+            quote!(#path { #( #members: #values, )* })
+        },
+        Fields::Unnamed(_) => quote!(#path( #( #values ),* )),
+        Fields::Unit => quote!(#path)
+    }
+}
+
+fn collect_fields(fields: &Fields) -> Result<Vec<FieldInfo>> {
+    let mut result: Vec<FieldInfo> = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let skip = field_skipped(&field.attrs)?;
+        let info = match field.ident {
+            Some(ref ident) => FieldInfo {
+                member: quote!(#ident),
+                binding: Ident::new(&format!("__field_{}", ident), Span::call_site()),
+                skip: skip
+            },
+            None => {
+                let index = Index::from(i);
+                FieldInfo {
+                    member: quote!(#index),
+                    binding: Ident::new(&format!("__field_{}", i), Span::call_site()),
+                    skip: skip
+                }
+            }
+        };
+        result.push(info);
+    }
+    Ok(result)
+}
+
+/// Parse `#[rlp(...)]` attributes into their nested items.
+fn rlp_attrs(attrs: &Vec<Attribute>) -> Result<Vec<NestedMeta>> {
+    let mut result: Vec<NestedMeta> = vec![];
+    for attr in attrs {
+        if !attr.path.is_ident(ATTR_NAME) {
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                result.extend(list.nested.into_iter());
+            },
+            other => {
+                return Err(Error::new(other.span(), "expected #[rlp(...)]"));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn field_skipped(attrs: &Vec<Attribute>) -> Result<bool> {
+    let mut skip = false;
+    for nested in rlp_attrs(attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == ATTR_SKIP => {
+                skip = true;
+            },
+            other => {
+                return Err(Error::new(other.span(), "unknown field attribute, expected `skip`"));
+            }
+        }
+    }
+    Ok(skip)
+}
+
+/// Path given by `#[rlp(check = "path")]` on the type, if any.
+fn container_check(attrs: &Vec<Attribute>) -> Result<Option<ExprPath>> {
+    let mut check: Option<ExprPath> = None;
+    for nested in rlp_attrs(attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(ref meta)) if meta.ident == ATTR_CHECK => {
+                match meta.lit {
+                    Lit::Str(ref lit) => {
+                        check = Some(lit.parse()?);
+                    },
+                    _ => {
+                        return Err(Error::new(meta.lit.span(), "expected a path, as in `check = \"Type::check\"`"));
+                    }
+                }
+            },
+            other => {
+                return Err(Error::new(other.span(), "unknown type attribute, expected `check = \"path\"`"));
+            }
+        }
+    }
+    Ok(check)
+}
+
+fn variant_tag(index: usize, discriminant: Option<&Expr>, attrs: &Vec<Attribute>) -> Result<u8> {
+    let mut tag: Option<u64> = None;
+    let mut span = Span::call_site();
+
+    for nested in rlp_attrs(attrs)? {
+        match nested {
+            NestedMeta::Meta(Meta::NameValue(ref meta)) if meta.ident == ATTR_TAG => {
+                match meta.lit {
+                    Lit::Int(ref lit) => {
+                        tag = Some(lit.value());
+                        span = lit.span();
+                    },
+                    _ => {
+                        return Err(Error::new(meta.lit.span(), "expected an integer tag"));
+                    }
+                }
+            },
+            other => {
+                return Err(Error::new(other.span(), "unknown variant attribute, expected `tag = N`"));
+            }
+        }
+    }
+
+    if tag.is_none() {
+        if let Some(&Expr::Lit(ref expr)) = discriminant {
+            if let Lit::Int(ref lit) = expr.lit {
+                tag = Some(lit.value());
+                span = lit.span();
+            }
+        }
+    }
+
+    let tag = tag.unwrap_or(index as u64);
+    if tag > u8::max_value() as u64 {
+        Err(Error::new(span, "tag must be in range 0-255"))
+    } else {
+        Ok(tag as u8)
+    }
+}
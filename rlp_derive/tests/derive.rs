@@ -0,0 +1,160 @@
+#[macro_use]
+extern crate rlp_derive;
+#[macro_use]
+extern crate rlp;
+
+use rlp::RLPSerialize;
+use rlp::types::*;
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+struct Named {
+    name: String,
+    raw: RLP,
+    #[rlp(skip)]
+    cache: Option<String>,
+}
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+struct Tuple(String, Named);
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+struct Unit;
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+enum Status {
+    Unknown,
+    #[rlp(tag = 7)]
+    Failed { reason: String, #[rlp(skip)] retries: Option<String> },
+    Successful(String, String),
+}
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+enum Kind {
+    Block = 42,
+    Transaction,
+}
+
+fn named() -> Named {
+    Named {
+        name: "dog".to_string(),
+        raw: rlp_list![],
+        cache: None,
+    }
+}
+
+#[test]
+fn test_named_struct() {
+    let value = Named { cache: Some("ignored".to_string()), ..named() };
+    let rlp = value.serialize().unwrap();
+    assert_eq!(rlp, rlp_list!["dog".to_string().into(), rlp_list![]]);
+    assert_eq!(Named::deserialize(&rlp).unwrap(), named());
+}
+
+#[test]
+fn test_tuple_struct() {
+    let value = Tuple("cat".to_string(), named());
+    let rlp = value.serialize().unwrap();
+    assert_eq!(rlp, rlp_list!["cat".to_string().into(), named().serialize().unwrap()]);
+    assert_eq!(Tuple::deserialize(&rlp).unwrap(), value);
+}
+
+#[test]
+fn test_unit_struct() {
+    let rlp = Unit.serialize().unwrap();
+    assert_eq!(rlp, rlp_list![]);
+    assert_eq!(Unit::deserialize(&rlp).unwrap(), Unit);
+}
+
+#[test]
+fn test_enum_tags() {
+    let cases = vec![
+        (Status::Unknown, rlp_list![RLP::RLPItem(vec![0])]),
+        (
+            Status::Failed { reason: "oops".to_string(), retries: None },
+            rlp_list![RLP::RLPItem(vec![7]), "oops".to_string().into()]
+        ),
+        (
+            Status::Successful("a".to_string(), "b".to_string()),
+            rlp_list![RLP::RLPItem(vec![2]), "a".to_string().into(), "b".to_string().into()]
+        ),
+    ];
+    for (value, expected) in cases {
+        let rlp = value.serialize().unwrap();
+        assert_eq!(rlp, expected);
+        assert_eq!(Status::deserialize(&rlp).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_enum_discriminants() {
+    let rlp = Kind::Block.serialize().unwrap();
+    assert_eq!(rlp, rlp_list![RLP::RLPItem(vec![42])]);
+    assert_eq!(Kind::deserialize(&rlp).unwrap(), Kind::Block);
+
+    let rlp = Kind::Transaction.serialize().unwrap();
+    assert_eq!(rlp, rlp_list![RLP::RLPItem(vec![1])]);
+    assert_eq!(Kind::deserialize(&rlp).unwrap(), Kind::Transaction);
+}
+
+#[test]
+fn test_wrong_num_params() {
+    let rlp = rlp_list!["dog".to_string().into()];
+    match Named::deserialize(&rlp) {
+        Err(RLPError::RLPErrorWrongNumParams) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_wrong_type() {
+    let rlp: RLP = "dog".to_string().into();
+    match Named::deserialize(&rlp) {
+        Err(RLPError::RLPErrorType) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[test]
+fn test_enum_tag_errors() {
+    match Status::deserialize(&rlp_list![]) {
+        Err(RLPError::RLPErrorTagMissing) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+    match Status::deserialize(&rlp_list![RLP::RLPItem(vec![3])]) {
+        Err(RLPError::RLPErrorTagType) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+    match Status::deserialize(&rlp_list![rlp_list![]]) {
+        Err(RLPError::RLPErrorTagType) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+}
+
+#[derive(Debug, PartialEq, RLPSerialize)]
+#[rlp(check = "Range::check")]
+struct Range {
+    low: u8,
+    high: u8,
+}
+
+impl Range {
+    fn check(&self) -> Result<(), RLPError> {
+        if self.low <= self.high { Ok(()) } else { Err(RLPError::RLPErrorCustom("empty range".to_string())) }
+    }
+}
+
+#[test]
+fn test_check() {
+    let value = Range { low: 1, high: 3 };
+    assert_eq!(Range::deserialize(&value.serialize().unwrap()).unwrap(), value);
+
+    let rlp = Range { low: 3, high: 1 }.serialize().unwrap();
+    match Range::deserialize(&rlp) {
+        Err(RLPError::RLPErrorCustom(ref reason)) if reason == "empty range" => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+    match Range::deserialize(&rlp_list![]) {
+        Err(RLPError::RLPErrorWrongNumParams) => {},
+        other => panic!("unexpected result {:?}", other)
+    }
+}
@@ -5,4 +5,3 @@ pub extern crate config;
 pub extern crate serde;
 
 pub mod log_writer;
-pub mod config_parser;
\ No newline at end of file